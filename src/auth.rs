@@ -0,0 +1,176 @@
+//! OAuth2 access tokens of `auth oauth2 { ... }`, fetched with the client credentials or
+//! password grant
+//!
+//! The server fetches them for the web UI through `/api/auth/token`, so the token endpoint needs
+//! no CORS headers, and the runner fetches them itself. Tokens are cached until shortly before
+//! they expire, then refreshed with the refresh token when the server gave one, or fetched again.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Tokens are fetched again this long before they expire, so a request does not race the expiry
+const EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Grant {
+    ClientCredentials,
+    Password,
+}
+
+/// The properties of an `auth oauth2` block, with the variables filled
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenRequest {
+    pub grant: Grant,
+    pub token_url: String,
+    pub client_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessToken {
+    pub access_token: String,
+    /// Seconds before the token is fetched again, never when the server gave no expiry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_in: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+pub struct TokenCache {
+    tokens: Mutex<HashMap<TokenRequest, CachedToken>>,
+}
+
+#[derive(Debug, Clone)]
+struct CachedToken {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_at: Option<Instant>,
+}
+
+impl CachedToken {
+    fn access_token(&self) -> AccessToken {
+        AccessToken {
+            access_token: self.access_token.clone(),
+            expires_in: self.expires_at.map(|at| {
+                at.saturating_duration_since(Instant::now() + EXPIRY_MARGIN)
+                    .as_secs()
+            }),
+        }
+    }
+
+    fn is_fresh(&self) -> bool {
+        self.expires_at
+            .is_none_or(|at| Instant::now() + EXPIRY_MARGIN < at)
+    }
+}
+
+impl TokenCache {
+    /// A token for `request`, from the cache while it is fresh
+    pub async fn token(
+        &self,
+        client: &reqwest::Client,
+        request: &TokenRequest,
+    ) -> Result<AccessToken, String> {
+        let cached = self.tokens.lock().unwrap().get(request).cloned();
+        let token = match cached {
+            Some(cached) if cached.is_fresh() => return Ok(cached.access_token()),
+            // A refresh token may be revoked or expired too, then the grant is used again
+            Some(CachedToken {
+                refresh_token: Some(refresh_token),
+                ..
+            }) => match fetch(client, request, Some(&refresh_token)).await {
+                Ok(token) => token,
+                Err(_) => fetch(client, request, None).await?,
+            },
+            _ => fetch(client, request, None).await?,
+        };
+
+        let access_token = token.access_token();
+        self.tokens.lock().unwrap().insert(request.clone(), token);
+        Ok(access_token)
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+    refresh_token: Option<String>,
+}
+
+/// Ask the token endpoint for a token with the grant of `request`, or with `refresh_token`
+async fn fetch(
+    client: &reqwest::Client,
+    request: &TokenRequest,
+    refresh_token: Option<&str>,
+) -> Result<CachedToken, String> {
+    let mut form = vec![("client_id", request.client_id.as_str())];
+    if let Some(ref secret) = request.client_secret {
+        form.push(("client_secret", secret));
+    }
+    match (refresh_token, request.grant) {
+        (Some(refresh_token), _) => {
+            form.push(("grant_type", "refresh_token"));
+            form.push(("refresh_token", refresh_token));
+        }
+        (None, Grant::ClientCredentials) => form.push(("grant_type", "client_credentials")),
+        (None, Grant::Password) => {
+            form.push(("grant_type", "password"));
+            form.push(("username", request.username.as_deref().unwrap_or_default()));
+            form.push(("password", request.password.as_deref().unwrap_or_default()));
+        }
+    }
+    if let Some(ref scope) = request.scope {
+        form.push(("scope", scope));
+    }
+
+    let response = client
+        .post(&request.token_url)
+        .header("Accept", "application/json")
+        .form(&form)
+        .send()
+        .await
+        .map_err(|e| format!("token request to {} failed: {}", request.token_url, e))?;
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+
+    if !status.is_success() {
+        // `error_description` and `error` are the standard fields of a failed token request
+        let reason = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|error| {
+                let text = error.get("error_description").or(error.get("error"))?;
+                text.as_str().map(str::to_string)
+            })
+            .unwrap_or(body);
+        return Err(format!(
+            "token request to {} failed with {}: {}",
+            request.token_url, status, reason
+        ));
+    }
+
+    let token: TokenResponse = serde_json::from_str(&body).map_err(|e| {
+        format!(
+            "token endpoint {} gave no access_token: {}",
+            request.token_url, e
+        )
+    })?;
+    Ok(CachedToken {
+        access_token: token.access_token,
+        refresh_token: token.refresh_token,
+        expires_at: token
+            .expires_in
+            .map(|seconds| Instant::now() + Duration::from_secs(seconds)),
+    })
+}
@@ -0,0 +1,753 @@
+//! Semantic checks over parsed .rqc files, used by `rqc check`
+
+use crate::parser::{
+    render_snippet, resolve_types_with, ApiBlock, ApiEndpoint, AuthBlock, AuthScheme,
+    CategoryBlock, EndpointType, EnumDefinition, Field, FieldType, MockValue, ParamLocation,
+    ParseError, ParseWarning, RqcConfig, SchemaBlock, Span, TypeDefinition, WarningKind,
+};
+use crate::dynamic;
+use crate::mock;
+use crate::runner::placeholders;
+use crate::validate;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub file: PathBuf,
+    pub span: Option<Span>,
+}
+
+impl Diagnostic {
+    /// Render the diagnostic, with a source snippet when the location and source are known
+    pub fn render(&self, source: Option<&str>) -> String {
+        let level = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        match (self.span, source) {
+            (Some(span), Some(source)) => render_snippet(
+                level,
+                &self.message,
+                &self.file.display().to_string(),
+                source,
+                span,
+            ),
+            _ => format!("{}: {}\n --> {}", level, self.message, self.file.display()),
+        }
+    }
+}
+
+/// A single parsed .rqc file, before imports are merged
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub content: String,
+    pub parsed: Result<RqcConfig, ParseError>,
+    /// Warnings are kept even when the file failed to parse
+    pub warnings: Vec<ParseWarning>,
+}
+
+/// Run all checks over the given files
+pub fn check(sources: &[SourceFile]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let parsed: Vec<(&PathBuf, &RqcConfig)> = sources
+        .iter()
+        .filter_map(|s| s.parsed.as_ref().ok().map(|config| (&s.path, config)))
+        .collect();
+
+    for source in sources {
+        for warning in &source.warnings {
+            let severity = match warning.kind {
+                WarningKind::Annotation => Severity::Warning,
+                WarningKind::Keyword | WarningKind::Type => Severity::Error,
+            };
+            diagnostics.push(Diagnostic {
+                severity,
+                message: warning.message.clone(),
+                file: source.path.clone(),
+                span: Some(warning.span),
+            });
+        }
+
+        match source.parsed {
+            Ok(_) => {}
+            Err(ParseError::Syntax(ref errors)) => {
+                for error in errors {
+                    diagnostics.push(Diagnostic {
+                        severity: Severity::Error,
+                        message: error.message.clone(),
+                        file: source.path.clone(),
+                        span: Some(error.span),
+                    });
+                }
+            }
+            Err(ParseError::IoError(ref e)) => diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                message: e.to_string(),
+                file: source.path.clone(),
+                span: None,
+            }),
+        }
+    }
+
+    let declared: HashSet<&str> = parsed
+        .iter()
+        .filter_map(|(_, config)| config.config.as_ref())
+        .flat_map(|c| {
+            let envs = c.envs.iter().flat_map(|e| e.variables.iter());
+            c.variables.iter().chain(envs).map(|v| v.name.as_str())
+        })
+        .collect();
+
+    check_duplicates(&parsed, &mut diagnostics);
+
+    let (types, enums) = project_types(&parsed);
+    for (path, config) in &parsed {
+        let mut resolved = (*config).clone();
+        resolve_file(path, &mut resolved, &types, &enums, &mut diagnostics);
+
+        let mut ctx = Context {
+            file: path,
+            declared: &declared,
+            diagnostics: &mut diagnostics,
+        };
+
+        for definition in &resolved.types {
+            ctx.check_schema(&definition.schema, &format!("type `{}`", definition.name));
+        }
+
+        if let Some(ref c) = config.config {
+            let envs = c.envs.iter().flat_map(|e| e.headers.iter());
+            for header in c.headers.iter().chain(envs) {
+                if let Some(ref value) = header.default_value {
+                    ctx.check_refs(
+                        value,
+                        &format!("default of header `{}`", header.name),
+                        Severity::Error,
+                    );
+                }
+            }
+        }
+
+        for (auth, location) in auth_blocks(config) {
+            ctx.check_auth(auth, &location);
+        }
+
+        for endpoint in resolved.to_endpoints() {
+            ctx.check_endpoint(&endpoint);
+        }
+    }
+
+    diagnostics
+}
+
+/// Types can be used in any file of the project, whichever file declares them
+fn project_types(parsed: &[(&PathBuf, &RqcConfig)]) -> (Vec<TypeDefinition>, Vec<EnumDefinition>) {
+    let types = parsed
+        .iter()
+        .flat_map(|(_, config)| config.types.iter().cloned())
+        .collect();
+    let enums = parsed
+        .iter()
+        .flat_map(|(_, config)| config.enums.iter().cloned())
+        .collect();
+    (types, enums)
+}
+
+/// Resolve the types of one file against those of the whole project. A problem inside a
+/// `type` is reported by the file declaring it, so it shows up once with its own source line
+fn resolve_file(
+    path: &Path,
+    config: &mut RqcConfig,
+    types: &[TypeDefinition],
+    enums: &[EnumDefinition],
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for error in resolve_types_with(config, types, enums) {
+        let declared_here = error
+            .in_type
+            .as_ref()
+            .is_none_or(|name| config.types.iter().any(|t| &t.name == name));
+        if declared_here {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                message: error.message,
+                file: path.to_path_buf(),
+                span: Some(error.span),
+            });
+        }
+    }
+}
+
+/// Problems with the `type` references of the project, one file at a time so they can be
+/// shown with their source lines
+pub fn resolve_diagnostics(sources: &[SourceFile]) -> Vec<Diagnostic> {
+    let parsed: Vec<(&PathBuf, &RqcConfig)> = sources
+        .iter()
+        .filter_map(|s| s.parsed.as_ref().ok().map(|config| (&s.path, config)))
+        .collect();
+
+    let mut diagnostics = Vec::new();
+    let (types, enums) = project_types(&parsed);
+    for (path, config) in &parsed {
+        resolve_file(path, &mut (*config).clone(), &types, &enums, &mut diagnostics);
+    }
+    diagnostics
+}
+
+/// Every `auth` block of a file, with where it is declared
+fn auth_blocks(config: &RqcConfig) -> Vec<(&AuthBlock, String)> {
+    fn from_apis<'a>(apis: &'a [ApiBlock], blocks: &mut Vec<(&'a AuthBlock, String)>) {
+        for api in apis {
+            for method in &api.methods {
+                if let Some(ref auth) = method.auth {
+                    blocks.push((auth, format!("`{} {}`", method.method, api.path)));
+                }
+            }
+        }
+    }
+
+    fn from_category<'a>(category: &'a CategoryBlock, blocks: &mut Vec<(&'a AuthBlock, String)>) {
+        if let Some(ref auth) = category.auth {
+            let name = category.name.as_deref().unwrap_or(&category.id);
+            blocks.push((auth, format!("category `{}`", name)));
+        }
+        from_apis(&category.apis, blocks);
+        for child in &category.children {
+            from_category(child, blocks);
+        }
+    }
+
+    let mut blocks = Vec::new();
+    if let Some(auth) = config.config.as_ref().and_then(|c| c.auth.as_ref()) {
+        blocks.push((auth, "the config block".to_string()));
+    }
+    from_apis(&config.apis, &mut blocks);
+    for category in &config.categories {
+        from_category(category, &mut blocks);
+    }
+    blocks
+}
+
+/// Report `METHOD path` pairs and types that are declared more than once, across all files
+fn check_duplicates(parsed: &[(&PathBuf, &RqcConfig)], diagnostics: &mut Vec<Diagnostic>) {
+    let mut seen: HashMap<(String, String), &PathBuf> = HashMap::new();
+    let mut seen_types: HashMap<&str, &PathBuf> = HashMap::new();
+
+    for (path, config) in parsed {
+        let names = config
+            .types
+            .iter()
+            .map(|t| &t.name)
+            .chain(config.enums.iter().map(|e| &e.name));
+        for name in names {
+            match seen_types.get(name.as_str()) {
+                Some(first) => diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    message: format!(
+                        "duplicate type `{}`, first declared in {}",
+                        name,
+                        first.display()
+                    ),
+                    file: (*path).clone(),
+                    span: None,
+                }),
+                None => {
+                    seen_types.insert(name, path);
+                }
+            }
+        }
+
+        for endpoint in config.to_endpoints() {
+            if endpoint.endpoint_type != EndpointType::Http {
+                continue;
+            }
+            let method = endpoint.method.clone().unwrap_or_default();
+            let key = (method.clone(), endpoint.path.clone());
+
+            match seen.get(&key) {
+                Some(first) => diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    message: format!(
+                        "duplicate endpoint `{} {}`, first defined in {}",
+                        method,
+                        endpoint.path,
+                        first.display()
+                    ),
+                    file: (*path).clone(),
+                    span: None,
+                }),
+                None => {
+                    seen.insert(key, path);
+                }
+            }
+        }
+    }
+}
+
+struct Context<'a> {
+    file: &'a PathBuf,
+    declared: &'a HashSet<&'a str>,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+impl Context<'_> {
+    fn report(&mut self, severity: Severity, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            message,
+            file: self.file.clone(),
+            span: None,
+        });
+    }
+
+    fn check_refs(&mut self, text: &str, location: &str, severity: Severity) {
+        for name in placeholders(text) {
+            if !self.declared.contains(name.as_str()) {
+                self.report(
+                    severity,
+                    format!("undeclared variable `{{{}}}` in {}", name, location),
+                );
+            }
+        }
+        self.check_builtins(text, location);
+    }
+
+    fn check_builtins(&mut self, text: &str, location: &str) {
+        for name in dynamic::references(text) {
+            if !dynamic::NAMES.contains(&name) {
+                self.report(
+                    Severity::Error,
+                    format!(
+                        "unknown built-in variable `{{${}}}` in {}, expected one of: {}",
+                        name,
+                        location,
+                        dynamic::NAMES.join(", ")
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_auth(&mut self, auth: &AuthBlock, location: &str) {
+        let scheme = auth.scheme.name();
+        let mut required = auth.scheme.required().to_vec();
+        match (auth.scheme, auth.get("grant")) {
+            (AuthScheme::OAuth2, Some("password")) => required.extend(["username", "password"]),
+            (AuthScheme::OAuth2, Some(grant)) if grant != "client_credentials" => self.report(
+                Severity::Error,
+                format!(
+                    "unknown grant `{}` in `auth oauth2` of {}, expected client_credentials or password",
+                    grant, location
+                ),
+            ),
+            _ => {}
+        }
+        for property in required {
+            if auth.get(property).is_none() {
+                self.report(
+                    Severity::Error,
+                    format!("`auth {}` of {} has no `{}`", scheme, location, property),
+                );
+            }
+        }
+
+        if let Some(sent_in) = auth.get("in") {
+            if !matches!(sent_in, "header" | "query" | "cookie") {
+                self.report(
+                    Severity::Error,
+                    format!(
+                        "`in {}` of `auth {}` in {}, expected header, query or cookie",
+                        sent_in, scheme, location
+                    ),
+                );
+            }
+        }
+
+        for (property, value) in &auth.properties {
+            let property_location = format!("`{}` of `auth {}` in {}", property, scheme, location);
+            self.check_refs(value, &property_location, Severity::Error);
+        }
+    }
+
+    fn check_endpoint(&mut self, endpoint: &ApiEndpoint) {
+        let label = match endpoint.method {
+            Some(ref method) => format!("{} {}", method, endpoint.path),
+            None => endpoint.path.clone(),
+        };
+
+        // `{id}` in a path that is not a variable is a path parameter, it should be described
+        // by a `@path` field, mock routing captures it either way so it is only a warning
+        let path_fields: Vec<&Field> = endpoint
+            .request
+            .iter()
+            .flat_map(|r| r.fields.iter())
+            .filter(|f| f.location == Some(ParamLocation::Path))
+            .collect();
+        self.check_builtins(&endpoint.path, &format!("path of `{}`", label));
+        let placeholders = placeholders(&endpoint.path);
+        for name in &placeholders {
+            if !self.declared.contains(name.as_str())
+                && !path_fields.iter().any(|f| f.name == *name)
+            {
+                self.report(
+                    Severity::Warning,
+                    format!(
+                        "`{{{}}}` in the path of `{}` is neither a variable nor a @path field",
+                        name, label
+                    ),
+                );
+            }
+        }
+        for field in path_fields {
+            if !placeholders.contains(&field.name) {
+                self.report(
+                    Severity::Warning,
+                    format!(
+                        "`{}` of `{}` is marked @path but the path has no `{{{}}}`",
+                        field.name, label, field.name
+                    ),
+                );
+            }
+        }
+
+        let mut schemas: Vec<(String, &SchemaBlock)> = Vec::new();
+        if let Some(ref s) = endpoint.request {
+            schemas.push(("request".to_string(), s));
+        }
+        if let Some(ref s) = endpoint.response {
+            schemas.push(("response".to_string(), s));
+        }
+        for response in &endpoint.responses {
+            schemas.push((format!("response {}", response.status), &response.schema));
+        }
+        if let Some(ref s) = endpoint.auth {
+            schemas.push(("auth".to_string(), s));
+        }
+        if let Some(ref s) = endpoint.connect_headers {
+            schemas.push(("headers".to_string(), s));
+        }
+        for event in endpoint.events.iter().flatten() {
+            if let Some(ref s) = event.request {
+                schemas.push((format!("event {} request", event.name), s));
+            }
+            if let Some(ref s) = event.response {
+                schemas.push((format!("event {} response", event.name), s));
+            }
+        }
+
+        for (name, schema) in schemas {
+            self.check_schema(schema, &format!("{} {}", label, name));
+        }
+
+        for event in endpoint.sse_events.iter().flatten() {
+            let schema = SchemaBlock {
+                fields: event.fields.clone(),
+                optional: false,
+                spreads: Vec::new(),
+            };
+            self.check_schema(&schema, &format!("{} event {}", label, event.name));
+        }
+    }
+
+    fn check_schema(&mut self, schema: &SchemaBlock, location: &str) {
+        for field in &schema.fields {
+            let field_location = format!("{}.{}", location, field.name);
+
+            if let Some(MockValue::String(ref s)) = field.example {
+                self.check_refs(
+                    s,
+                    &format!("example of `{}`", field_location),
+                    Severity::Error,
+                );
+            }
+
+            if let Some(MockValue::String(ref s)) = field.mock {
+                self.check_builtins(s, &format!("mock of `{}`", field_location));
+            }
+
+            if let Some(ref mock) = field.mock {
+                if !mock_matches(mock, field) {
+                    self.report(
+                        Severity::Error,
+                        format!(
+                            "@mock({}) does not match type {} of `{}`",
+                            mock.to_json(),
+                            field.field_type.keyword(),
+                            field_location
+                        ),
+                    );
+                }
+            }
+
+            if let Some(ref generator) = field.generator {
+                let generated = generator.value_type().or_else(|| {
+                    // `oneOf` gives values of the type of its arguments
+                    let first = generator.args.first()?;
+                    let all_match = generator
+                        .args
+                        .iter()
+                        .all(|a| std::mem::discriminant(a) == std::mem::discriminant(first));
+                    all_match.then_some(match first {
+                        MockValue::String(_) => FieldType::String,
+                        MockValue::Number(_) => {
+                            let integral = generator.args.iter().all(|a| {
+                                matches!(a, MockValue::Number(n) if n.fract() == 0.0)
+                            });
+                            if integral {
+                                FieldType::Integer
+                            } else {
+                                FieldType::Number
+                            }
+                        }
+                        MockValue::Boolean(_) => FieldType::Boolean,
+                    })
+                });
+                // On a typed array or map the generator fills the items
+                let expected = field.items.as_ref().map_or(&field.field_type, |i| &i.field_type);
+                let fits = |t: FieldType| match field.variants {
+                    Some(ref variants) => variants.iter().any(|v| generator_fits(&t, &v.field_type)),
+                    None => generator_fits(&t, expected),
+                };
+                if generated.is_some_and(|t| !fits(t)) {
+                    self.report(
+                        Severity::Error,
+                        format!(
+                            "@mock({}) does not match type {} of `{}`",
+                            generator,
+                            expected.keyword(),
+                            field_location
+                        ),
+                    );
+                }
+            }
+
+            if let Some(ref values) = field.enum_values {
+                let fixed = field.mock.iter().map(|v| ("mock", v));
+                let example = field.example.iter().map(|v| ("example", v));
+                for (annotation, value) in fixed.chain(example) {
+                    if !values.contains(value) {
+                        self.report(
+                            Severity::Error,
+                            format!(
+                                "@{}({}) is not one of the values of `{}`",
+                                annotation,
+                                value.to_json(),
+                                field_location
+                            ),
+                        );
+                    }
+                }
+            }
+
+            self.check_constraints(field, &field_location);
+
+            if let Some(ref variants) = field.variants {
+                self.check_variants(field, variants, &field_location);
+            }
+
+            // Fields of a referenced type are checked with the type
+            if let Some(nested) = field.nested.as_ref().filter(|_| field.type_ref.is_none()) {
+                self.check_schema(nested, &field_location);
+            }
+
+            // Objects inside typed arrays, `users [{ ... }]` is checked as `users[]`
+            let mut element = field.items.as_deref();
+            let mut element_location = field_location.clone();
+            while let Some(item) = element {
+                element_location.push_str("[]");
+                if let Some(nested) = item.nested.as_ref().filter(|_| item.type_ref.is_none()) {
+                    self.check_schema(nested, &element_location);
+                }
+                element = item.items.as_deref();
+            }
+        }
+    }
+
+    /// Inline objects of a union, and variants a discriminator cannot tell apart
+    fn check_variants(&mut self, field: &Field, variants: &[Field], location: &str) {
+        for variant in variants {
+            if let Some(nested) = variant.nested.as_ref().filter(|_| variant.type_ref.is_none()) {
+                self.check_schema(nested, location);
+            }
+        }
+
+        let Some(ref property) = field.discriminator else {
+            return;
+        };
+        let mut seen = Vec::new();
+        for (i, variant) in variants.iter().enumerate() {
+            match variant.discriminator_value(property) {
+                Some(value) if seen.contains(&value) => self.report(
+                    Severity::Error,
+                    format!(
+                        "variants of `{}` share the discriminator value {}",
+                        location,
+                        value.to_json()
+                    ),
+                ),
+                Some(value) => seen.push(value),
+                None => self.report(
+                    Severity::Warning,
+                    format!(
+                        "variant {} of `{}` has no value for discriminator `{}`, use a type or give `{}` a fixed @mock",
+                        i + 1,
+                        location,
+                        property,
+                        property
+                    ),
+                ),
+            }
+        }
+    }
+
+    /// Constraint annotations that cannot be met or do not apply to the field type, and fixed
+    /// values that break them
+    fn check_constraints(&mut self, field: &Field, location: &str) {
+        let constraints = &field.constraints;
+
+        if let Some(ref pattern) = constraints.pattern {
+            if let Err(e) = Regex::new(pattern) {
+                // The message of a syntax error draws the pattern over several lines
+                let message = e.to_string();
+                let reason = message.lines().last().unwrap_or_default();
+                self.report(
+                    Severity::Error,
+                    format!(
+                        "invalid @pattern of `{}`: {}",
+                        location,
+                        reason.trim_start_matches("error: ")
+                    ),
+                );
+            }
+        }
+        if let (Some(min), Some(max)) = (constraints.min, constraints.max) {
+            if min > max {
+                self.report(
+                    Severity::Error,
+                    format!("@min({}) is greater than @max({}) on `{}`", min, max, location),
+                );
+            } else if field.field_type == FieldType::Integer && min.ceil() > max.floor() {
+                self.report(
+                    Severity::Error,
+                    format!(
+                        "@min({}) and @max({}) of `{}` leave no integer in between",
+                        min, max, location
+                    ),
+                );
+            }
+        }
+        if let (Some(min), Some(max)) = (constraints.min_length, constraints.max_length) {
+            if min > max {
+                self.report(
+                    Severity::Error,
+                    format!("@length({}, {}) of `{}` can never be met", min, max, location),
+                );
+            }
+        }
+
+        let misplaced = match field.field_type {
+            FieldType::Number | FieldType::Integer => {
+                constraints.pattern.is_some() || constraints.format.is_some()
+            }
+            FieldType::String => constraints.min.is_some() || constraints.max.is_some(),
+            // The format of these is their type
+            FieldType::DateTime | FieldType::Date | FieldType::Uuid => {
+                constraints.min.is_some()
+                    || constraints.max.is_some()
+                    || constraints.format.is_some()
+            }
+            FieldType::Array | FieldType::Map => {
+                constraints.min.is_some()
+                    || constraints.max.is_some()
+                    || constraints.pattern.is_some()
+                    || constraints.format.is_some()
+            }
+            FieldType::Boolean | FieldType::Object | FieldType::File | FieldType::Union => {
+                !constraints.is_empty()
+            }
+        };
+        if misplaced {
+            self.report(
+                Severity::Warning,
+                format!(
+                    "some constraints of `{}` do not apply to type {}: @min/@max are for numbers, @pattern/@format for strings and @length for strings, arrays and maps",
+                    location,
+                    field.field_type.keyword()
+                ),
+            );
+        }
+
+        let fixed = field.mock.iter().map(|v| ("mock", v));
+        let example = field.example.iter().map(|v| ("example", v));
+        for (annotation, value) in fixed.chain(example) {
+            // `@mock("user-{id}")` is only known once the path is, `{$uuid}` once generated
+            if matches!(value, MockValue::String(s)
+                if !placeholders(s).is_empty() || !dynamic::references(s).is_empty())
+            {
+                continue;
+            }
+            for problem in validate::constraints(field, &value.to_json(), location) {
+                self.report(
+                    Severity::Error,
+                    format!("@{}({}): {}", annotation, value.to_json(), problem),
+                );
+            }
+        }
+    }
+}
+
+/// Whether values of a generator are valid for the field type, the mock server converts
+/// dates and rounds numbers where needed
+fn generator_fits(generated: &FieldType, expected: &FieldType) -> bool {
+    generated == expected
+        || matches!(
+            (generated, expected),
+            (FieldType::Integer, FieldType::Number)
+                | (FieldType::Number, FieldType::Integer)
+                | (FieldType::DateTime, FieldType::Date)
+                | (FieldType::Uuid | FieldType::DateTime, FieldType::String)
+        )
+}
+
+fn mock_matches(mock: &MockValue, field: &Field) -> bool {
+    if let Some(ref variants) = field.variants {
+        return variants.iter().any(|v| mock_matches(mock, v));
+    }
+    if let MockValue::String(s) = mock {
+        // `@mock("{id}")` is filled from the path and typed by the mock server, per request
+        if !placeholders(s).is_empty() {
+            return true;
+        }
+        // `@mock("{$uuid}")` is checked against a generated value, typed like the mock does
+        let generated = dynamic::expand(s);
+        if generated != *s {
+            return match mock::typed_value(&generated, &field.field_type) {
+                serde_json::Value::Number(_) => {
+                    matches!(field.field_type, FieldType::Number | FieldType::Integer)
+                }
+                _ => mock_matches(&MockValue::String(generated), field),
+            };
+        }
+    }
+    match (mock, &field.field_type) {
+        (MockValue::String(_), FieldType::String | FieldType::File) => true,
+        (MockValue::String(s), FieldType::DateTime) => validate::matches_format("date-time", s),
+        (MockValue::String(s), FieldType::Date) => validate::matches_format("date", s),
+        (MockValue::String(s), FieldType::Uuid) => validate::matches_format("uuid", s),
+        (MockValue::Number(_), FieldType::Number) => true,
+        (MockValue::Number(n), FieldType::Integer) => n.fract() == 0.0,
+        (MockValue::Boolean(_), FieldType::Boolean) => true,
+        _ => false,
+    }
+}
@@ -1,4 +1,5 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(name = "rqc")]
@@ -34,9 +35,152 @@ pub enum Commands {
         /// Enable watch mode - auto reload on .rqc file changes
         #[arg(short, long, default_value = "false")]
         watch: bool,
+
+        /// Environment to use, an `env` block of the config, defaults to the first one
+        #[arg(short, long)]
+        env: Option<String>,
+
+        /// Seed for generated mock data, the same request then always gets the same response
+        #[arg(long, value_name = "SEED")]
+        mock_seed: Option<u64>,
+
+        /// Randomly leave optional fields out of mock responses and set nullable ones to null
+        #[arg(long, default_value = "false")]
+        mock_omit_optional: bool,
+    },
+
+    /// Start a standalone mock server, mocks are served at the real API paths
+    Mock {
+        /// .rqc or OpenAPI (json/yaml) file to mock, defaults to .rqc
+        file: Option<PathBuf>,
+
+        /// Environment to use, an `env` block of the config, defaults to the first one
+        #[arg(short, long)]
+        env: Option<String>,
+
+        /// Port to listen on
+        #[arg(short, long, default_value = "4000")]
+        port: u16,
+
+        /// Host to bind to
+        #[arg(short = 'H', long, default_value = "127.0.0.1")]
+        host: String,
+
+        /// Seed for generated mock data, the same request then always gets the same response
+        #[arg(long, value_name = "SEED")]
+        mock_seed: Option<u64>,
+
+        /// Randomly leave optional fields out of mock responses and set nullable ones to null
+        #[arg(long, default_value = "false")]
+        mock_omit_optional: bool,
+
+        /// Forward requests that are not mocked to the first `baseUrl` of the config
+        #[arg(long, default_value = "false")]
+        forward: bool,
+
+        /// Forward requests that are not mocked to this URL
+        #[arg(long, value_name = "URL")]
+        upstream: Option<String>,
+    },
+
+    /// Execute an endpoint from the terminal and print the response
+    Run {
+        /// Endpoint to run: name, id (e.g. api-1) or "METHOD /path"
+        endpoint: String,
+
+        /// Override a variable, in key=value form (can be repeated)
+        #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+        vars: Vec<(String, String)>,
+
+        /// Base URL to use instead of the first one in config
+        #[arg(short, long)]
+        base_url: Option<String>,
+
+        /// Environment to use, an `env` block of the config, defaults to the first one
+        #[arg(short, long)]
+        env: Option<String>,
+    },
+
+    /// Run every endpoint with an `expect` block and report pass/fail
+    Test {
+        /// Report format
+        #[arg(short, long, value_enum, default_value_t = ReportFormat::Human)]
+        format: ReportFormat,
+
+        /// Override a variable, in key=value form (can be repeated)
+        #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+        vars: Vec<(String, String)>,
+
+        /// Base URL to use instead of the first one in config
+        #[arg(short, long)]
+        base_url: Option<String>,
+
+        /// Environment to use, an `env` block of the config, defaults to the first one
+        #[arg(short, long)]
+        env: Option<String>,
+
+        /// Also check response bodies against the `response` block of their status
+        #[arg(long)]
+        validate: bool,
+    },
+
+    /// Check .rqc files for mistakes, exits non-zero when errors are found
+    Check {
+        /// Treat warnings as errors
+        #[arg(long, default_value = "false")]
+        strict: bool,
+    },
+
+    /// Export the project to another format
+    Export {
+        #[command(subcommand)]
+        target: ExportTarget,
+    },
+
+    /// Format .rqc files in place
+    Fmt {
+        /// Files or directories to format, defaults to .rqc and the files it imports
+        files: Vec<PathBuf>,
+
+        /// Only report files that are not formatted, exits non-zero if there are any
+        #[arg(long, default_value = "false")]
+        check: bool,
     },
 }
 
+#[derive(Subcommand, Debug)]
+pub enum ExportTarget {
+    /// OpenAPI 3.1 document, printed to stdout unless --output is given
+    Openapi {
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Yaml)]
+        format: ExportFormat,
+
+        /// File to write the document to
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ExportFormat {
+    Yaml,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ReportFormat {
+    Human,
+    Json,
+    Junit,
+}
+
+fn parse_key_value(s: &str) -> Result<(String, String), String> {
+    s.split_once('=')
+        .map(|(k, v)| (k.trim().to_string(), v.to_string()))
+        .ok_or_else(|| format!("invalid KEY=VALUE: no `=` found in `{}`", s))
+}
+
 impl Cli {
     pub fn parse_args() -> Self {
         Cli::parse()
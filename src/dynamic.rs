@@ -0,0 +1,109 @@
+//! Built-in dynamic variables, `{$uuid}`, `{$timestamp}`, `{$randomInt}` and `{$isoDate}`
+//!
+//! They are filled after the declared variables, every reference gets a fresh value. The
+//! runner, the CORS proxy and the mock generator all go through `expand`, the mock draws from
+//! its seeded rng so `--mock-seed` still gives the same responses.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use rand::Rng;
+
+/// Names of the built-ins, referenced as `{$name}`
+pub const NAMES: &[&str] = &["uuid", "timestamp", "randomInt", "isoDate"];
+
+/// Openings and closings of a reference, the percent-encoded ones come from proxied URLs and
+/// form bodies
+const DELIMITERS: &[(&str, &str)] = &[("{$", "}"), ("%7B$", "%7D"), ("%7B%24", "%7D")];
+
+/// Value of the built-in `name`, drawn from `rng` at `now`
+pub fn value<R: Rng + ?Sized>(name: &str, rng: &mut R, now: DateTime<Utc>) -> Option<String> {
+    match name {
+        "uuid" => Some(uuid_v4(rng)),
+        "timestamp" => Some(now.timestamp().to_string()),
+        "randomInt" => Some(rng.gen_range(0..=1000).to_string()),
+        "isoDate" => Some(now.to_rfc3339_opts(SecondsFormat::Millis, true)),
+        _ => None,
+    }
+}
+
+/// Replace the built-in references of `text` with fresh values, unknown ones are left as is
+pub fn expand(text: &str) -> String {
+    expand_with(text, &mut rand::thread_rng(), Utc::now())
+}
+
+/// `expand` drawing from `rng` at `now`
+pub fn expand_with<R: Rng + ?Sized>(text: &str, rng: &mut R, now: DateTime<Utc>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find(['{', '%']) {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let replaced = reference(rest).and_then(|(name, len)| Some((value(name, rng, now)?, len)));
+        match replaced {
+            Some((value, len)) => {
+                result.push_str(&value);
+                rest = &rest[len..];
+            }
+            None => {
+                result.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Names of the `{$name}` references of `text`, known or not
+pub fn references(text: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(['{', '%']) {
+        rest = &rest[start..];
+        match reference(rest) {
+            Some((name, len)) => {
+                names.push(name);
+                rest = &rest[len..];
+            }
+            None => rest = &rest[1..],
+        }
+    }
+    names
+}
+
+/// The name of a reference at the start of `text` and the length of the reference
+fn reference(text: &str) -> Option<(&str, usize)> {
+    let starts_with = |text: &str, prefix: &str| {
+        text.get(..prefix.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+    };
+
+    DELIMITERS.iter().find_map(|(open, close)| {
+        if !starts_with(text, open) {
+            return None;
+        }
+        let after = &text[open.len()..];
+        let len = after
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(after.len());
+        (len > 0 && starts_with(&after[len..], close))
+            .then(|| (&after[..len], open.len() + len + close.len()))
+    })
+}
+
+/// A random version 4 UUID
+pub fn uuid_v4<R: Rng + ?Sized>(rng: &mut R) -> String {
+    let mut bytes: [u8; 16] = rng.gen();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
@@ -0,0 +1,434 @@
+//! Canonical formatter for .rqc files, used by `rqc fmt`
+
+use crate::parser::cst::{Block, CstToken, Element, Node, Statement, SyntaxTree};
+use crate::parser::{ParseError, Parser, TokenType};
+
+const INDENT: &str = "  ";
+
+/// Single field schema blocks and short enums stay on one line while the line fits in this width
+const MAX_WIDTH: usize = 80;
+
+/// What a block holds, this decides how it is laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    /// Fields of `request`, `response`, `type` and nested objects
+    Schema,
+    /// Values of an `enum`
+    Values,
+    /// `api`, method, `category`, `config` and the other blocks, always one statement a line
+    Other,
+}
+
+/// Annotations are written in this order, unknown ones keep their relative order after these
+const ANNOTATION_ORDER: &[&str] = &[
+    "params", "path", "header", "cookie", "min", "max", "length", "pattern", "format",
+    "discriminator", "example", "mock", "default",
+];
+
+#[derive(Debug)]
+pub enum FormatError {
+    /// The file has syntax errors, it is left untouched
+    Parse(ParseError),
+    /// The formatted output does not parse to the same definitions, this is a formatter bug
+    Changed,
+}
+
+impl FormatError {
+    pub fn render(&self, file: &str, source: &str) -> String {
+        match self {
+            FormatError::Parse(e) => e.render(file, source),
+            FormatError::Changed => format!(
+                "error: formatting would change the meaning of this file, it was left untouched\n --> {}",
+                file
+            ),
+        }
+    }
+}
+
+/// Format a .rqc source, comments and blank lines between statements are kept
+pub fn format_source(source: &str) -> Result<String, FormatError> {
+    let before = Parser::new(source).parse().map_err(FormatError::Parse)?;
+
+    let tree = SyntaxTree::parse(source);
+    if tree.to_source() != source {
+        return Err(FormatError::Changed);
+    }
+
+    let mut out = String::new();
+    write_items(&tree.items, 0, BlockKind::Other, &mut out);
+
+    // Formatting only moves whitespace, comments and annotations around, make sure of it
+    let after = Parser::new(&out).parse().map_err(|_| FormatError::Changed)?;
+    if serde_json::to_value(&before).ok() != serde_json::to_value(&after).ok() {
+        return Err(FormatError::Changed);
+    }
+
+    Ok(out)
+}
+
+fn write_items(items: &[Node], indent: usize, kind: BlockKind, out: &mut String) {
+    let items = split_items(items, kind);
+    // Top level statements spanning several lines are always separated by a blank line
+    let mut after_lines = false;
+
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 && (item.blank_line_before() || indent == 0 && after_lines) {
+            out.push('\n');
+        }
+
+        out.push_str(&INDENT.repeat(indent));
+        let start = out.len();
+        match item {
+            Node::Comment(comment) => write_comment(comment, indent, out),
+            Node::Statement(statement) => write_statement(statement, indent, kind, out),
+        }
+        after_lines = matches!(item, Node::Statement(_)) && out[start..].contains('\n');
+        out.push('\n');
+    }
+}
+
+/// Items with one statement a line. Several fields or statements written on one line, as in
+/// `{ id Number name String }`, are split, so a block is laid out the same however the
+/// source wrote it
+fn split_items(items: &[Node], kind: BlockKind) -> Vec<Node> {
+    let mut split = Vec::with_capacity(items.len());
+    for item in items {
+        let Node::Statement(statement) = item else {
+            split.push(item.clone());
+            continue;
+        };
+
+        let mut rest = statement.elements.as_slice();
+        while !rest.is_empty() {
+            let len = match kind {
+                BlockKind::Schema => field_len(rest),
+                BlockKind::Values => 1,
+                BlockKind::Other => statement_len(rest),
+            };
+            let (elements, tail) = rest.split_at(len.clamp(1, rest.len()));
+            rest = tail;
+            split.push(Node::Statement(Statement {
+                elements: elements.to_vec(),
+                trailing_comment: None,
+            }));
+        }
+        if let Some(Node::Statement(last)) = split.last_mut() {
+            last.trailing_comment = statement.trailing_comment.clone();
+        }
+    }
+    split
+}
+
+/// Number of elements of the first field, `name Type? @annotation(...)`. What does not
+/// look like a field stays with it
+fn field_len(elements: &[Element]) -> usize {
+    let token = |i: usize| match elements.get(i) {
+        Some(Element::Token(token)) => Some(token),
+        _ => None,
+    };
+    let is = |i: usize, kind: TokenType| token(i).is_some_and(|t| t.kind == kind);
+
+    // `...User` spreads the fields of a type
+    if token(0).is_some_and(|t| t.text.starts_with("...")) {
+        return 1;
+    }
+
+    // The type, `A | B` for unions, each part may be followed by `?` and `!`
+    let mut i = 1;
+    loop {
+        i = match elements.get(i) {
+            Some(Element::Block(_)) => i + 1,
+            Some(Element::Token(t)) if t.kind == TokenType::LBracket => {
+                closing(elements, i, TokenType::LBracket, TokenType::RBracket)
+            }
+            Some(Element::Token(t)) if t.text == "Enum" && is(i + 1, TokenType::LParen) => {
+                closing(elements, i + 1, TokenType::LParen, TokenType::RParen)
+            }
+            Some(Element::Token(t))
+                if t.text == "Map" && token(i + 1).is_some_and(|t| is_angle(t, '<')) =>
+            {
+                closing_angle(elements, i + 1)
+            }
+            Some(Element::Token(t)) if t.kind == TokenType::Ident => i + 1,
+            _ => return elements.len(),
+        };
+        while token(i).is_some_and(|t| {
+            t.kind == TokenType::Question || t.kind == TokenType::Operator && t.text == "!"
+        }) {
+            i += 1;
+        }
+        if !is(i, TokenType::Pipe) {
+            break;
+        }
+        i += 1;
+    }
+
+    while is(i, TokenType::At) {
+        i += 2;
+        if is(i, TokenType::LParen) {
+            i = closing(elements, i, TokenType::LParen, TokenType::RParen);
+        }
+    }
+    i.min(elements.len())
+}
+
+/// Index after the `close` token matching the `open` one at `start`
+fn closing(elements: &[Element], start: usize, open: TokenType, close: TokenType) -> usize {
+    let mut depth = 0;
+    for (i, element) in elements.iter().enumerate().skip(start) {
+        if let Element::Token(token) = element {
+            if token.kind == open {
+                depth += 1;
+            } else if token.kind == close {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+        }
+    }
+    elements.len()
+}
+
+/// Index after the `>` closing the `<` of `Map<K, V>` at `start`, `>>` closes two
+fn closing_angle(elements: &[Element], start: usize) -> usize {
+    let mut depth = 0;
+    for (i, element) in elements.iter().enumerate().skip(start) {
+        if let Element::Token(token) = element {
+            if token.kind == TokenType::Operator {
+                depth += token.text.matches('<').count();
+                depth = depth.saturating_sub(token.text.matches('>').count());
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+        }
+    }
+    elements.len()
+}
+
+/// Number of elements of the first statement of a line such as `get { ... } post { ... }`
+/// or `name "Login" mock true`: a word after a block, a string or a number starts the next one
+fn statement_len(elements: &[Element]) -> usize {
+    (1..elements.len())
+        .find(|&i| {
+            let Element::Token(ref token) = elements[i] else {
+                return false;
+            };
+            // `response 4XX` lexes as `4` and `XX`
+            let starts = token.kind == TokenType::Ident && !token.leading.is_empty();
+            starts
+                && match elements[i - 1] {
+                    Element::Block(_) => true,
+                    Element::Token(ref previous) => {
+                        matches!(previous.kind, TokenType::String | TokenType::Number)
+                    }
+                }
+        })
+        .unwrap_or(elements.len())
+}
+
+fn write_comment(comment: &CstToken, indent: usize, out: &mut String) {
+    let mut lines = comment.text.trim_end().lines();
+    if let Some(first) = lines.next() {
+        out.push_str(first.trim_end());
+    }
+
+    // Continuation lines of a `/** ... */` comment, aligned on the opening `*`
+    for line in lines {
+        let line = line.trim();
+        out.push('\n');
+        out.push_str(&INDENT.repeat(indent));
+        if line.starts_with('*') {
+            out.push(' ');
+        } else if !line.is_empty() {
+            out.push_str("   ");
+        }
+        out.push_str(line);
+    }
+}
+
+fn write_statement(statement: &Statement, indent: usize, kind: BlockKind, out: &mut String) {
+    let mut previous: Option<&CstToken> = None;
+    // Open `<` of `Map<K, V>` types, written without spaces around them
+    let mut angles = 0;
+
+    for element in sort_annotations(&statement.elements) {
+        match element {
+            Element::Token(token) => {
+                let after_angle = angles > 0 && previous.is_some_and(|p| is_angle(p, '<'));
+                if is_angle(token, '<') && previous.is_some_and(|p| p.text == "Map") {
+                    angles += token.text.len();
+                } else if angles > 0 && is_angle(token, '>') {
+                    angles = angles.saturating_sub(token.text.matches('>').count());
+                } else if !after_angle && previous.is_some_and(|p| needs_space(p, token)) {
+                    out.push(' ');
+                }
+                out.push_str(&token.text);
+                previous = Some(token);
+            }
+            Element::Block(block) => {
+                // `[{ ... }]` keeps the block against the bracket
+                if previous.is_some_and(|p| p.kind != TokenType::LBracket) {
+                    out.push(' ');
+                }
+                // Objects nested in a field hold fields too
+                let kind = match kind {
+                    BlockKind::Schema => BlockKind::Schema,
+                    _ => block_kind(statement),
+                };
+                write_block(block, indent, kind, out);
+                previous = block.close.as_ref().or(Some(&block.open));
+            }
+        }
+    }
+
+    if let Some(ref comment) = statement.trailing_comment {
+        out.push(' ');
+        out.push_str(comment.text.trim_end());
+    }
+}
+
+fn block_kind(statement: &Statement) -> BlockKind {
+    let keyword = match statement.elements.first() {
+        Some(Element::Token(first)) => first.text.as_str(),
+        _ => "",
+    };
+    match keyword {
+        "request" | "response" | "type" | "event" | "headers" => BlockKind::Schema,
+        "enum" => BlockKind::Values,
+        _ => BlockKind::Other,
+    }
+}
+
+fn write_block(block: &Block, indent: usize, kind: BlockKind, out: &mut String) {
+    if block.items.is_empty() && block.open_comment.is_none() {
+        out.push_str("{}");
+        return;
+    }
+
+    if let Some(inline) = inline_block(block, indent, kind) {
+        let line = &out[out.rfind('\n').map_or(0, |i| i + 1)..];
+        if line.chars().count() + inline.chars().count() <= MAX_WIDTH {
+            out.push_str(&inline);
+            return;
+        }
+    }
+
+    out.push('{');
+    if let Some(ref comment) = block.open_comment {
+        out.push(' ');
+        out.push_str(comment.text.trim_end());
+    }
+    out.push('\n');
+    write_items(&block.items, indent + 1, kind, out);
+    out.push_str(&INDENT.repeat(indent));
+    out.push('}');
+}
+
+/// The one-line form of a single field schema block such as `response { id Number }` or of
+/// a short enum, `None` when it holds several fields, comments or blocks, those are always
+/// written on several lines
+fn inline_block(block: &Block, indent: usize, kind: BlockKind) -> Option<String> {
+    if kind == BlockKind::Other || block.open_comment.is_some() {
+        return None;
+    }
+
+    let items = split_items(&block.items, kind);
+    if kind == BlockKind::Schema && items.len() > 1 {
+        return None;
+    }
+
+    let mut inline = String::from("{");
+    for item in &items {
+        let Node::Statement(statement) = item else {
+            return None;
+        };
+        let leaf = statement.trailing_comment.is_none()
+            && statement.elements.iter().all(|e| matches!(e, Element::Token(_)));
+        if !leaf {
+            return None;
+        }
+        inline.push(' ');
+        write_statement(statement, indent, kind, &mut inline);
+    }
+    inline.push_str(" }");
+    Some(inline)
+}
+
+/// An operator token made of `<` or `>` only, `Map<String, Number!>` lexes `!>` as one token
+fn is_angle(token: &CstToken, angle: char) -> bool {
+    let text = token.text.trim_start_matches('!');
+    token.kind == TokenType::Operator && !text.is_empty() && text.chars().all(|c| c == angle)
+}
+
+fn needs_space(previous: &CstToken, token: &CstToken) -> bool {
+    match (previous.kind, token.kind) {
+        (TokenType::At, _) | (TokenType::LParen, _) | (TokenType::LBracket, _) => false,
+        (_, TokenType::RBracket) => false,
+        (_, TokenType::LBracket) => true,
+        (_, TokenType::RParen) | (_, TokenType::Question) => false,
+        (TokenType::Ident, TokenType::LParen) => false,
+        (_, TokenType::At) | (TokenType::RBrace, _) => true,
+        (_, TokenType::Pipe) | (TokenType::Pipe, _) => true,
+        // `String!` marks the type as nullable
+        (_, TokenType::Operator) if token.text.starts_with('!') => false,
+        (_, TokenType::Operator) | (TokenType::Operator, _) => true,
+        _ if token.text == "," => false,
+        _ if previous.text == "," => true,
+        // Anything else keeps the spacing of the source, collapsed to a single space
+        _ => !token.leading.is_empty(),
+    }
+}
+
+/// Reorder the trailing `@name(...)` annotations of a statement following ANNOTATION_ORDER
+fn sort_annotations(elements: &[Element]) -> Vec<&Element> {
+    let unchanged = || elements.iter().collect();
+
+    let is_token = |e: &Element, kind: TokenType| matches!(e, Element::Token(t) if t.kind == kind);
+    let Some(start) = elements.iter().position(|e| is_token(e, TokenType::At)) else {
+        return unchanged();
+    };
+
+    // Split the tail into `@ name` groups, each optionally followed by `( ... )`
+    let mut groups: Vec<(usize, &[Element])> = Vec::new();
+    let mut i = start;
+    while i < elements.len() {
+        let name = match elements.get(i + 1) {
+            Some(Element::Token(t)) if is_token(&elements[i], TokenType::At) => t.text.as_str(),
+            _ => return unchanged(),
+        };
+        let mut end = i + 2;
+        if elements.get(end).is_some_and(|e| is_token(e, TokenType::LParen)) {
+            // Arguments can nest, as in `@mock(int(1, 100))`
+            let mut depth = 0;
+            while end < elements.len() {
+                if is_token(&elements[end], TokenType::LParen) {
+                    depth += 1;
+                } else if is_token(&elements[end], TokenType::RParen) {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                end += 1;
+            }
+            end += 1;
+        }
+        let end = end.min(elements.len());
+
+        let rank = ANNOTATION_ORDER
+            .iter()
+            .position(|n| *n == name)
+            .unwrap_or(ANNOTATION_ORDER.len());
+        groups.push((rank, &elements[i..end]));
+        i = end;
+    }
+
+    groups.sort_by_key(|(rank, _)| *rank);
+
+    elements[..start]
+        .iter()
+        .chain(groups.into_iter().flat_map(|(_, group)| group.iter()))
+        .collect()
+}
@@ -1,6 +1,14 @@
+mod auth;
+mod checker;
 mod cli;
+mod dynamic;
+mod formatter;
+mod mock;
 mod openapi;
 mod parser;
+mod runner;
+mod tester;
+mod validate;
 mod web;
 
 use cli::{Cli, Commands};
@@ -22,33 +30,76 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| "reqcraft=debug,tower_http=debug".into()),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
         .init();
 
     let cli = Cli::parse_args();
 
-    match cli.command {
-        Commands::Init => {
-            init_project()?;
+    let result = match cli.command {
+        Commands::Init => init_project(),
+        Commands::Dev {
+            port,
+            host,
+            mock,
+            cors,
+            watch,
+            env,
+            mock_seed,
+            mock_omit_optional,
+        } => {
+            let mock_options = mock::MockOptions {
+                seed: mock_seed,
+                omit_optional: mock_omit_optional,
+            };
+            dev_server(&host, port, mock, cors, watch, env, mock_options).await
         }
-        Commands::Dev { port, host, mock, cors, watch } => {
-            dev_server(&host, port, mock, cors, watch).await?;
+        Commands::Mock {
+            file,
+            env,
+            port,
+            host,
+            mock_seed,
+            mock_omit_optional,
+            forward,
+            upstream,
+        } => {
+            let mock_options = mock::MockOptions {
+                seed: mock_seed,
+                omit_optional: mock_omit_optional,
+            };
+            mock_server(file, env, &host, port, mock_options, forward, upstream).await
         }
+        Commands::Run {
+            endpoint,
+            vars,
+            base_url,
+            env,
+        } => run_endpoint(&endpoint, env, runner::RunOptions { base_url, vars }).await,
+        Commands::Test {
+            format,
+            vars,
+            base_url,
+            env,
+            validate,
+        } => run_tests(format, env, runner::RunOptions { base_url, vars }, validate).await,
+        Commands::Check { strict } => check_project(strict),
+        Commands::Export {
+            target: cli::ExportTarget::Openapi { format, output },
+        } => export_openapi(format, output),
+        Commands::Fmt { files, check } => format_files(files, check),
+    };
+
+    // Parse errors span several lines, print them as is instead of their Debug form
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
     }
 
     Ok(())
 }
 
-fn init_project() -> Result<(), Box<dyn std::error::Error>> {
-    let rqc_path = Path::new(RQC_FILE);
-
-    if rqc_path.exists() {
-        warn!("{} already exists", RQC_FILE);
-        return Ok(());
-    }
-
-    // Create with example content
-    let example = r#"// Import other .rqc files
+/// Example project written by `rqc init`
+const INIT_TEMPLATE: &str = r#"// Import other .rqc files
 // import "./user.rqc"
 
 config {
@@ -87,7 +138,16 @@ api /api/posts {
   }
 }
 "#;
-    fs::write(rqc_path, example)?;
+
+fn init_project() -> Result<(), Box<dyn std::error::Error>> {
+    let rqc_path = Path::new(RQC_FILE);
+
+    if rqc_path.exists() {
+        warn!("{} already exists", RQC_FILE);
+        return Ok(());
+    }
+
+    fs::write(rqc_path, INIT_TEMPLATE)?;
     info!("Created {} file with example config", RQC_FILE);
     info!("Run 'rqc dev' to start the development server");
 
@@ -100,6 +160,8 @@ async fn dev_server(
     cli_mock: bool,
     cli_cors: bool,
     watch: bool,
+    env: Option<String>,
+    mock_options: mock::MockOptions,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let rqc_path = Path::new(RQC_FILE);
 
@@ -110,7 +172,8 @@ async fn dev_server(
 
     // Parse .rqc file with imports
     let base_dir = rqc_path.parent().unwrap_or(Path::new("."));
-    let config = parse_with_imports(rqc_path, base_dir)?;
+    let mut config = parse_with_imports(rqc_path, base_dir)?;
+    select_env(&mut config, env)?;
 
     let endpoints = config.to_endpoints();
     info!("Loaded {} API endpoints from {}", endpoints.len(), RQC_FILE);
@@ -133,14 +196,352 @@ async fn dev_server(
     let (reload_tx, _) = tokio::sync::broadcast::channel::<()>(16);
 
     if watch {
-        info!("Watch mode enabled - watching for .rqc file changes");
+        info!("Watch mode enabled - watching for .rqc and .env file changes");
         let config_clone = Arc::clone(&config);
         let reload_tx_clone = reload_tx.clone();
         start_watcher(config_clone, reload_tx_clone)?;
     }
 
-    web::start_server(host, port, config, mock_mode, cors_mode, reload_tx).await?;
+    web::start_server(host, port, config, mock_mode, mock_options, cors_mode, reload_tx).await?;
+
+    Ok(())
+}
+
+async fn mock_server(
+    file: Option<PathBuf>,
+    env: Option<String>,
+    host: &str,
+    port: u16,
+    mock_options: mock::MockOptions,
+    forward: bool,
+    upstream: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = file.unwrap_or_else(|| PathBuf::from(RQC_FILE));
+    if !path.exists() {
+        return Err(format!("{} not found", path.display()).into());
+    }
+
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let mut config = match ext {
+        "json" | "yaml" | "yml" => {
+            let mut config = openapi::parse_openapi_file(&path)?;
+            load_secrets(&mut config, &path);
+            config
+        }
+        _ => {
+            let base_dir = path.parent().unwrap_or(Path::new("."));
+            parse_with_imports(&path, base_dir)?
+        }
+    };
+    select_env(&mut config, env)?;
+
+    let endpoints = config.to_endpoints();
+    info!("Loaded {} API endpoints from {}", endpoints.len(), path.display());
+    for endpoint in endpoints
+        .iter()
+        .filter(|e| e.endpoint_type == parser::EndpointType::Http)
+    {
+        info!(
+            "  {} {}",
+            endpoint.method.as_deref().unwrap_or("GET"),
+            endpoint.path
+        );
+    }
+
+    // Everything is mocked unless forwarding is asked for
+    let upstream = match upstream {
+        Some(url) => Some(url),
+        None if forward => Some(
+            web::default_base_url(&config).ok_or("--forward needs a baseUrl in the config")?,
+        ),
+        None => None,
+    };
+    if let Some(ref url) = upstream {
+        info!("Requests that are not mocked are forwarded to {}", url);
+    }
+
+    web::start_mock_server(host, port, Arc::new(RwLock::new(config)), mock_options, upstream)
+        .await
+}
+
+async fn run_endpoint(
+    query: &str,
+    env: Option<String>,
+    options: runner::RunOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = load_project()?;
+    select_env(&mut config, env)?;
+    let endpoints = config.to_endpoints();
+    let endpoint = runner::find_endpoint(&endpoints, query)?;
+
+    let request = runner::prepare_request(&config, endpoint, &options)?;
+    let client = reqwest::Client::builder()
+        .connect_timeout(std::time::Duration::from_secs(10))
+        .build()?;
+    let response = runner::send_request(&client, &request, &auth::TokenCache::default()).await?;
+    runner::print_response(&request, &response);
+
+    if !response.is_success() {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+async fn run_tests(
+    format: cli::ReportFormat,
+    env: Option<String>,
+    options: runner::RunOptions,
+    validate: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = load_project()?;
+    select_env(&mut config, env)?;
+    let results = tester::run_tests(&config, &options, validate).await?;
+
+    if results.is_empty() {
+        warn!("No endpoints with an expect block found");
+    }
+    print!("{}", tester::render(&results, format));
+
+    if results.iter().any(|r| !r.passed()) {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+fn check_project(strict: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let rqc_path = Path::new(RQC_FILE);
+
+    if !rqc_path.exists() {
+        return Err(format!("{} not found. Run 'rqc init' first.", RQC_FILE).into());
+    }
+
+    let mut sources = Vec::new();
+    let base_dir = rqc_path.parent().unwrap_or(Path::new("."));
+    collect_sources(rqc_path, base_dir, &mut HashSet::new(), &mut sources);
+
+    let diagnostics = checker::check(&sources);
+    for diagnostic in &diagnostics {
+        let source = sources
+            .iter()
+            .find(|s| s.path == diagnostic.file)
+            .map(|s| s.content.as_str());
+        eprintln!("{}\n", diagnostic.render(source));
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|d| strict || d.severity == checker::Severity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+    eprintln!(
+        "Checked {} file(s): {} error(s), {} warning(s)",
+        sources.len(),
+        errors,
+        warnings
+    );
+
+    if errors > 0 {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+fn export_openapi(
+    format: cli::ExportFormat,
+    output: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = load_project()?;
+
+    // Title the document after the project directory
+    let title = std::env::current_dir()
+        .ok()
+        .and_then(|dir| dir.file_name().map(|n| n.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "API".to_string());
+
+    let (document, notes) = openapi::export_openapi(&config, &title);
+    for note in notes {
+        warn!("{}", note);
+    }
+
+    let text = match format {
+        cli::ExportFormat::Yaml => serde_yaml::to_string(&document)?,
+        cli::ExportFormat::Json => serde_json::to_string_pretty(&document)? + "\n",
+    };
+
+    match output {
+        Some(path) => {
+            fs::write(&path, text)?;
+            info!("Exported OpenAPI document to {}", path.display());
+        }
+        None => print!("{}", text),
+    }
+
+    Ok(())
+}
+
+fn format_files(paths: Vec<PathBuf>, check: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut files = Vec::new();
+
+    if paths.is_empty() {
+        let rqc_path = Path::new(RQC_FILE);
+        if !rqc_path.exists() {
+            return Err(format!("{} not found. Run 'rqc init' first.", RQC_FILE).into());
+        }
+        let mut sources = Vec::new();
+        let base_dir = rqc_path.parent().unwrap_or(Path::new("."));
+        collect_sources(rqc_path, base_dir, &mut HashSet::new(), &mut sources);
+        files.extend(sources.into_iter().map(|s| s.path));
+    } else {
+        for path in paths {
+            if path.is_dir() {
+                find_rqc_files(&path, &mut files)?;
+            } else {
+                files.push(path);
+            }
+        }
+    }
+
+    let mut changed = 0;
+    let mut failed = 0;
+    for file in &files {
+        let source = fs::read_to_string(file)
+            .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+
+        let formatted = match formatter::format_source(&source) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                eprintln!("{}\n", e.render(&file.display().to_string(), &source));
+                failed += 1;
+                continue;
+            }
+        };
+
+        if formatted == source {
+            continue;
+        }
+        changed += 1;
+
+        if check {
+            println!("Not formatted: {}", file.display());
+        } else {
+            fs::write(file, formatted)?;
+            println!("Formatted {}", file.display());
+        }
+    }
+
+    let verb = if check { "need formatting" } else { "formatted" };
+    eprintln!(
+        "{} of {} file(s) {}, {} could not be parsed",
+        changed,
+        files.len(),
+        verb,
+        failed
+    );
+
+    if failed > 0 || (check && changed > 0) {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Find .rqc files below `dir`, skipping hidden directories and dependencies
+fn find_rqc_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(dir)?.filter_map(|e| e.ok()).collect();
+    entries.sort_by_key(|e| e.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        if path.is_dir() {
+            if !name.starts_with('.') && name != "node_modules" && name != "target" {
+                find_rqc_files(&path, files)?;
+            }
+        } else if name == RQC_FILE || path.extension().and_then(|e| e.to_str()) == Some("rqc") {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse every .rqc file reachable from `file_path` without merging them
+fn collect_sources(
+    file_path: &Path,
+    base_dir: &Path,
+    visited: &mut HashSet<PathBuf>,
+    sources: &mut Vec<checker::SourceFile>,
+) {
+    let canonical_path = file_path.canonicalize().unwrap_or(file_path.to_path_buf());
+    if !visited.insert(canonical_path) {
+        return;
+    }
+
+    let (content, parsed, warnings) = match fs::read_to_string(file_path) {
+        Ok(content) => {
+            let mut parser = Parser::new(&content);
+            let parsed = parser.parse();
+            let warnings = parser.warnings().to_vec();
+            (content, parsed, warnings)
+        }
+        Err(e) => (String::new(), Err(e.into()), Vec::new()),
+    };
+
+    let imports = parsed
+        .as_ref()
+        .map(|config| config.imports.clone())
+        .unwrap_or_default();
+
+    sources.push(checker::SourceFile {
+        path: file_path.to_path_buf(),
+        content,
+        parsed,
+        warnings,
+    });
+
+    for import_path in imports {
+        let import_file = resolve_import_path(&import_path, base_dir, file_path);
+        if import_file.extension().and_then(|e| e.to_str()) == Some("rqc") {
+            let import_base = import_file.parent().unwrap_or(base_dir);
+            collect_sources(&import_file, import_base, visited, sources);
+        }
+    }
+}
+
+fn load_project() -> Result<RqcConfig, Box<dyn std::error::Error>> {
+    let rqc_path = Path::new(RQC_FILE);
+
+    if !rqc_path.exists() {
+        return Err(format!("{} not found. Run 'rqc init' first.", RQC_FILE).into());
+    }
+
+    let base_dir = rqc_path.parent().unwrap_or(Path::new("."));
+    parse_with_imports(rqc_path, base_dir)
+}
+
+/// Make the `--env` environment the active one, say which one is used and which variable
+/// commands fail
+fn select_env(
+    config: &mut RqcConfig,
+    env: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(name) = env {
+        config.select_env(&name)?;
+    }
+    if let Some(env) = config.active_env() {
+        info!("Using env `{}`", env.name);
+    }
 
+    // Run the `from_command` variables once, so failing commands are reported up front
+    for variable in config.get_variables() {
+        if let Some(error) = variable.error {
+            warn!("Variable `{}`: {}", variable.name, error);
+        }
+    }
     Ok(())
 }
 
@@ -156,8 +557,10 @@ fn start_watcher(
                 event.kind,
                 EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_)
             ) && event.paths.iter().any(|p| {
+                let name = p.file_name().and_then(|n| n.to_str()).unwrap_or("");
                 p.extension().and_then(|e| e.to_str()) == Some("rqc")
-                    || p.file_name().and_then(|n| n.to_str()) == Some(".rqc")
+                    || name == ".rqc"
+                    || parser::secrets::ENV_FILES.contains(&name)
             });
 
             if is_rqc_change {
@@ -182,10 +585,13 @@ fn start_watcher(
             let base_dir = rqc_path.parent().unwrap_or(Path::new("."));
 
             match parse_with_imports(rqc_path, base_dir) {
-                Ok(new_config) => {
+                Ok(mut new_config) => {
                     let endpoints = new_config.to_endpoints();
                     info!("Reloaded {} API endpoints from {}", endpoints.len(), RQC_FILE);
                     let mut config_guard = config.write().unwrap();
+                    // Stay on the selected environment
+                    new_config.active_env = config_guard.active_env.take();
+                    new_config.commands = config_guard.commands.clone();
                     *config_guard = new_config;
                     drop(config_guard);
                     let _ = reload_tx.send(());
@@ -205,7 +611,49 @@ fn parse_with_imports(
     base_dir: &Path,
 ) -> Result<RqcConfig, Box<dyn std::error::Error>> {
     let mut visited = HashSet::new();
-    parse_file_recursive(file_path, base_dir, &mut visited)
+    let mut config = parse_file_recursive(file_path, base_dir, &mut visited)?;
+
+    // Types are resolved once every file is merged, so imported files can share them
+    if let Err(message) = parser::resolve_types(&mut config) {
+        return Err(render_resolve_errors(file_path, base_dir).unwrap_or(message).into());
+    }
+    load_secrets(&mut config, file_path);
+    Ok(config)
+}
+
+/// The type errors of the project with their source lines, `None` when they are not found
+/// in the .rqc files, e.g. when they come from an OpenAPI import
+fn render_resolve_errors(file_path: &Path, base_dir: &Path) -> Option<String> {
+    let mut sources = Vec::new();
+    collect_sources(file_path, base_dir, &mut HashSet::new(), &mut sources);
+
+    let rendered: Vec<String> = checker::resolve_diagnostics(&sources)
+        .iter()
+        .map(|diagnostic| {
+            let source = sources
+                .iter()
+                .find(|s| s.path == diagnostic.file)
+                .map(|s| s.content.as_str());
+            diagnostic.render(source)
+        })
+        .collect();
+    (!rendered.is_empty()).then(|| rendered.join("\n\n"))
+}
+
+/// Read the `.env` files next to the entry file, and warn about secrets that are not set
+fn load_secrets(config: &mut RqcConfig, entry: &Path) {
+    let dir = match entry.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    config.env_files = parser::secrets::load_env_files(dir);
+    for name in config.missing_env() {
+        warn!(
+            "Environment variable `{}` is not set, neither in the environment nor in {}",
+            name,
+            parser::secrets::ENV_FILES.join(" or ")
+        );
+    }
 }
 
 fn parse_file_recursive(
@@ -226,7 +674,7 @@ fn parse_file_recursive(
     let mut parser = Parser::new(&content);
     let mut config = parser
         .parse()
-        .map_err(|e| format!("Parse error in {:?}: {}", file_path, e))?;
+        .map_err(|e| e.render(&file_path.display().to_string(), &content))?;
 
     // Process imports
     let imports = std::mem::take(&mut config.imports);
@@ -264,7 +712,10 @@ fn parse_file_recursive(
             "rqc" => {
                 info!("Importing RQC file: {}", import_path);
                 let import_base = import_file.parent().unwrap_or(base_dir);
-                let imported_config = parse_file_recursive(&import_file, import_base, visited)?;
+                let imported_config = parse_file_recursive(&import_file, import_base, visited)
+                    .map_err(|e| {
+                        format!("{}\n  = note: imported from {}", e, file_path.display())
+                    })?;
                 merge_configs(&mut config, imported_config);
             }
             "json" | "yaml" | "yml" => match openapi::parse_openapi_file(&import_file) {
@@ -305,6 +756,34 @@ fn merge_configs(target: &mut RqcConfig, source: RqcConfig) {
         if t.base_urls.is_empty() {
             t.base_urls = s.base_urls;
         }
+        // Variables, headers and envs of imported files, such as OpenAPI server variables and
+        // security schemes, unless declared already
+        for variable in s.variables {
+            if !t.variables.iter().any(|v| v.name == variable.name) {
+                t.variables.push(variable);
+            }
+        }
+        for header in s.headers {
+            if !t.headers.iter().any(|h| h.name.eq_ignore_ascii_case(&header.name)) {
+                t.headers.push(header);
+            }
+        }
+        for env in s.envs {
+            if !t.envs.iter().any(|e| e.name == env.name) {
+                t.envs.push(env);
+            }
+        }
+        // The auth of an imported file, such as an OpenAPI security scheme, applies unless
+        // the importing config has its own
+        match (&t.auth, s.auth) {
+            (None, auth) => t.auth = auth,
+            (Some(current), Some(imported)) if *current != imported => warn!(
+                "`auth {}` of an imported config is ignored, the importing config uses `auth {}`",
+                imported.scheme.name(),
+                current.scheme.name()
+            ),
+            _ => {}
+        }
     }
 
     // Merge APIs
@@ -318,4 +797,19 @@ fn merge_configs(target: &mut RqcConfig, source: RqcConfig) {
 
     // Merge categories
     target.categories.extend(source.categories);
+
+    // Merge type and enum declarations
+    target.types.extend(source.types);
+    target.enums.extend(source.enums);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn init_template_is_formatted() {
+        let formatted = formatter::format_source(INIT_TEMPLATE).expect("template parses");
+        assert_eq!(formatted, INIT_TEMPLATE);
+    }
 }
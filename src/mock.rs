@@ -0,0 +1,660 @@
+//! Mock routing and response generation, shared by `rqc dev --mock` and `rqc mock`
+
+use crate::dynamic::{self, uuid_v4};
+use crate::parser::{
+    ApiEndpoint, Constraints, EndpointType, Field, FieldType, MockGenerator, MockValue,
+    SchemaBlock,
+};
+use crate::runner::interpolate;
+use crate::validate;
+use chrono::{DateTime, Duration, SecondsFormat, TimeZone, Utc};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde_json::{json, Value};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Values captured from the request path, `/users/{id}` and `/users/42` give `id = 42`.
+/// The rest of the path matched by a trailing `*` is captured as `wildcard`.
+pub type PathParams = HashMap<String, String>;
+
+/// Find the most specific HTTP endpoint matching a request
+pub fn find_route<'a>(
+    endpoints: &'a [ApiEndpoint],
+    method: &str,
+    path: &str,
+) -> Option<(&'a ApiEndpoint, PathParams)> {
+    endpoints
+        .iter()
+        .filter(|e| e.endpoint_type == EndpointType::Http)
+        .filter(|e| e.method.as_deref().is_some_and(|m| m.eq_ignore_ascii_case(method)))
+        .filter_map(|e| match_path(&e.path, path).map(|params| (e, params)))
+        .max_by(|(a, _), (b, _)| compare_specificity(&a.path, &b.path))
+}
+
+/// Match a route pattern against a request path.
+///
+/// `{name}` and `*` match exactly one segment, a `*` at the end of the pattern matches
+/// everything that is left.
+pub fn match_path(pattern: &str, path: &str) -> Option<PathParams> {
+    let pattern: Vec<&str> = segments(pattern).collect();
+    let path: Vec<&str> = segments(path).collect();
+    let mut params = PathParams::new();
+
+    for (i, segment) in pattern.iter().enumerate() {
+        if *segment == "*" && i == pattern.len() - 1 {
+            if i >= path.len() {
+                return None;
+            }
+            params.insert("wildcard".to_string(), path[i..].join("/"));
+            return Some(params);
+        }
+
+        let actual = path.get(i)?;
+        if let Some(name) = segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+            let value = urlencoding::decode(actual)
+                .map(|v| v.into_owned())
+                .unwrap_or_else(|_| actual.to_string());
+            params.insert(name.to_string(), value);
+        } else if *segment != "*" && segment != actual {
+            return None;
+        }
+    }
+
+    (pattern.len() == path.len()).then_some(params)
+}
+
+fn segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|s| !s.is_empty())
+}
+
+/// Literal segments beat `{param}` segments, which beat `*`. Compared from left to right,
+/// and the longer pattern wins when one is a prefix of the other.
+fn compare_specificity(a: &str, b: &str) -> Ordering {
+    let rank = |segment: &str| match segment {
+        "*" => 0,
+        s if s.starts_with('{') && s.ends_with('}') => 1,
+        _ => 2,
+    };
+    let a: Vec<u8> = segments(a).map(rank).collect();
+    let b: Vec<u8> = segments(b).map(rank).collect();
+    a.cmp(&b)
+}
+
+/// Whether an endpoint is answered with a mock rather than forwarded to the real server.
+/// `mock true|false` decides when set, otherwise endpoints with mock data in their response
+/// are mocked
+pub fn should_mock(endpoint: &ApiEndpoint) -> bool {
+    endpoint
+        .mock
+        .unwrap_or_else(|| endpoint.response.as_ref().is_some_and(has_mock_data))
+}
+
+/// `@mock` on any field, nested ones and array items included. Examples count too, OpenAPI
+/// imports only have those
+fn has_mock_data(schema: &SchemaBlock) -> bool {
+    schema.fields.iter().any(field_has_mock_data)
+}
+
+fn field_has_mock_data(field: &Field) -> bool {
+    field.mock.is_some()
+        || field.generator.is_some()
+        || field.example.is_some()
+        || field.nested.as_deref().is_some_and(has_mock_data)
+        || field.items.as_deref().is_some_and(field_has_mock_data)
+}
+
+/// How mock data is generated, from `--mock-seed` and `--mock-omit-optional`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MockOptions {
+    pub seed: Option<u64>,
+    pub omit_optional: bool,
+}
+
+/// State used while generating one response
+pub struct MockContext<'a> {
+    params: &'a PathParams,
+    rng: StdRng,
+    omit_optional: bool,
+    /// Dates are generated around this point in time
+    now: DateTime<Utc>,
+}
+
+impl<'a> MockContext<'a> {
+    /// `key` identifies the request (method and path). With a seed, the same request always
+    /// gets the same response and dates are relative to a fixed day instead of today.
+    pub fn new(options: &MockOptions, key: &str, params: &'a PathParams) -> Self {
+        let (rng, now) = match options.seed {
+            Some(seed) => (
+                StdRng::seed_from_u64(seed ^ fnv1a(key)),
+                Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+            ),
+            None => (StdRng::from_entropy(), Utc::now()),
+        };
+        MockContext {
+            params,
+            rng,
+            omit_optional: options.omit_optional,
+            now,
+        }
+    }
+}
+
+/// Stable across runs and platforms, unlike the std hasher
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Number of items generated for typed arrays such as `[String]`
+const ARRAY_LEN: std::ops::RangeInclusive<usize> = 1..=5;
+
+/// Generate a mock body for a schema, captured path parameters can be used as `{name}` in
+/// string mocks and fill top-level fields of the same name that have no mock
+pub fn generate(schema: &SchemaBlock, ctx: &mut MockContext) -> Value {
+    object(schema, ctx, true)
+}
+
+fn object(schema: &SchemaBlock, ctx: &mut MockContext, top_level: bool) -> Value {
+    let mut obj = serde_json::Map::new();
+
+    for field in &schema.fields {
+        let optional = field.optional || field.nested.as_ref().is_some_and(|n| n.optional);
+        if optional && ctx.omit_optional && ctx.rng.gen_bool(0.5) {
+            continue;
+        }
+        if field.nullable && ctx.omit_optional && ctx.rng.gen_bool(0.5) {
+            obj.insert(field.name.clone(), Value::Null);
+            continue;
+        }
+
+        let param = top_level.then(|| param_value(field, ctx.params)).flatten();
+        let value = param.unwrap_or_else(|| field_value(field, ctx));
+        obj.insert(field.name.clone(), value);
+    }
+
+    Value::Object(obj)
+}
+
+/// The captured path parameter of the same name for a plain field, when it fits the field type
+fn param_value(field: &Field, params: &PathParams) -> Option<Value> {
+    let plain = field.mock.is_none()
+        && field.example.is_none()
+        && field.generator.is_none()
+        && field.variants.is_none()
+        && field.items.is_none()
+        && field.nested.is_none();
+    if !plain {
+        return None;
+    }
+
+    let text = params.get(&field.name)?;
+    let value = typed_value(text, &field.field_type);
+    let fits = match field.field_type {
+        FieldType::String | FieldType::File => true,
+        FieldType::Number => value.is_number(),
+        FieldType::Integer => value.is_i64(),
+        FieldType::Boolean => value.is_boolean(),
+        FieldType::Uuid => validate::matches_format("uuid", text),
+        FieldType::Date => validate::matches_format("date", text),
+        FieldType::DateTime => validate::matches_format("date-time", text),
+        FieldType::Array | FieldType::Object | FieldType::Map | FieldType::Union => false,
+    };
+    fits.then_some(value)
+}
+
+fn field_value(field: &Field, ctx: &mut MockContext) -> Value {
+    // Fall back to the example, OpenAPI imports only have those
+    let example = field.example.as_ref().filter(|_| field.generator.is_none());
+    if let Some(mock) = field.mock.as_ref().or(example) {
+        return match mock.to_json() {
+            // `{$uuid}` and the like follow the seed, drawn before the path parameters are filled
+            Value::String(s) if !ctx.params.is_empty() || !dynamic::references(&s).is_empty() => {
+                let s = dynamic::expand_with(&s, &mut ctx.rng, ctx.now);
+                typed_value(&interpolate(&s, ctx.params), &field.field_type)
+            }
+            value => value,
+        };
+    }
+
+    if let Some(ref variants) = field.variants {
+        return union_value(field, variants, ctx);
+    }
+
+    if let Some(ref items) = field.items {
+        // A generator on the array field, as in `tags [String] @mock(faker.word)`, fills
+        // every item, or every value of a map
+        let len = array_len(&field.constraints, ctx);
+        let value = |ctx: &mut MockContext| match field.generator {
+            Some(ref generator) => fit_type(generate_value(generator, ctx), &items.field_type),
+            None => field_value(items, ctx),
+        };
+        if field.field_type == FieldType::Map {
+            let key_type = field.key_type.as_ref().unwrap_or(&FieldType::String);
+            return Value::Object(
+                (0..len)
+                    .map(|i| (map_key(key_type, i, ctx), value(ctx)))
+                    .collect(),
+            );
+        }
+        return Value::Array((0..len).map(|_| value(ctx)).collect());
+    }
+
+    if let Some(ref generator) = field.generator {
+        fit_type(generate_value(generator, ctx), &field.field_type)
+    } else if let Some(ref nested) = field.nested {
+        object(nested, ctx, false)
+    } else if let Some(value) = field.enum_values.as_ref().and_then(|v| v.choose(&mut ctx.rng)) {
+        value.to_json()
+    } else if let Some(value) = constrained_value(field, ctx) {
+        value
+    } else if let Some(generator) = infer_generator(&field.name, &field.field_type) {
+        let value = generate_value(&generator, ctx);
+        fit_length(value, &field.constraints, ctx)
+    } else {
+        // Generate default mock based on type
+        match field.field_type {
+            FieldType::String => fit_length(
+                Value::String(format!("mock_{}", field.name)),
+                &field.constraints,
+                ctx,
+            ),
+            FieldType::Number | FieldType::Integer => json!(ctx.rng.gen_range(0..=1000)),
+            FieldType::Boolean => Value::Bool(ctx.rng.gen_bool(0.5)),
+            FieldType::DateTime | FieldType::Date => {
+                let generator = MockGenerator {
+                    name: "date.recent".to_string(),
+                    args: Vec::new(),
+                };
+                fit_type(generate_value(&generator, ctx), &field.field_type)
+            }
+            FieldType::Uuid => json!(uuid_v4(&mut ctx.rng)),
+            FieldType::File => json!(format!("{}.bin", field.name)),
+            FieldType::Array => Value::Array(vec![]),
+            FieldType::Object | FieldType::Map | FieldType::Union => {
+                Value::Object(serde_json::Map::new())
+            }
+        }
+    }
+}
+
+/// A value of a random variant of a union, objects get the discriminator of their variant
+fn union_value(field: &Field, variants: &[Field], ctx: &mut MockContext) -> Value {
+    let Some(variant) = variants.choose(&mut ctx.rng) else {
+        return Value::Null;
+    };
+    let mut value = match field.generator {
+        Some(ref generator) => fit_type(generate_value(generator, ctx), &variant.field_type),
+        None => field_value(variant, ctx),
+    };
+    let tag = field
+        .discriminator
+        .as_ref()
+        .and_then(|property| Some((property, variant.discriminator_value(property)?)));
+    if let (Value::Object(ref mut object), Some((property, tag))) = (&mut value, tag) {
+        object.insert(property.clone(), tag.to_json());
+    }
+    value
+}
+
+/// Adapt a generated value to the field type: `date.recent` on a `Date` gives the day only
+/// and `float` on an `Integer` is rounded
+fn fit_type(value: Value, field_type: &FieldType) -> Value {
+    match (field_type, value) {
+        // `2024-01-01T08:30:00Z` -> `2024-01-01`
+        (FieldType::Date, Value::String(s)) if s.len() > 10 && s.is_char_boundary(10) => {
+            json!(s[..10])
+        }
+        (FieldType::Integer, Value::Number(n)) if !n.is_i64() && !n.is_u64() => {
+            json!(n.as_f64().unwrap_or_default().round() as i64)
+        }
+        (_, value) => value,
+    }
+}
+
+/// Key of the `i`th generated entry of a map
+fn map_key(key_type: &FieldType, i: usize, ctx: &mut MockContext) -> String {
+    match key_type {
+        FieldType::Integer => (i + 1).to_string(),
+        FieldType::Uuid => uuid_v4(&mut ctx.rng),
+        FieldType::Date => (ctx.now - Duration::days(i as i64))
+            .format("%Y-%m-%d")
+            .to_string(),
+        _ => format!("{}{}", pick(&mut ctx.rng, WORDS), i + 1),
+    }
+}
+
+/// Number of items of a typed array, within `@length` when it has one
+fn array_len(constraints: &Constraints, ctx: &mut MockContext) -> usize {
+    let min = constraints.min_length.map_or(*ARRAY_LEN.start(), |n| n as usize);
+    let max = constraints
+        .max_length
+        .map_or(min.max(*ARRAY_LEN.end()), |n| n as usize);
+    ctx.rng.gen_range(min.min(max)..=max)
+}
+
+/// A value that follows `@min`/`@max`, `@pattern` or `@format`, when the field has them
+fn constrained_value(field: &Field, ctx: &mut MockContext) -> Option<Value> {
+    let constraints = &field.constraints;
+    match field.field_type {
+        FieldType::Number | FieldType::Integer
+            if constraints.min.is_some() || constraints.max.is_some() =>
+        {
+            let (min, max) = match (constraints.min, constraints.max) {
+                (Some(min), Some(max)) => (min, max.max(min)),
+                (Some(min), None) => (min, min + 1000.0),
+                (None, Some(max)) if max >= 0.0 => (0.0, max),
+                (None, Some(max)) => (max - 1000.0, max),
+                (None, None) => unreachable!(),
+            };
+            let integral = min.fract() == 0.0 && max.fract() == 0.0;
+            let name = if field.field_type == FieldType::Integer || integral {
+                "int"
+            } else {
+                "float"
+            };
+            let generator = MockGenerator {
+                name: name.to_string(),
+                args: vec![MockValue::Number(min), MockValue::Number(max)],
+            };
+            Some(generate_value(&generator, ctx))
+        }
+        FieldType::String => {
+            if let Some(ref pattern) = constraints.pattern {
+                if let Some(value) = pattern_value(pattern, constraints, ctx) {
+                    return Some(Value::String(value));
+                }
+            }
+            let generator = match constraints.format.as_deref()? {
+                "email" => "faker.email",
+                "uuid" => "uuid",
+                "date-time" | "date" => "date.recent",
+                "uri" => "faker.url",
+                "ipv4" => "faker.ip",
+                "ipv6" => {
+                    let groups: Vec<String> = (0..8)
+                        .map(|_| format!("{:x}", ctx.rng.gen::<u16>()))
+                        .collect();
+                    return Some(json!(groups.join(":")));
+                }
+                _ => return None,
+            };
+            let generator = MockGenerator {
+                name: generator.to_string(),
+                args: Vec::new(),
+            };
+            let value = generate_value(&generator, ctx);
+            match (constraints.format.as_deref(), value) {
+                // `2024-01-01T08:30:00Z` -> `2024-01-01`
+                (Some("date"), Value::String(s)) => Some(json!(s[..10])),
+                (_, value) => Some(value),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// A string matching `@pattern`, tried a few times to also fit `@length`
+fn pattern_value(pattern: &str, constraints: &Constraints, ctx: &mut MockContext) -> Option<String> {
+    // Anchors are implied, the generator does not support them
+    let pattern = pattern.strip_prefix('^').unwrap_or(pattern);
+    let pattern = match pattern.strip_suffix('$') {
+        Some(p) if !p.ends_with('\\') => p,
+        _ => pattern,
+    };
+    let generator = rand_regex::Regex::compile(pattern, 16).ok()?;
+
+    let fits = |s: &String| {
+        let len = s.chars().count() as u64;
+        constraints.min_length.is_none_or(|min| len >= min)
+            && constraints.max_length.is_none_or(|max| len <= max)
+    };
+    let mut value: String = ctx.rng.sample(&generator);
+    for _ in 0..20 {
+        if fits(&value) {
+            break;
+        }
+        value = ctx.rng.sample(&generator);
+    }
+    Some(value)
+}
+
+/// Pad or cut a generated string to fit `@length`
+fn fit_length(value: Value, constraints: &Constraints, ctx: &mut MockContext) -> Value {
+    let Value::String(mut s) = value else {
+        return value;
+    };
+    if let Some(max) = constraints.max_length {
+        s = s.chars().take(max as usize).collect();
+    }
+    if let Some(min) = constraints.min_length {
+        while (s.chars().count() as u64) < min {
+            s.push(ctx.rng.gen_range(b'a'..=b'z') as char);
+        }
+    }
+    Value::String(s)
+}
+
+/// Pick a generator from the field name, e.g. `email`, `avatar` or `createdAt`
+fn infer_generator(name: &str, field_type: &FieldType) -> Option<MockGenerator> {
+    let lower = name.to_lowercase().replace(['_', '-'], "");
+    let is_id = lower == "id" || name.ends_with("Id") || name.ends_with("_id");
+    let is_time = lower.ends_with("date")
+        || lower.ends_with("time")
+        || name.ends_with("At")
+        || name.ends_with("_at");
+    let has = |words: &[&str]| words.iter().any(|w| lower.contains(w));
+
+    let (generator, args): (&str, Vec<f64>) = match field_type {
+        FieldType::String => {
+            let generator = if has(&["email"]) {
+                "faker.email"
+            } else if is_id || has(&["uuid"]) {
+                "uuid"
+            } else if has(&["avatar", "image", "photo", "picture"]) {
+                "faker.avatar"
+            } else if has(&["url", "website", "link", "homepage"]) {
+                "faker.url"
+            } else if has(&["phone", "mobile"]) {
+                "faker.phone"
+            } else if has(&["username", "login", "nickname"]) {
+                "faker.username"
+            } else if has(&["firstname"]) {
+                "faker.firstName"
+            } else if has(&["lastname", "surname"]) {
+                "faker.lastName"
+            } else if has(&["company"]) {
+                "faker.company"
+            } else if lower == "name" || has(&["fullname", "author", "owner"]) {
+                "faker.name"
+            } else if has(&["city"]) {
+                "faker.city"
+            } else if has(&["country"]) {
+                "faker.country"
+            } else if has(&["address", "street"]) {
+                "faker.address"
+            } else if lower == "ip" || has(&["ipaddress"]) {
+                "faker.ip"
+            } else if has(&["birth"]) {
+                "date.past"
+            } else if is_time {
+                "date.recent"
+            } else if has(&["title", "description", "summary", "content", "bio", "comment"]) {
+                "faker.sentence"
+            } else {
+                return None;
+            };
+            (generator, vec![])
+        }
+        FieldType::Number | FieldType::Integer if is_id => ("int", vec![1.0, 10000.0]),
+        FieldType::Number | FieldType::Integer if lower == "age" => ("int", vec![18.0, 80.0]),
+        FieldType::Number if has(&["price", "amount", "cost", "balance"]) => {
+            ("float", vec![1.0, 1000.0])
+        }
+        FieldType::Integer if has(&["price", "amount", "cost", "balance"]) => {
+            ("int", vec![1.0, 1000.0])
+        }
+        FieldType::Number | FieldType::Integer if has(&["count", "total", "quantity"]) => {
+            ("int", vec![0.0, 100.0])
+        }
+        _ => return None,
+    };
+
+    Some(MockGenerator {
+        name: generator.to_string(),
+        args: args.into_iter().map(MockValue::Number).collect(),
+    })
+}
+
+const FIRST_NAMES: &[&str] = &[
+    "James", "Mary", "Robert", "Patricia", "John", "Jennifer", "Michael", "Linda", "David",
+    "Elizabeth", "William", "Barbara", "Richard", "Susan", "Joseph", "Jessica", "Thomas", "Sarah",
+    "Daniel", "Karen",
+];
+
+const LAST_NAMES: &[&str] = &[
+    "Smith", "Johnson", "Williams", "Brown", "Jones", "Garcia", "Miller", "Davis", "Rodriguez",
+    "Martinez", "Hernandez", "Lopez", "Wilson", "Anderson", "Thomas", "Taylor", "Moore",
+    "Jackson", "Martin", "Lee",
+];
+
+const COMPANIES: &[&str] = &[
+    "Acme Corp", "Globex", "Initech", "Umbrella", "Hooli", "Stark Industries", "Wayne Enterprises",
+    "Wonka Industries", "Cyberdyne Systems", "Soylent",
+];
+
+const CITIES: &[&str] = &[
+    "New York", "London", "Paris", "Tokyo", "Berlin", "Sydney", "Toronto", "Shanghai", "Madrid",
+    "Amsterdam",
+];
+
+const COUNTRIES: &[&str] = &[
+    "United States", "United Kingdom", "France", "Japan", "Germany", "Australia", "Canada",
+    "China", "Spain", "Netherlands",
+];
+
+const STREETS: &[&str] = &[
+    "Main St", "Oak Ave", "Maple Rd", "Cedar Ln", "Park Blvd", "Pine St", "Elm St", "Lake Dr",
+];
+
+const WORDS: &[&str] = &[
+    "lorem", "ipsum", "dolor", "sit", "amet", "consectetur", "adipiscing", "elit", "sed", "do",
+    "eiusmod", "tempor", "incididunt", "ut", "labore", "et", "dolore", "magna", "aliqua", "enim",
+];
+
+fn pick<'a>(rng: &mut StdRng, items: &[&'a str]) -> &'a str {
+    items.choose(rng).copied().unwrap_or_default()
+}
+
+/// Run a generator, unknown generators give `null`
+fn generate_value(generator: &MockGenerator, ctx: &mut MockContext) -> Value {
+    let rng = &mut ctx.rng;
+    let number = |i: usize| match generator.args.get(i) {
+        Some(MockValue::Number(n)) => Some(*n),
+        _ => None,
+    };
+    let range = |default: (f64, f64)| {
+        let (a, b) = (number(0).unwrap_or(default.0), number(1).unwrap_or(default.1));
+        if a <= b {
+            (a, b)
+        } else {
+            (b, a)
+        }
+    };
+
+    match generator.name.as_str() {
+        "uuid" => json!(uuid_v4(rng)),
+        "int" => {
+            // Fractional bounds round inwards, `int(2.5, 2.7)` holds no integer and gives `null`
+            let (min, max) = range((0.0, 100.0));
+            let (min, max) = (min.ceil() as i64, max.floor() as i64);
+            if min <= max {
+                json!(rng.gen_range(min..=max))
+            } else {
+                Value::Null
+            }
+        }
+        "float" => {
+            let (min, max) = range((0.0, 1.0));
+            let value: f64 = if min < max { rng.gen_range(min..max) } else { min };
+            json!((value * 100.0).round() / 100.0)
+        }
+        "bool" => json!(rng.gen_bool(0.5)),
+        "oneOf" => generator
+            .args
+            .choose(rng)
+            .map(MockValue::to_json)
+            .unwrap_or(Value::Null),
+        "date.recent" => date_value(ctx.now - Duration::seconds(rng.gen_range(0..7 * 86400))),
+        "date.past" => date_value(ctx.now - Duration::days(rng.gen_range(1..3650))),
+        "date.future" => date_value(ctx.now + Duration::days(rng.gen_range(1..365))),
+        "faker.firstName" => json!(pick(rng, FIRST_NAMES)),
+        "faker.lastName" => json!(pick(rng, LAST_NAMES)),
+        "faker.name" => json!(format!("{} {}", pick(rng, FIRST_NAMES), pick(rng, LAST_NAMES))),
+        "faker.username" => json!(format!(
+            "{}{}",
+            pick(rng, FIRST_NAMES).to_lowercase(),
+            rng.gen_range(1..1000)
+        )),
+        "faker.email" => json!(format!(
+            "{}.{}@example.com",
+            pick(rng, FIRST_NAMES).to_lowercase(),
+            pick(rng, LAST_NAMES).to_lowercase()
+        )),
+        "faker.phone" => json!(format!(
+            "+1-555-{:03}-{:04}",
+            rng.gen_range(0..1000),
+            rng.gen_range(0..10000)
+        )),
+        "faker.avatar" => json!(format!("https://i.pravatar.cc/150?img={}", rng.gen_range(1..=70))),
+        "faker.url" => json!(format!("https://{}.example.com", pick(rng, WORDS))),
+        "faker.company" => json!(pick(rng, COMPANIES)),
+        "faker.city" => json!(pick(rng, CITIES)),
+        "faker.country" => json!(pick(rng, COUNTRIES)),
+        "faker.address" => json!(format!("{} {}", rng.gen_range(1..10000), pick(rng, STREETS))),
+        "faker.ip" => json!(format!(
+            "{}.{}.{}.{}",
+            rng.gen_range(1..255),
+            rng.gen_range(0..256),
+            rng.gen_range(0..256),
+            rng.gen_range(1..255)
+        )),
+        "faker.word" => json!(pick(rng, WORDS)),
+        "faker.sentence" => {
+            let count = rng.gen_range(4..10);
+            let words: Vec<&str> = (0..count).map(|_| pick(rng, WORDS)).collect();
+            let mut sentence = words.join(" ");
+            sentence[..1].make_ascii_uppercase();
+            sentence.push('.');
+            json!(sentence)
+        }
+        _ => Value::Null,
+    }
+}
+
+fn date_value(date: DateTime<Utc>) -> Value {
+    json!(date.to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+/// A path or query value converted to the type of the field it is echoed into
+pub fn typed_value(value: &str, field_type: &FieldType) -> Value {
+    match field_type {
+        FieldType::Integer => value
+            .parse::<i64>()
+            .map(Value::from)
+            .unwrap_or_else(|_| json!(value)),
+        FieldType::Number => value
+            .parse::<i64>()
+            .map(Value::from)
+            .or_else(|_| value.parse::<f64>().map(Value::from))
+            .unwrap_or_else(|_| json!(value)),
+        FieldType::Boolean => match value {
+            "true" => json!(true),
+            "false" => json!(false),
+            _ => json!(value),
+        },
+        _ => json!(value),
+    }
+}
@@ -1,23 +1,54 @@
 //! OpenAPI parser - converts OpenAPI JSON/YAML to RqcConfig (supports local files and remote URLs)
 
+mod export;
+mod refs;
+mod security;
+mod swagger;
+
+pub use export::export_openapi;
+
 use crate::parser::{
-    ApiBlock, CategoryBlock, ConfigBlock, Field, FieldType, MethodBlock, MockValue, RqcConfig,
-    SchemaBlock,
+    enum_value_type, ApiBlock, CategoryBlock, ConfigBlock, Constraints, Field, FieldType,
+    MethodBlock, MockValue, ParamLocation, RqcConfig, SchemaBlock, StatusResponse,
+    VariableDefinition,
 };
+use indexmap::IndexMap;
+use refs::Location;
+use security::{Security, SecurityRequirements, SecurityScheme};
 use serde::Deserialize;
 use std::collections::HashMap;
-use std::fs;
 use std::path::Path;
+use tracing::warn;
+
+/// `propertyNames` pattern of maps with integer keys, keys are always strings in JSON
+const INTEGER_KEY_PATTERN: &str = "^-?[0-9]+$";
 
 #[derive(Deserialize)]
 struct OpenApiSpec {
     servers: Option<Vec<Server>>,
-    paths: Option<HashMap<String, HashMap<String, Operation>>>,
+    /// Path items, read per method as they also hold shared `parameters` and a `summary`
+    paths: Option<HashMap<String, HashMap<String, serde_json::Value>>>,
+    components: Option<Components>,
+    /// Schemes required by operations without their own `security`
+    security: Option<SecurityRequirements>,
+}
+
+#[derive(Deserialize)]
+struct Components {
+    #[serde(rename = "securitySchemes")]
+    security_schemes: Option<IndexMap<String, SecurityScheme>>,
 }
 
 #[derive(Deserialize)]
 struct Server {
     url: Option<String>,
+    /// `{name}` placeholders of the URL, they become config variables
+    variables: Option<IndexMap<String, ServerVariable>>,
+}
+
+#[derive(Deserialize)]
+struct ServerVariable {
+    default: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -31,9 +62,10 @@ struct Operation {
     #[serde(rename = "requestBody")]
     request_body: Option<RequestBody>,
     responses: Option<HashMap<String, Response>>,
+    security: Option<SecurityRequirements>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 struct Parameter {
     name: Option<String>,
     #[serde(rename = "in")]
@@ -61,23 +93,121 @@ struct MediaType {
 #[derive(Deserialize, Clone)]
 struct Schema {
     #[serde(rename = "type")]
-    schema_type: Option<String>,
-    properties: Option<HashMap<String, Schema>>,
+    schema_type: Option<SchemaType>,
+    /// OpenAPI 3.0, 3.1 lists `null` in `type` instead
+    nullable: Option<bool>,
+    #[serde(rename = "oneOf")]
+    one_of: Option<Vec<Schema>>,
+    #[serde(rename = "anyOf")]
+    any_of: Option<Vec<Schema>>,
+    discriminator: Option<Discriminator>,
+    /// Kept in document order, it is the order of the generated fields
+    properties: Option<IndexMap<String, Schema>>,
     items: Option<Box<Schema>>,
+    /// Value schema of a dictionary, `true` allows any value
+    #[serde(rename = "additionalProperties")]
+    additional_properties: Option<AdditionalProperties>,
+    /// Key schema of a dictionary (JSON Schema)
+    #[serde(rename = "propertyNames")]
+    property_names: Option<Box<Schema>>,
     required: Option<Vec<String>>,
     description: Option<String>,
     example: Option<serde_json::Value>,
+    /// OpenAPI 3.1 (JSON Schema) form of `example`
+    examples: Option<Vec<serde_json::Value>>,
+    #[serde(rename = "enum")]
+    enum_values: Option<Vec<serde_json::Value>>,
+    /// A single allowed value (JSON Schema)
+    #[serde(rename = "const")]
+    const_value: Option<serde_json::Value>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+    pattern: Option<String>,
+    format: Option<String>,
+    #[serde(rename = "minLength")]
+    min_length: Option<u64>,
+    #[serde(rename = "maxLength")]
+    max_length: Option<u64>,
+    #[serde(rename = "minItems")]
+    min_items: Option<u64>,
+    #[serde(rename = "maxItems")]
+    max_items: Option<u64>,
+    #[serde(rename = "minProperties")]
+    min_properties: Option<u64>,
+    #[serde(rename = "maxProperties")]
+    max_properties: Option<u64>,
+}
+
+/// `type: string`, or `type: [string, "null"]` in OpenAPI 3.1
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+enum SchemaType {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+#[derive(Deserialize, Clone)]
+struct Discriminator {
+    #[serde(rename = "propertyName")]
+    property_name: String,
+}
+
+impl Schema {
+    /// The declared type, other than `null`
+    fn type_name(&self) -> Option<String> {
+        let name = match self.schema_type.as_ref()? {
+            SchemaType::Single(name) => Some(name),
+            SchemaType::Multiple(names) => names.iter().find(|n| *n != "null"),
+        };
+        name.filter(|n| *n != "null").map(|n| n.to_lowercase())
+    }
+
+    /// `nullable: true`, `null` in `type` or `enum`, or `null` alone
+    fn allows_null(&self) -> bool {
+        let in_type = match self.schema_type {
+            Some(SchemaType::Single(ref name)) => name == "null",
+            Some(SchemaType::Multiple(ref names)) => names.iter().any(|n| n == "null"),
+            None => false,
+        };
+        let in_enum = self.enum_values.iter().flatten().any(serde_json::Value::is_null);
+        self.nullable == Some(true) || in_type || in_enum
+    }
+
+    /// `{ "type": "null" }`, an alternative of `oneOf` that only makes the field nullable
+    fn is_null(&self) -> bool {
+        self.allows_null() && self.type_name().is_none() && self.properties.is_none()
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+enum AdditionalProperties {
+    Allowed(bool),
+    Schema(Box<Schema>),
 }
 
 /// Parse OpenAPI from a local file
 pub fn parse_openapi_file(path: &Path) -> Result<RqcConfig, Box<dyn std::error::Error>> {
-    let content = fs::read_to_string(path)?;
-    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
-    parse_openapi_content(&content, ext)
+    parse_openapi(Location::File(path.to_path_buf()))
 }
 
 /// Parse OpenAPI from a remote URL
 pub fn parse_openapi_url(url: &str) -> Result<RqcConfig, Box<dyn std::error::Error>> {
+    parse_openapi(Location::Url(url.to_string()))
+}
+
+fn parse_openapi(location: Location) -> Result<RqcConfig, Box<dyn std::error::Error>> {
+    let document = location.load()?;
+    let mut document = refs::resolve(document, &location);
+    if swagger::is_swagger(&document) {
+        document = swagger::to_openapi(&document);
+    }
+    let spec: OpenApiSpec = serde_json::from_value(document)?;
+    convert_to_rqc(spec)
+}
+
+/// Download a document, along with its format from the Content-Type header or URL extension
+fn fetch(url: &str) -> Result<(String, &str), Box<dyn std::error::Error>> {
     let response = reqwest::blocking::get(url)?;
 
     if !response.status().is_success() {
@@ -101,7 +231,7 @@ pub fn parse_openapi_url(url: &str) -> Result<RqcConfig, Box<dyn std::error::Err
     };
 
     let content = response.text()?;
-    parse_openapi_content(&content, ext)
+    Ok((content, ext))
 }
 
 /// Check if a path is a URL
@@ -109,40 +239,90 @@ pub fn is_url(path: &str) -> bool {
     path.contains("://")
 }
 
-fn parse_openapi_content(
-    content: &str,
-    ext: &str,
-) -> Result<RqcConfig, Box<dyn std::error::Error>> {
-    let spec: OpenApiSpec = match ext {
+fn parse_document(content: &str, ext: &str) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let document = match ext {
         "json" => serde_json::from_str(content)?,
-        "yaml" | "yml" => serde_yaml::from_str(content)?,
+        "yaml" | "yml" => yaml_to_json(serde_yaml::from_str(content)?),
         _ => {
             // Try JSON first, then YAML
             serde_json::from_str(content)
-                .or_else(|_| serde_yaml::from_str(content))
+                .or_else(|_| serde_yaml::from_str(content).map(yaml_to_json))
                 .map_err(|e| format!("Failed to parse OpenAPI: {}", e))?
         }
     };
+    Ok(document)
+}
 
-    convert_to_rqc(spec)
+/// YAML allows keys that are not strings, such as the unquoted `200:` of a response
+fn yaml_to_json(value: serde_yaml::Value) -> serde_json::Value {
+    match value {
+        serde_yaml::Value::Null => serde_json::Value::Null,
+        serde_yaml::Value::Bool(b) => serde_json::Value::Bool(b),
+        serde_yaml::Value::Number(n) => serde_json::to_value(n).unwrap_or(serde_json::Value::Null),
+        serde_yaml::Value::String(s) => serde_json::Value::String(s),
+        serde_yaml::Value::Sequence(items) => items.into_iter().map(yaml_to_json).collect(),
+        serde_yaml::Value::Mapping(mapping) => serde_json::Value::Object(
+            mapping
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        serde_yaml::Value::String(s) => s,
+                        other => serde_yaml::to_string(&other)
+                            .unwrap_or_default()
+                            .trim_end()
+                            .to_string(),
+                    };
+                    (key, yaml_to_json(value))
+                })
+                .collect(),
+        ),
+        serde_yaml::Value::Tagged(tagged) => yaml_to_json(tagged.value),
+    }
 }
 
 fn convert_to_rqc(spec: OpenApiSpec) -> Result<RqcConfig, Box<dyn std::error::Error>> {
     let mut config = RqcConfig::default();
 
-    // Extract base URL from servers
-    if let Some(servers) = spec.servers {
-        if let Some(url) = servers.first().and_then(|s| s.url.clone()) {
-            config.config = Some(ConfigBlock {
-                base_urls: vec![url],
-                cors: false,
-                mock: false,
-                variables: Vec::new(),
-                headers: Vec::new(),
+    // Every server is a base URL to pick from, server variables are config variables
+    let servers = spec.servers.unwrap_or_default();
+    let base_urls: Vec<String> = servers.iter().filter_map(|s| s.url.clone()).collect();
+    let mut variables: Vec<VariableDefinition> = Vec::new();
+    for (name, variable) in servers.iter().flat_map(|s| s.variables.iter().flatten()) {
+        if !variables.iter().any(|v| &v.name == name) {
+            variables.push(VariableDefinition {
+                name: name.clone(),
+                var_type: "String".to_string(),
+                default_value: variable.default.clone(),
+                ..Default::default()
             });
         }
     }
 
+    // Credentials are variables, sent by the auth of the imported category or the operations
+    let security = Security::new(
+        spec.components
+            .as_ref()
+            .and_then(|c| c.security_schemes.as_ref()),
+        spec.security.as_ref(),
+    );
+    for variable in security.variables() {
+        if !variables.iter().any(|v| v.name == variable.name) {
+            variables.push(variable);
+        }
+    }
+
+    if !base_urls.is_empty() || !variables.is_empty() {
+        config.config = Some(ConfigBlock {
+            base_urls,
+            cors: false,
+            mock: false,
+            variables,
+            headers: Vec::new(),
+            envs: Vec::new(),
+            auth: None,
+        });
+    }
+
     // Convert paths to APIs
     let Some(paths) = spec.paths else {
         return Ok(config);
@@ -157,6 +337,10 @@ fn convert_to_rqc(spec: OpenApiSpec) -> Result<RqcConfig, Box<dyn std::error::Er
             methods: Vec::new(),
         };
         let mut api_tag: Option<String> = None;
+        let shared_parameters: Vec<Parameter> = match methods.get("parameters") {
+            Some(parameters) => serde_json::from_value(parameters.clone())?,
+            None => Vec::new(),
+        };
 
         for (method, op) in methods {
             let method_upper = method.to_uppercase();
@@ -166,38 +350,84 @@ fn convert_to_rqc(spec: OpenApiSpec) -> Result<RqcConfig, Box<dyn std::error::Er
             ) {
                 continue;
             }
+            let mut op: Operation = serde_json::from_value(op)?;
+
+            // Parameters of the path item apply to every operation that does not redefine them
+            let own = op.parameters.take().unwrap_or_default();
+            let mut parameters: Vec<Parameter> = shared_parameters
+                .iter()
+                .filter(|shared| {
+                    !own.iter()
+                        .any(|p| p.name == shared.name && p.location == shared.location)
+                })
+                .cloned()
+                .collect();
+            parameters.extend(own);
+            op.parameters = Some(parameters);
 
             if api_tag.is_none() {
                 api_tag = op.tags.as_ref().and_then(|t| t.first().cloned());
             }
 
             let mut request_fields = parse_parameters(&op.parameters);
-
-            // Parse request body
-            if let Some(body) = op.request_body {
-                if let Some(schema) = body
-                    .content
-                    .and_then(|c| c.get("application/json").cloned())
-                    .and_then(|m| m.schema)
-                {
-                    request_fields.extend(parse_schema(&schema));
+            let (auth, security_fields) = security.operation(op.security.as_ref());
+            for field in security_fields {
+                let declared = request_fields
+                    .iter()
+                    .any(|f| f.name == field.name && f.location == field.location);
+                if !declared {
+                    request_fields.push(field);
                 }
             }
 
-            // Parse response
-            let response = op.responses.and_then(|r| {
-                r.get("200")
-                    .or_else(|| r.get("201"))
-                    .or_else(|| r.get("default"))
-                    .cloned()
-            });
+            // Parse request body, other media types than JSON are kept as the content type
+            let mut content_type = None;
+            if let Some((media_type, schema)) = op
+                .request_body
+                .and_then(|body| body.content)
+                .and_then(|content| media_schema(&content))
+            {
+                request_fields.extend(parse_schema(&schema));
+                if !is_json(&media_type) {
+                    content_type = Some(media_type);
+                }
+            }
 
-            let response_fields = response
-                .and_then(|r| r.content)
-                .and_then(|c| c.get("application/json").cloned())
-                .and_then(|m| m.schema)
-                .map(|s| parse_schema(&s))
-                .unwrap_or_default();
+            // The first success response is the main one, every other status is kept too
+            let mut responses: Vec<(String, Response)> =
+                op.responses.unwrap_or_default().into_iter().collect();
+            responses.sort_by_key(|(status, _)| status_order(status));
+            let main = responses
+                .iter()
+                .position(|(status, _)| status.starts_with('2'))
+                .or_else(|| responses.iter().position(|(status, _)| status == "default"))
+                .map(|i| responses.remove(i));
+
+            let operation = format!("{} {}", method_upper, path);
+            let response_schema = |status: &str, response: Response| {
+                let schema = response.content.and_then(|c| media_schema(&c)).map(|(_, s)| s);
+                if let Some(kind) = schema.as_ref().and_then(root_type) {
+                    warn!(
+                        "response {} of {} is {}, only object responses can be imported, its schema is left out",
+                        status, operation, kind
+                    );
+                }
+                SchemaBlock {
+                    fields: schema.map(|s| parse_schema(&s)).unwrap_or_default(),
+                    optional: false,
+                    spreads: Vec::new(),
+                }
+            };
+            let (status, response) = match main {
+                Some((status, response)) => {
+                    let schema = response_schema(&status, response);
+                    // `default` alone reads as the response of any status
+                    let status = (status.starts_with('2') && status != "200")
+                        .then(|| status.to_uppercase());
+                    (status, Some(schema).filter(|s| !s.fields.is_empty()))
+                }
+                None => (None, None),
+            };
 
             api_block.methods.push(MethodBlock {
                 method: method_upper,
@@ -209,16 +439,29 @@ fn convert_to_rqc(spec: OpenApiSpec) -> Result<RqcConfig, Box<dyn std::error::Er
                     Some(SchemaBlock {
                         fields: request_fields,
                         optional: false,
+                        spreads: Vec::new(),
                     })
                 },
-                response: if response_fields.is_empty() {
-                    None
-                } else {
-                    Some(SchemaBlock {
-                        fields: response_fields,
-                        optional: false,
+                response,
+                status,
+                responses: responses
+                    .into_iter()
+                    .map(|(status, response)| {
+                        let schema = response_schema(&status, response);
+                        StatusResponse {
+                            status: match status.as_str() {
+                                "default" => status,
+                                // `4xx` is written `4XX` in .rqc files
+                                _ => status.to_uppercase(),
+                            },
+                            schema,
+                        }
                     })
-                },
+                    .collect(),
+                content_type,
+                expect: Vec::new(),
+                mock: None,
+                auth,
             });
         }
 
@@ -238,6 +481,8 @@ fn convert_to_rqc(spec: OpenApiSpec) -> Result<RqcConfig, Box<dyn std::error::Er
             name: Some(tag),
             desc: None,
             prefix: None,
+            mock: None,
+            auth: None,
             apis,
             ws_apis: Vec::new(),
             socketio_apis: Vec::new(),
@@ -255,6 +500,8 @@ fn convert_to_rqc(spec: OpenApiSpec) -> Result<RqcConfig, Box<dyn std::error::Er
         name: Some("OpenAPI".to_string()),
         desc: Some("Imported from OpenAPI specification".to_string()),
         prefix: None,
+        mock: None,
+        auth: security.default_auth(),
         apis: untagged_apis,
         ws_apis: Vec::new(),
         socketio_apis: Vec::new(),
@@ -265,6 +512,45 @@ fn convert_to_rqc(spec: OpenApiSpec) -> Result<RqcConfig, Box<dyn std::error::Er
     Ok(config)
 }
 
+/// `200` before `201`, codes before ranges such as `4XX`, `default` last
+fn status_order(status: &str) -> (u8, String) {
+    match status {
+        "default" => (2, String::new()),
+        s if s.chars().all(|c| c.is_ascii_digit()) => (0, s.to_string()),
+        s => (1, s.to_uppercase()),
+    }
+}
+
+fn is_json(media_type: &str) -> bool {
+    let media_type = media_type.split(';').next().unwrap_or_default().trim();
+    media_type.ends_with("/json") || media_type.ends_with("+json")
+}
+
+/// Media type and schema of a request or response body, JSON is preferred over forms and
+/// forms over other media types
+fn media_schema(content: &HashMap<String, MediaType>) -> Option<(String, Schema)> {
+    let rank = |media_type: &str| {
+        let media_type = media_type.split(';').next().unwrap_or_default().trim();
+        if media_type == "application/json" {
+            0
+        } else if media_type.ends_with("+json") || media_type.ends_with("/json") {
+            1
+        } else if media_type == "multipart/form-data"
+            || media_type == "application/x-www-form-urlencoded"
+        {
+            2
+        } else {
+            3
+        }
+    };
+    let mut candidates: Vec<(&String, &MediaType)> =
+        content.iter().filter(|(_, m)| m.schema.is_some()).collect();
+    // Ties keep a stable order, the content map is unordered
+    candidates.sort_by_key(|(media_type, _)| (rank(media_type), media_type.as_str()));
+    let (media_type, media) = candidates.first()?;
+    Some((media_type.to_string(), media.schema.clone()?))
+}
+
 fn parse_parameters(params: &Option<Vec<Parameter>>) -> Vec<Field> {
     let Some(params) = params else {
         return Vec::new();
@@ -274,26 +560,50 @@ fn parse_parameters(params: &Option<Vec<Parameter>>) -> Vec<Field> {
         .iter()
         .filter_map(|p| {
             let name = p.name.clone()?;
-            let field_type = p
-                .schema
-                .as_ref()
-                .and_then(|s| s.schema_type.clone())
-                .unwrap_or_else(|| "string".to_string());
-
-            Some(Field {
-                name,
-                field_type: convert_type(&field_type),
-                optional: !p.required.unwrap_or(false),
-                nested: None,
-                mock: None,
-                comment: p.description.clone(),
-                example: p.schema.as_ref().and_then(|s| convert_example(&s.example)),
-                is_params: p.location.as_deref() == Some("query"),
-            })
+            // These are described by the request body and security schemes instead
+            let ignored = ["accept", "content-type", "authorization"];
+            if p.location.as_deref() == Some("header")
+                && ignored.contains(&name.to_lowercase().as_str())
+            {
+                return None;
+            }
+            let mut field = match p.schema {
+                Some(ref schema) => schema_field(&name, schema),
+                None => Field::element(&name, FieldType::String),
+            };
+            field.optional = !p.required.unwrap_or(false);
+            field.comment = p.description.clone();
+            field.example = p.schema.as_ref().and_then(schema_example);
+            match p.location.as_deref() {
+                Some("query") => field.is_params = true,
+                Some("path") => {
+                    // Path parameters are always required
+                    field.location = Some(ParamLocation::Path);
+                    field.optional = false;
+                }
+                Some("header") => field.location = Some(ParamLocation::Header),
+                Some("cookie") => field.location = Some(ParamLocation::Cookie),
+                _ => {}
+            }
+            Some(field)
         })
         .collect()
 }
 
+/// What a root schema is when it is not an object, such as `an array` for a Swagger
+/// `GET /pets` answering `[Pet]`. Bodies of .rqc files are always objects
+fn root_type(schema: &Schema) -> Option<String> {
+    if schema.properties.is_some() {
+        return None;
+    }
+    match schema.type_name() {
+        Some(name) if name == "array" => Some("an array".to_string()),
+        None if schema.items.is_some() => Some("an array".to_string()),
+        Some(name) if name != "object" => Some(format!("of type `{}`", name)),
+        _ => None,
+    }
+}
+
 fn parse_schema(schema: &Schema) -> Vec<Field> {
     let Some(properties) = &schema.properties else {
         return Vec::new();
@@ -304,58 +614,167 @@ fn parse_schema(schema: &Schema) -> Vec<Field> {
     properties
         .iter()
         .map(|(name, prop)| {
-            let field_type = prop
-                .schema_type
-                .clone()
-                .unwrap_or_else(|| "string".to_string());
-
-            let nested = match field_type.as_str() {
-                "object" => {
-                    let fields = parse_schema(prop);
-                    (!fields.is_empty()).then(|| {
-                        Box::new(SchemaBlock {
-                            fields,
-                            optional: false,
-                        })
-                    })
+            let mut field = schema_field(name, prop);
+            field.optional = !required.contains(name);
+            field.comment = prop.description.clone();
+            field.example = schema_example(prop);
+            field
+        })
+        .collect()
+}
+
+/// Field for a property schema, array `items` become the element type, recursively.
+/// `oneOf` / `anyOf` become unions, unless a single alternative is left besides `null`.
+fn schema_field(name: &str, schema: &Schema) -> Field {
+    if let Some(alternatives) = schema.one_of.as_ref().or(schema.any_of.as_ref()) {
+        let mut variants: Vec<Field> = alternatives
+            .iter()
+            .filter(|alternative| !alternative.is_null())
+            .map(|alternative| schema_field(name, alternative))
+            .collect();
+        let nullable = schema.allows_null() || alternatives.iter().any(Schema::allows_null);
+
+        let mut field = if variants.len() == 1 {
+            variants.pop().unwrap()
+        } else {
+            let mut field = Field::element(name, FieldType::Union);
+            field.variants = Some(variants);
+            field.discriminator = schema.discriminator.as_ref().map(|d| d.property_name.clone());
+            field
+        };
+        field.nullable |= nullable;
+        return field;
+    }
+
+    let mut field = Field::element(name, convert_type(schema));
+    field.nullable = schema.allows_null();
+    if let Some(values) = schema_enum(schema) {
+        if schema.type_name().is_none() {
+            field.field_type = enum_value_type(&values);
+        }
+        field.enum_values = Some(values);
+    }
+    field.constraints = schema_constraints(schema);
+    // The format is part of the type, `string` + `uuid` is a `UUID`
+    if !matches!(field.field_type, FieldType::String | FieldType::Number) {
+        field.constraints.format = None;
+    }
+    match field.field_type {
+        FieldType::Object => {
+            let fields = parse_schema(schema);
+            if !fields.is_empty() {
+                field.nested = Some(Box::new(SchemaBlock {
+                    fields,
+                    optional: false,
+                    spreads: Vec::new(),
+                }));
+            }
+        }
+        FieldType::Array => {
+            // `items: {}` allows anything, it stays an untyped `Array`
+            field.items = schema
+                .items
+                .as_ref()
+                .filter(|items| {
+                    items.schema_type.is_some()
+                        || items.properties.is_some()
+                        || items.items.is_some()
+                        || items.one_of.is_some()
+                        || items.any_of.is_some()
+                })
+                .map(|items| Box::new(schema_field(name, items)));
+        }
+        FieldType::Map => {
+            field.items = match schema.additional_properties {
+                Some(AdditionalProperties::Schema(ref values)) => {
+                    Some(Box::new(schema_field(name, values)))
                 }
-                "array" => prop.items.as_ref().and_then(|items| {
-                    let fields = parse_schema(items);
-                    (!fields.is_empty()).then(|| {
-                        Box::new(SchemaBlock {
-                            fields,
-                            optional: false,
-                        })
-                    })
-                }),
                 _ => None,
             };
+            field.key_type = schema.property_names.as_deref().map(|keys| {
+                match (keys.format.as_deref(), keys.pattern.as_deref()) {
+                    (Some("uuid"), _) => FieldType::Uuid,
+                    (Some("date"), _) => FieldType::Date,
+                    (_, Some(INTEGER_KEY_PATTERN)) => FieldType::Integer,
+                    _ => FieldType::String,
+                }
+            });
+            field.constraints.min_length = schema.min_properties;
+            field.constraints.max_length = schema.max_properties;
+        }
+        _ => {}
+    }
+    field
+}
 
-            Field {
-                name: name.clone(),
-                field_type: convert_type(&field_type),
-                optional: !required.contains(name),
-                nested,
-                mock: None,
-                comment: prop.description.clone(),
-                example: convert_example(&prop.example),
-                is_params: false,
+/// Type of a schema, `format` picks the richer string types and an object without
+/// `properties` but with `additionalProperties` is a map
+fn convert_type(schema: &Schema) -> FieldType {
+    let schema_type = match schema.type_name() {
+        Some(t) => t,
+        None if schema.properties.is_some() => "object".to_string(),
+        None if schema.additional_properties.is_some() => "object".to_string(),
+        None if schema.items.is_some() => "array".to_string(),
+        None => "string".to_string(),
+    };
+
+    match (schema_type.as_str(), schema.format.as_deref()) {
+        ("string", Some("date-time")) => FieldType::DateTime,
+        ("string", Some("date")) => FieldType::Date,
+        ("string", Some("uuid")) => FieldType::Uuid,
+        ("string", Some("binary")) => FieldType::File,
+        ("string", _) => FieldType::String,
+        ("integer", _) => FieldType::Integer,
+        ("number", _) => FieldType::Number,
+        ("boolean", _) => FieldType::Boolean,
+        ("array", _) => FieldType::Array,
+        ("object", _) => {
+            let is_map = schema.properties.is_none()
+                && matches!(
+                    schema.additional_properties,
+                    Some(AdditionalProperties::Schema(_)) | Some(AdditionalProperties::Allowed(true))
+                );
+            if is_map {
+                FieldType::Map
+            } else {
+                FieldType::Object
             }
-        })
-        .collect()
+        }
+        _ => FieldType::String,
+    }
 }
 
-fn convert_type(t: &str) -> FieldType {
-    match t.to_lowercase().as_str() {
-        "string" => FieldType::String,
-        "integer" | "number" => FieldType::Number,
-        "boolean" => FieldType::Boolean,
-        "array" => FieldType::Array,
-        "object" => FieldType::Object,
-        _ => FieldType::String,
+/// `enum` or `const` values, structured values have no equivalent and are left out, `null`
+/// makes the field nullable instead
+fn schema_enum(schema: &Schema) -> Option<Vec<MockValue>> {
+    let values: Vec<MockValue> = schema
+        .enum_values
+        .iter()
+        .flatten()
+        .chain(schema.const_value.iter())
+        .filter_map(|v| convert_example(&Some(v.clone())))
+        .collect();
+    (!values.is_empty()).then_some(values)
+}
+
+/// `minimum`, `maxLength`, `pattern`, `format`..., lengths of arrays come from `minItems` and
+/// `maxItems`
+fn schema_constraints(schema: &Schema) -> Constraints {
+    Constraints {
+        min: schema.minimum,
+        max: schema.maximum,
+        pattern: schema.pattern.clone(),
+        format: schema.format.clone(),
+        min_length: schema.min_length.or(schema.min_items),
+        max_length: schema.max_length.or(schema.max_items),
     }
 }
 
+fn schema_example(schema: &Schema) -> Option<MockValue> {
+    let first = schema.examples.as_ref().and_then(|e| e.first().cloned());
+    convert_example(&schema.example.clone().or(first))
+}
+
 fn convert_example(value: &Option<serde_json::Value>) -> Option<MockValue> {
     value.as_ref().and_then(|v| match v {
         serde_json::Value::String(s) => Some(MockValue::String(s.clone())),
@@ -0,0 +1,462 @@
+//! OpenAPI exporter - converts RqcConfig to an OpenAPI 3.1 document
+
+use super::INTEGER_KEY_PATTERN;
+use crate::parser::{
+    ApiEndpoint, CategoryBlock, EndpointType, ExpectOp, ExpectSubject, Field, FieldType,
+    MockValue, ParamLocation, RqcConfig, SchemaBlock, VariableDefinition,
+};
+use crate::runner::placeholders;
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+
+/// Build an OpenAPI 3.1 document from a project, endpoints that cannot be exported are
+/// listed in the returned notes
+pub fn export_openapi(config: &RqcConfig, title: &str) -> (Value, Vec<String>) {
+    let mut notes = Vec::new();
+    let mut doc = Map::new();
+
+    doc.insert("openapi".to_string(), json!("3.1.0"));
+    doc.insert(
+        "info".to_string(),
+        json!({ "title": title, "version": "1.0.0" }),
+    );
+
+    let servers = export_servers(config);
+    if !servers.is_empty() {
+        doc.insert("servers".to_string(), Value::Array(servers));
+    }
+
+    let mut tags = Vec::new();
+    let mut seen_tags = HashSet::new();
+    for category in &config.categories {
+        collect_tags(category, &mut tags, &mut seen_tags);
+    }
+    if !tags.is_empty() {
+        doc.insert("tags".to_string(), Value::Array(tags));
+    }
+
+    let mut paths = Map::new();
+    let mut operation_ids = HashSet::new();
+
+    for endpoint in config.to_endpoints() {
+        if !matches!(
+            endpoint.endpoint_type,
+            EndpointType::Http | EndpointType::Sse
+        ) {
+            continue;
+        }
+
+        if endpoint.path.contains("://") {
+            notes.push(format!(
+                "skipped {} {}: absolute URLs cannot be expressed as OpenAPI paths",
+                endpoint.method.as_deref().unwrap_or("GET"),
+                endpoint.path
+            ));
+            continue;
+        }
+
+        let method = match endpoint.endpoint_type {
+            EndpointType::Sse => "get".to_string(),
+            _ => endpoint
+                .method
+                .as_deref()
+                .unwrap_or("GET")
+                .to_lowercase(),
+        };
+
+        let item = paths
+            .entry(endpoint.path.clone())
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .unwrap();
+
+        if item.contains_key(&method) {
+            notes.push(format!(
+                "skipped duplicate {} {}",
+                method.to_uppercase(),
+                endpoint.path
+            ));
+            continue;
+        }
+
+        let operation = export_operation(&endpoint, &method, &mut operation_ids);
+        item.insert(method, operation);
+    }
+
+    doc.insert("paths".to_string(), Value::Object(paths));
+    (Value::Object(doc), notes)
+}
+
+/// One server per base URL of the config block and of each `env` block, `{var}` placeholders
+/// become server variables
+fn export_servers(config: &RqcConfig) -> Vec<Value> {
+    let Some(ref block) = config.config else {
+        return Vec::new();
+    };
+
+    let mut servers: Vec<Value> = Vec::new();
+    let defaults = config.env_variables(None);
+    for url in &block.base_urls {
+        servers.push(export_server(url, &defaults));
+    }
+    for env in &block.envs {
+        let variables = config.env_variables(Some(env));
+        for url in &env.base_urls {
+            if !servers.iter().any(|s| s["url"] == json!(url)) {
+                let mut server = export_server(url, &variables);
+                server["description"] = json!(env.name);
+                servers.push(server);
+            }
+        }
+    }
+    servers
+}
+
+fn export_server(url: &str, variables: &[VariableDefinition]) -> Value {
+    let mut server = json!({ "url": url });
+    let mut server_vars = Map::new();
+    for name in placeholders(url) {
+        let default = variables
+            .iter()
+            .find(|v| v.name == name)
+            .and_then(|v| v.default_value.clone())
+            .unwrap_or_default();
+        server_vars.insert(name, json!({ "default": default }));
+    }
+    if !server_vars.is_empty() {
+        server["variables"] = Value::Object(server_vars);
+    }
+    server
+}
+
+fn collect_tags(category: &CategoryBlock, tags: &mut Vec<Value>, seen: &mut HashSet<String>) {
+    let name = tag_name(category.name.as_ref(), &category.id);
+    if seen.insert(name.clone()) {
+        let mut tag = json!({ "name": name });
+        if let Some(ref desc) = category.desc {
+            tag["description"] = json!(desc);
+        }
+        tags.push(tag);
+    }
+
+    for child in &category.children {
+        collect_tags(child, tags, seen);
+    }
+}
+
+/// Category name, or the identifier it was declared with (ids look like `cat-users-1`)
+fn tag_name(name: Option<&String>, id: &str) -> String {
+    if let Some(name) = name {
+        return name.clone();
+    }
+    id.strip_prefix("cat-")
+        .and_then(|rest| rest.rsplit_once('-'))
+        .map(|(ident, _)| ident.to_string())
+        .unwrap_or_else(|| id.to_string())
+}
+
+fn export_operation(endpoint: &ApiEndpoint, method: &str, ids: &mut HashSet<String>) -> Value {
+    let mut op = Map::new();
+
+    if let Some(ref name) = endpoint.name {
+        op.insert("summary".to_string(), json!(name));
+    }
+    if let Some(ref description) = endpoint.description {
+        op.insert("description".to_string(), json!(description));
+    }
+    op.insert(
+        "operationId".to_string(),
+        json!(operation_id(endpoint, method, ids)),
+    );
+    if let Some(ref id) = endpoint.category_id {
+        op.insert(
+            "tags".to_string(),
+            json!([tag_name(endpoint.category_name.as_ref(), id)]),
+        );
+    }
+
+    let fields: Vec<&Field> = endpoint
+        .request
+        .iter()
+        .flat_map(|r| r.fields.iter())
+        .collect();
+
+    // Path parameters, typed by the request field of the same name when there is one
+    let path_params = placeholders(&endpoint.path);
+    let mut parameters: Vec<Value> = path_params
+        .iter()
+        .map(|name| {
+            let schema = fields
+                .iter()
+                .find(|f| &f.name == name)
+                .map(|f| field_schema(f))
+                .unwrap_or_else(|| json!({ "type": "string" }));
+            json!({ "name": name, "in": "path", "required": true, "schema": schema })
+        })
+        .collect();
+
+    for field in fields.iter().filter(|f| !path_params.contains(&f.name)) {
+        let location = match field.location {
+            _ if field.is_params => "query",
+            Some(ParamLocation::Header) => "header",
+            Some(ParamLocation::Cookie) => "cookie",
+            // A `@path` field without a placeholder in the path is not sent at all
+            Some(ParamLocation::Path) | None => continue,
+        };
+        parameters.push(export_parameter(field, location));
+    }
+    if !parameters.is_empty() {
+        op.insert("parameters".to_string(), Value::Array(parameters));
+    }
+
+    if let Some(ref request) = endpoint.request {
+        let body_fields: Vec<Field> = request
+            .fields
+            .iter()
+            .filter(|f| f.in_body() && !path_params.contains(&f.name))
+            .cloned()
+            .collect();
+        if !body_fields.is_empty() {
+            // Files can only be uploaded as a form
+            let media_type = match endpoint.content_type {
+                Some(ref media_type) => media_type.as_str(),
+                None if body_fields.iter().any(|f| f.field_type == FieldType::File) => {
+                    "multipart/form-data"
+                }
+                None => "application/json",
+            };
+            let schema = object_schema(&SchemaBlock {
+                fields: body_fields,
+                optional: request.optional,
+                spreads: Vec::new(),
+            });
+            op.insert(
+                "requestBody".to_string(),
+                json!({
+                    "required": !request.optional,
+                    "content": { media_type: { "schema": schema } }
+                }),
+            );
+        }
+    }
+
+    let description = endpoint
+        .name
+        .clone()
+        .unwrap_or_else(|| "Successful response".to_string());
+    let mut response = json!({ "description": description });
+    if endpoint.endpoint_type == EndpointType::Sse {
+        response["content"] = json!({ "text/event-stream": { "schema": { "type": "string" } } });
+    } else if let Some(ref schema) = endpoint.response {
+        response["content"] = json!({ "application/json": { "schema": object_schema(schema) } });
+    }
+    let mut responses = Map::new();
+    responses.insert(expected_status(endpoint), response);
+    for other in &endpoint.responses {
+        let mut response = json!({ "description": status_description(&other.status) });
+        if !other.schema.fields.is_empty() {
+            response["content"] =
+                json!({ "application/json": { "schema": object_schema(&other.schema) } });
+        }
+        responses.insert(other.status.clone(), response);
+    }
+    op.insert("responses".to_string(), Value::Object(responses));
+
+    Value::Object(op)
+}
+
+/// Status of `response 201 { ... }`, or from an `expect { status 201 }` assertion, 200
+/// otherwise
+fn expected_status(endpoint: &ApiEndpoint) -> String {
+    if let Some(ref status) = endpoint.status {
+        return status.clone();
+    }
+    endpoint
+        .expect
+        .iter()
+        .find(|e| e.subject == ExpectSubject::Status && e.op == ExpectOp::Eq)
+        .and_then(|e| e.value.as_ref())
+        .map(|v| v.to_json().to_string())
+        .unwrap_or_else(|| "200".to_string())
+}
+
+/// `Not Found` for `404`, OpenAPI requires a description for every response
+fn status_description(status: &str) -> String {
+    let reason = status
+        .parse::<u16>()
+        .ok()
+        .and_then(|code| reqwest::StatusCode::from_u16(code).ok())
+        .and_then(|code| code.canonical_reason());
+    match (reason, status) {
+        (Some(reason), _) => reason.to_string(),
+        (None, "default") => "Other responses".to_string(),
+        (None, range) => format!("{} responses", range),
+    }
+}
+
+/// camelCase id from the endpoint name, or from the method and path when it has none
+fn operation_id(endpoint: &ApiEndpoint, method: &str, ids: &mut HashSet<String>) -> String {
+    let source = match endpoint.name {
+        Some(ref name) => name.clone(),
+        None => format!("{} {}", method, endpoint.path),
+    };
+
+    let mut id = String::new();
+    for word in source
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            if id.is_empty() {
+                id.extend(first.to_lowercase());
+            } else {
+                id.extend(first.to_uppercase());
+            }
+            id.push_str(chars.as_str());
+        }
+    }
+
+    let mut unique = id.clone();
+    let mut n = 2;
+    while !ids.insert(unique.clone()) {
+        unique = format!("{}{}", id, n);
+        n += 1;
+    }
+    unique
+}
+
+fn export_parameter(field: &Field, location: &str) -> Value {
+    let mut schema = field_schema(field);
+    let mut param = json!({ "name": field.name, "in": location, "required": !field.optional });
+    if let Some(description) = schema.as_object_mut().and_then(|s| s.remove("description")) {
+        param["description"] = description;
+    }
+    param["schema"] = schema;
+    param
+}
+
+fn object_schema(schema: &SchemaBlock) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+
+    for field in &schema.fields {
+        properties.insert(field.name.clone(), field_schema(field));
+        let optional = field.optional || field.nested.as_ref().is_some_and(|n| n.optional);
+        if !optional {
+            required.push(json!(field.name));
+        }
+    }
+
+    let mut out = json!({ "type": "object", "properties": properties });
+    if !required.is_empty() {
+        out["required"] = Value::Array(required);
+    }
+    out
+}
+
+/// `@min`, `@max`, `@pattern`, `@format` and `@length`, the length of an array is its number
+/// of items
+fn export_constraints(field: &Field, schema: &mut Value) {
+    let constraints = &field.constraints;
+    let (min_length, max_length) = match field.field_type {
+        FieldType::Array => ("minItems", "maxItems"),
+        FieldType::Map => ("minProperties", "maxProperties"),
+        _ => ("minLength", "maxLength"),
+    };
+
+    if let Some(min) = constraints.min {
+        schema["minimum"] = MockValue::Number(min).to_json();
+    }
+    if let Some(max) = constraints.max {
+        schema["maximum"] = MockValue::Number(max).to_json();
+    }
+    if let Some(min) = constraints.min_length {
+        schema[min_length] = json!(min);
+    }
+    if let Some(max) = constraints.max_length {
+        schema[max_length] = json!(max);
+    }
+    if let Some(ref pattern) = constraints.pattern {
+        schema["pattern"] = json!(pattern);
+    }
+    if let Some(ref format) = constraints.format {
+        schema["format"] = json!(format);
+    }
+}
+
+/// OpenAPI 3.1 has no `nullable`, `null` is added to the types, the enum values or the
+/// alternatives of a `oneOf`
+fn export_nullable(schema: &mut Value) {
+    if let Some(values) = schema.get_mut("enum").and_then(Value::as_array_mut) {
+        values.push(Value::Null);
+    }
+    if let Some(Value::String(schema_type)) = schema.get("type") {
+        schema["type"] = json!([schema_type, "null"]);
+    } else if let Some(variants) = schema.get_mut("oneOf").and_then(Value::as_array_mut) {
+        variants.push(json!({ "type": "null" }));
+    }
+}
+
+/// Keys of a `Map` are strings, other key types are exported as a string format or pattern
+fn property_names(key_type: &FieldType) -> Option<Value> {
+    match key_type {
+        FieldType::Integer => Some(json!({ "pattern": INTEGER_KEY_PATTERN })),
+        FieldType::Uuid => Some(json!({ "format": "uuid" })),
+        FieldType::Date => Some(json!({ "format": "date" })),
+        _ => None,
+    }
+}
+
+fn field_schema(field: &Field) -> Value {
+    let mut schema = match field.field_type {
+        FieldType::String => json!({ "type": "string" }),
+        FieldType::Number => json!({ "type": "number" }),
+        FieldType::Integer => json!({ "type": "integer" }),
+        FieldType::Boolean => json!({ "type": "boolean" }),
+        FieldType::DateTime => json!({ "type": "string", "format": "date-time" }),
+        FieldType::Date => json!({ "type": "string", "format": "date" }),
+        FieldType::Uuid => json!({ "type": "string", "format": "uuid" }),
+        FieldType::File => json!({ "type": "string", "format": "binary" }),
+        FieldType::Array => match (&field.items, &field.nested) {
+            (Some(items), _) => json!({ "type": "array", "items": field_schema(items) }),
+            (None, Some(nested)) => json!({ "type": "array", "items": object_schema(nested) }),
+            (None, None) => json!({ "type": "array", "items": {} }),
+        },
+        FieldType::Object => match field.nested {
+            Some(ref nested) => object_schema(nested),
+            None => json!({ "type": "object" }),
+        },
+        FieldType::Map => {
+            let values = field.items.as_ref().map_or(json!({}), |items| field_schema(items));
+            let mut schema = json!({ "type": "object", "additionalProperties": values });
+            if let Some(keys) = field.key_type.as_ref().and_then(property_names) {
+                schema["propertyNames"] = keys;
+            }
+            schema
+        }
+        FieldType::Union => {
+            let variants = field.variants.iter().flatten().map(field_schema);
+            let mut schema = json!({ "oneOf": variants.collect::<Vec<_>>() });
+            if let Some(ref property) = field.discriminator {
+                schema["discriminator"] = json!({ "propertyName": property });
+            }
+            schema
+        }
+    };
+
+    if let Some(ref values) = field.enum_values {
+        schema["enum"] = values.iter().map(|v| v.to_json()).collect();
+    }
+    if field.nullable {
+        export_nullable(&mut schema);
+    }
+    export_constraints(field, &mut schema);
+    if let Some(ref comment) = field.comment {
+        schema["description"] = json!(comment);
+    }
+    if let Some(example) = field.example.as_ref().or(field.mock.as_ref()) {
+        schema["examples"] = json!([example.to_json()]);
+    }
+    schema
+}
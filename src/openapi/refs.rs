@@ -0,0 +1,250 @@
+//! `$ref` and `allOf` resolution for OpenAPI import.
+//!
+//! Runs on the raw document before it is read into the importer's structs: every `$ref`
+//! (local `#/components/...` pointers, relative files and URLs) is replaced by the value it
+//! points to, and `allOf` schemas are merged into one. A schema that refers back to itself
+//! is cut at the point where it recurses.
+
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Where a document was loaded from, relative `$ref`s are resolved against it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) enum Location {
+    File(PathBuf),
+    Url(String),
+}
+
+impl Location {
+    /// The document a relative reference such as `./schemas.yaml` points to
+    fn join(&self, reference: &str) -> Option<Location> {
+        if super::is_url(reference) {
+            return Some(Location::Url(reference.to_string()));
+        }
+        match self {
+            Location::File(path) => {
+                let dir = path.parent().unwrap_or(Path::new("."));
+                Some(Location::File(dir.join(reference)))
+            }
+            Location::Url(base) => {
+                let url = reqwest::Url::parse(base).and_then(|u| u.join(reference)).ok()?;
+                Some(Location::Url(url.to_string()))
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Location::File(path) => path.display().to_string(),
+            Location::Url(url) => url.clone(),
+        }
+    }
+
+    /// Read and parse the document
+    pub(super) fn load(&self) -> Result<Value, Box<dyn std::error::Error>> {
+        match self {
+            Location::File(path) => {
+                let content = fs::read_to_string(path)?;
+                let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+                super::parse_document(&content, ext)
+            }
+            Location::Url(url) => {
+                let (content, ext) = super::fetch(url)?;
+                super::parse_document(&content, ext)
+            }
+        }
+    }
+}
+
+/// Inline every `$ref` of `document` and merge its `allOf` schemas
+pub(super) fn resolve(document: Value, location: &Location) -> Value {
+    let mut resolver = Resolver {
+        documents: HashMap::from([(location.clone(), document.clone())]),
+        stack: Vec::new(),
+    };
+    resolver.value(&document, location)
+}
+
+struct Resolver {
+    /// Documents by location, each file is read once. `Null` when it could not be read.
+    documents: HashMap<Location, Value>,
+    /// References being expanded, one found again inside itself is recursive
+    stack: Vec<String>,
+}
+
+impl Resolver {
+    fn value(&mut self, value: &Value, location: &Location) -> Value {
+        match value {
+            Value::Object(object) => {
+                if let Some(Value::String(reference)) = object.get("$ref") {
+                    let mut resolved = self.reference(reference, location);
+                    // OpenAPI 3.1 allows keys next to `$ref`, such as a `description`
+                    if let Value::Object(ref mut target) = resolved {
+                        for (key, sibling) in object.iter().filter(|(k, _)| *k != "$ref") {
+                            target.insert(key.clone(), self.value(sibling, location));
+                        }
+                    }
+                    return resolved;
+                }
+
+                let mut resolved: Map<String, Value> = object
+                    .iter()
+                    .map(|(key, value)| (key.clone(), self.value(value, location)))
+                    .collect();
+                tag_variants(object, &mut resolved);
+                match resolved.remove("allOf") {
+                    Some(Value::Array(parts)) => Value::Object(merge_all_of(parts, resolved)),
+                    _ => Value::Object(resolved),
+                }
+            }
+            Value::Array(items) => {
+                Value::Array(items.iter().map(|v| self.value(v, location)).collect())
+            }
+            other => other.clone(),
+        }
+    }
+
+    fn reference(&mut self, reference: &str, location: &Location) -> Value {
+        let (file, pointer) = reference.split_once('#').unwrap_or((reference, ""));
+        let target = if file.is_empty() {
+            Some(location.clone())
+        } else {
+            location.join(file)
+        };
+        let Some(target) = target else {
+            warn!("Cannot resolve $ref `{}` from {}", reference, location.describe());
+            return json!({});
+        };
+
+        let key = format!("{}#{}", target.describe(), pointer);
+        if self.stack.contains(&key) {
+            return json!({
+                "type": "object",
+                "description": format!("Recursive reference to {}", reference)
+            });
+        }
+
+        let document = self.documents.entry(target.clone()).or_insert_with(|| {
+            target.load().unwrap_or_else(|e| {
+                warn!("Failed to load {} for $ref `{}`: {}", target.describe(), reference, e);
+                Value::Null
+            })
+        });
+        if document.is_null() {
+            return json!({});
+        }
+        let Some(value) = document.pointer(&decode_pointer(pointer)).cloned() else {
+            warn!("$ref `{}` points to nothing in {}", reference, target.describe());
+            return json!({});
+        };
+
+        self.stack.push(key);
+        let resolved = self.value(&value, &target);
+        self.stack.pop();
+        resolved
+    }
+}
+
+/// JSON pointer of a `$ref` fragment, which may be percent-encoded, e.g. `/paths/~1users%7Bid%7D`
+fn decode_pointer(fragment: &str) -> String {
+    let bytes = fragment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = fragment.get(i + 1..i + 3).filter(|_| bytes[i] == b'%');
+        match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Give the variants of a `oneOf` with a `discriminator` the value that selects them, taken
+/// from `mapping` or the name of the referenced schema, as a single-value `enum` of the
+/// property. The importer reads it back as the variant's discriminator value.
+fn tag_variants(original: &Map<String, Value>, resolved: &mut Map<String, Value>) {
+    let Some(property) = original
+        .get("discriminator")
+        .and_then(|d| d.get("propertyName"))
+        .and_then(Value::as_str)
+    else {
+        return;
+    };
+    let mapping = original.get("discriminator").and_then(|d| d.get("mapping"));
+    let key = if original.contains_key("oneOf") { "oneOf" } else { "anyOf" };
+
+    let references = original.get(key).and_then(Value::as_array);
+    let variants = resolved.get_mut(key).and_then(Value::as_array_mut);
+    let (Some(references), Some(variants)) = (references, variants) else {
+        return;
+    };
+    for (reference, variant) in references.iter().zip(variants.iter_mut()) {
+        let Some(reference) = reference.get("$ref").and_then(Value::as_str) else {
+            continue;
+        };
+        let mapped = mapping.and_then(Value::as_object).and_then(|m| {
+            m.iter()
+                .find(|(_, target)| target.as_str() == Some(reference))
+                .map(|(value, _)| value.clone())
+        });
+        let name = reference.rsplit('/').next().unwrap_or(reference);
+        let tag = mapped.unwrap_or_else(|| name.to_string());
+
+        let Some(variant) = variant.as_object_mut() else {
+            continue;
+        };
+        let properties = variant
+            .entry("properties")
+            .or_insert_with(|| json!({}))
+            .as_object_mut();
+        if let Some(properties) = properties {
+            let schema = properties
+                .entry(property)
+                .or_insert_with(|| json!({ "type": "string" }));
+            let fixed = schema.get("enum").is_some() || schema.get("const").is_some();
+            if let (false, Some(schema)) = (fixed, schema.as_object_mut()) {
+                schema.insert("enum".to_string(), json!([tag]));
+            }
+        }
+    }
+}
+
+/// Merge the schemas of an `allOf` and the keys next to it into one schema. Properties are
+/// kept in order, `required` lists are joined, and for other keys the last schema wins.
+fn merge_all_of(parts: Vec<Value>, own: Map<String, Value>) -> Map<String, Value> {
+    let mut merged = Map::new();
+    let schemas = parts.into_iter().filter_map(|part| match part {
+        Value::Object(object) => Some(object),
+        _ => None,
+    });
+    for schema in schemas.chain(std::iter::once(own)) {
+        for (key, value) in schema {
+            match (key.as_str(), merged.get_mut(&key), value) {
+                ("properties", Some(Value::Object(properties)), Value::Object(more)) => {
+                    properties.extend(more);
+                }
+                ("required", Some(Value::Array(required)), Value::Array(more)) => {
+                    for name in more {
+                        if !required.contains(&name) {
+                            required.push(name);
+                        }
+                    }
+                }
+                (_, _, value) => {
+                    merged.insert(key, value);
+                }
+            }
+        }
+    }
+    merged
+}
@@ -0,0 +1,236 @@
+//! Security schemes for OpenAPI import.
+//!
+//! Each scheme of `components.securitySchemes` becomes an `auth` block whose credentials are
+//! config variables named after the scheme, e.g. `auth bearer { token "{bearerAuth}" }`. The
+//! scheme the whole document requires is the `auth` of the imported category, operations that
+//! require another scheme get their own and `security: []` gets `auth none`. API keys required
+//! next to it get a `@header` / `@params` / `@cookie` field whose example refers to the variable.
+
+use crate::parser::{
+    AuthBlock, AuthScheme, Field, FieldType, MockValue, ParamLocation, VariableDefinition,
+};
+use indexmap::IndexMap;
+use serde::Deserialize;
+
+/// Alternatives of a `security` requirement, each naming schemes that are used together
+pub(super) type SecurityRequirements = Vec<IndexMap<String, Vec<String>>>;
+
+#[derive(Deserialize)]
+pub(super) struct SecurityScheme {
+    #[serde(rename = "type")]
+    scheme_type: Option<String>,
+    /// `bearer` or `basic` for `http` schemes
+    scheme: Option<String>,
+    /// Header, query parameter or cookie of an `apiKey` scheme
+    name: Option<String>,
+    #[serde(rename = "in")]
+    location: Option<String>,
+}
+
+/// How the credentials of a scheme are sent
+struct Credential {
+    /// Properties refer to the variables, e.g. `token "{bearerAuth}"`
+    auth: AuthBlock,
+    /// Variables holding the credentials
+    variables: Vec<String>,
+}
+
+impl Credential {
+    fn new(key: &str, scheme: &SecurityScheme) -> Option<Credential> {
+        let variable = variable_name(key);
+        let credential =
+            |scheme: AuthScheme, properties: &[(&str, &str)], variables: Vec<String>| Credential {
+                auth: AuthBlock {
+                    scheme,
+                    properties: properties
+                        .iter()
+                        .map(|(name, value)| (name.to_string(), value.to_string()))
+                        .collect(),
+                },
+                variables,
+            };
+        let bearer = || {
+            let token = format!("{{{}}}", variable);
+            credential(
+                AuthScheme::Bearer,
+                &[("token", &token)],
+                vec![variable.clone()],
+            )
+        };
+        match scheme.scheme_type.as_deref()? {
+            "http" => match scheme.scheme.as_deref()?.to_lowercase().as_str() {
+                "bearer" => Some(bearer()),
+                // The auth block encodes `username:password`
+                "basic" => {
+                    let username = format!("{}_username", variable);
+                    let password = format!("{}_password", variable);
+                    Some(credential(
+                        AuthScheme::Basic,
+                        &[
+                            ("username", &format!("{{{}}}", username)),
+                            ("password", &format!("{{{}}}", password)),
+                        ],
+                        vec![username, password],
+                    ))
+                }
+                _ => None,
+            },
+            "oauth2" | "openIdConnect" => Some(bearer()),
+            "apiKey" => {
+                let location = scheme.location.as_deref()?;
+                if !matches!(location, "header" | "query" | "cookie") {
+                    return None;
+                }
+                let value = format!("{{{}}}", variable);
+                Some(credential(
+                    AuthScheme::ApiKey,
+                    &[
+                        ("name", scheme.name.as_deref()?),
+                        ("value", &value),
+                        ("in", location),
+                    ],
+                    vec![variable.clone()],
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether it is sent in the `Authorization` header
+    fn is_authorization(&self) -> bool {
+        matches!(self.auth.scheme, AuthScheme::Bearer | AuthScheme::Basic)
+    }
+
+    /// A field sending an API key next to the auth of an operation, `None` for the other
+    /// schemes, an operation sends one `Authorization` header
+    fn field(&self, scheme: &str) -> Option<Field> {
+        if self.auth.scheme != AuthScheme::ApiKey {
+            return None;
+        }
+        let mut field = Field::element(self.auth.get("name")?, FieldType::String);
+        field.optional = false;
+        field.comment = Some(format!("`{}` security scheme", scheme));
+        field.example = Some(MockValue::String(self.auth.get("value")?.to_string()));
+        match self.auth.get("in")? {
+            "header" => field.location = Some(ParamLocation::Header),
+            "cookie" => field.location = Some(ParamLocation::Cookie),
+            _ => field.is_params = true,
+        }
+        Some(field)
+    }
+}
+
+/// The security schemes of a document and the ones it requires by default
+pub(super) struct Security {
+    credentials: IndexMap<String, Credential>,
+    /// Schemes required by operations without their own `security`
+    required: Vec<String>,
+}
+
+impl Security {
+    pub(super) fn new(
+        schemes: Option<&IndexMap<String, SecurityScheme>>,
+        required: Option<&SecurityRequirements>,
+    ) -> Security {
+        let credentials = schemes
+            .into_iter()
+            .flatten()
+            .filter_map(|(key, scheme)| Some((key.clone(), Credential::new(key, scheme)?)))
+            .collect();
+        Security {
+            credentials,
+            required: first_alternative(required),
+        }
+    }
+
+    /// The variables holding the credentials of every scheme
+    pub(super) fn variables(&self) -> Vec<VariableDefinition> {
+        self.credentials
+            .values()
+            .flat_map(|credential| credential.variables.iter())
+            .map(|name| VariableDefinition {
+                name: name.clone(),
+                var_type: "String".to_string(),
+                default_value: None,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// `auth` of the operations without their own `security`
+    pub(super) fn default_auth(&self) -> Option<AuthBlock> {
+        self.auth_of(&self.required)
+    }
+
+    /// `auth` of an operation when it differs from the default one, `auth none` when it
+    /// requires none, and the fields of the API keys it requires next to it
+    pub(super) fn operation(
+        &self,
+        requirements: Option<&SecurityRequirements>,
+    ) -> (Option<AuthBlock>, Vec<Field>) {
+        let required = match requirements {
+            Some(_) => first_alternative(requirements),
+            None => self.required.clone(),
+        };
+
+        let default = self.default_auth();
+        let auth = if required.is_empty() {
+            default.map(|_| AuthBlock {
+                scheme: AuthScheme::None,
+                properties: IndexMap::new(),
+            })
+        } else {
+            self.auth_of(&required)
+                .filter(|auth| Some(auth) != default.as_ref())
+        };
+
+        let primary = self.primary(&required);
+        let fields = required
+            .iter()
+            .filter(|key| Some(key.as_str()) != primary)
+            .filter_map(|key| self.credentials.get(key)?.field(key))
+            .collect();
+        (auth, fields)
+    }
+
+    fn auth_of(&self, required: &[String]) -> Option<AuthBlock> {
+        let key = self.primary(required)?;
+        Some(self.credentials.get(key)?.auth.clone())
+    }
+
+    /// The scheme sent by the `auth` block among schemes required together, the first one
+    /// sent in the `Authorization` header, otherwise the first one
+    fn primary<'a>(&self, required: &'a [String]) -> Option<&'a str> {
+        let known = || {
+            required
+                .iter()
+                .filter(|key| self.credentials.contains_key(*key))
+        };
+        known()
+            .find(|key| self.credentials[*key].is_authorization())
+            .or_else(|| known().next())
+            .map(String::as_str)
+    }
+}
+
+/// Schemes of the first alternative, none for `security: []` and `- {}`
+fn first_alternative(requirements: Option<&SecurityRequirements>) -> Vec<String> {
+    requirements
+        .and_then(|alternatives| alternatives.first())
+        .map(|schemes| schemes.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Variable name for a scheme such as `api-key`, names are letters, digits and `_`
+fn variable_name(scheme: &str) -> String {
+    scheme
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
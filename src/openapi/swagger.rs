@@ -0,0 +1,339 @@
+//! Swagger 2.0 support for OpenAPI import.
+//!
+//! A `swagger: "2.0"` document is rewritten into the OpenAPI 3 shape the importer reads,
+//! after its `$ref`s are resolved: `host`, `basePath` and `schemes` become `servers`, `in: body`
+//! and `formData` parameters become a `requestBody`, and response schemas move under
+//! `content`, with the media types of `consumes` and `produces`. `securityDefinitions` become
+//! `components.securitySchemes`.
+
+use serde_json::{json, Map, Value};
+
+/// Media type of bodies when the document declares no `consumes` / `produces`
+const DEFAULT_MEDIA_TYPE: &str = "application/json";
+
+/// Keys of a Swagger 2.0 parameter that describe its value, they move to its `schema`
+const SCHEMA_KEYS: &[&str] = &[
+    "type",
+    "format",
+    "items",
+    "enum",
+    "default",
+    "minimum",
+    "maximum",
+    "minLength",
+    "maxLength",
+    "pattern",
+    "minItems",
+    "maxItems",
+    "x-nullable",
+];
+
+/// Whether the document is Swagger 2.0 rather than OpenAPI 3
+pub(super) fn is_swagger(document: &Value) -> bool {
+    document
+        .get("swagger")
+        .and_then(Value::as_str)
+        .is_some_and(|version| version.starts_with('2'))
+}
+
+/// Rewrite a Swagger 2.0 document, whose `$ref`s are already resolved, as OpenAPI 3
+pub(super) fn to_openapi(document: &Value) -> Value {
+    let consumes = document_media_types(document.get("consumes"));
+    let produces = document_media_types(document.get("produces"));
+
+    let mut paths = Map::new();
+    for (path, item) in document
+        .get("paths")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+    {
+        let Some(item) = item.as_object() else {
+            continue;
+        };
+        let mut converted = Map::new();
+        for (key, value) in item {
+            if key == "parameters" {
+                let parameters = value.as_array().map(Vec::as_slice).unwrap_or_default();
+                converted.insert(key.clone(), Value::Array(plain_parameters(parameters)));
+            } else if let Some(operation) = value.as_object() {
+                converted.insert(
+                    key.clone(),
+                    operation_to_openapi(operation, &consumes, &produces),
+                );
+            }
+        }
+        paths.insert(path.clone(), Value::Object(converted));
+    }
+
+    let mut openapi = json!({ "openapi": "3.0.0", "paths": paths });
+    let servers = servers(document);
+    if !servers.is_empty() {
+        openapi["servers"] = Value::Array(servers);
+    }
+    if let Some(definitions) = document.get("securityDefinitions").and_then(Value::as_object) {
+        let schemes: Map<String, Value> = definitions
+            .iter()
+            .map(|(name, definition)| (name.clone(), security_scheme(definition)))
+            .collect();
+        openapi["components"] = json!({ "securitySchemes": schemes });
+    }
+    if let Some(security) = document.get("security") {
+        openapi["security"] = security.clone();
+    }
+    openapi
+}
+
+/// `type: basic` is an `http` scheme in OpenAPI 3, `apiKey` and `oauth2` keep their type
+fn security_scheme(definition: &Value) -> Value {
+    match definition.get("type").and_then(Value::as_str) {
+        Some("basic") => json!({ "type": "http", "scheme": "basic" }),
+        _ => definition.clone(),
+    }
+}
+
+/// A server for each of `schemes`, `https` when there are none
+fn servers(document: &Value) -> Vec<Value> {
+    let base_path = document
+        .get("basePath")
+        .and_then(Value::as_str)
+        .unwrap_or("");
+    let base_path = base_path.trim_end_matches('/');
+    let Some(host) = document.get("host").and_then(Value::as_str) else {
+        // Without a host the API is served from wherever the document is
+        return if base_path.is_empty() {
+            Vec::new()
+        } else {
+            vec![json!({ "url": base_path })]
+        };
+    };
+
+    let schemes: Vec<&str> = document
+        .get("schemes")
+        .and_then(Value::as_array)
+        .map(|s| s.iter().filter_map(Value::as_str).collect())
+        .filter(|s: &Vec<&str>| !s.is_empty())
+        .unwrap_or_else(|| vec!["https"]);
+    schemes
+        .into_iter()
+        .filter(|scheme| matches!(*scheme, "http" | "https"))
+        .map(|scheme| json!({ "url": format!("{}://{}{}", scheme, host, base_path) }))
+        .collect()
+}
+
+fn operation_to_openapi(
+    operation: &Map<String, Value>,
+    consumes: &[String],
+    produces: &[String],
+) -> Value {
+    let consumes = match media_types(operation.get("consumes")) {
+        own if own.is_empty() => consumes.to_vec(),
+        own => own,
+    };
+    let produces = match media_types(operation.get("produces")) {
+        own if own.is_empty() => produces.to_vec(),
+        own => own,
+    };
+
+    let mut converted = Map::new();
+    for (key, value) in operation {
+        match key.as_str() {
+            "parameters" | "responses" | "consumes" | "produces" => {}
+            _ => {
+                converted.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    let parameters = operation
+        .get("parameters")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    converted.insert(
+        "parameters".to_string(),
+        Value::Array(plain_parameters(parameters)),
+    );
+    if let Some(body) = request_body(parameters, &consumes) {
+        converted.insert("requestBody".to_string(), body);
+    }
+
+    let mut responses = Map::new();
+    for (status, response) in operation
+        .get("responses")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+    {
+        let mut converted_response = json!({
+            "description": response.get("description").cloned().unwrap_or(json!(""))
+        });
+        if let Some(schema) = response.get("schema") {
+            let content: Map<String, Value> = produces
+                .iter()
+                .map(|media_type| {
+                    (
+                        media_type.clone(),
+                        json!({ "schema": schema_to_openapi(schema) }),
+                    )
+                })
+                .collect();
+            converted_response["content"] = Value::Object(content);
+        }
+        if let Some(headers) = response.get("headers") {
+            converted_response["headers"] = headers.clone();
+        }
+        responses.insert(status.clone(), converted_response);
+    }
+    converted.insert("responses".to_string(), Value::Object(responses));
+
+    Value::Object(converted)
+}
+
+/// Query, path, header and cookie parameters, with their type moved to a `schema`
+fn plain_parameters(parameters: &[Value]) -> Vec<Value> {
+    parameters
+        .iter()
+        .filter_map(Value::as_object)
+        .filter(|p| !matches!(location(p), "body" | "formData"))
+        .map(|parameter| {
+            let mut converted = Map::new();
+            let mut schema = Map::new();
+            for (key, value) in parameter {
+                if SCHEMA_KEYS.contains(&key.as_str()) {
+                    schema.insert(key.clone(), value.clone());
+                } else {
+                    converted.insert(key.clone(), value.clone());
+                }
+            }
+            converted.insert(
+                "schema".to_string(),
+                schema_to_openapi(&Value::Object(schema)),
+            );
+            Value::Object(converted)
+        })
+        .collect()
+}
+
+/// The `in: body` parameter, or the `formData` parameters as the properties of a form
+fn request_body(parameters: &[Value], consumes: &[String]) -> Option<Value> {
+    let parameters: Vec<&Map<String, Value>> =
+        parameters.iter().filter_map(Value::as_object).collect();
+
+    if let Some(body) = parameters.iter().find(|p| location(p) == "body") {
+        let schema = schema_to_openapi(body.get("schema").unwrap_or(&json!({})));
+        let content: Map<String, Value> = consumes
+            .iter()
+            .map(|media_type| (media_type.clone(), json!({ "schema": schema })))
+            .collect();
+        let mut request_body = json!({
+            "content": content,
+            "required": body.get("required").cloned().unwrap_or(json!(false)),
+        });
+        if let Some(description) = body.get("description") {
+            request_body["description"] = description.clone();
+        }
+        return Some(request_body);
+    }
+
+    let fields: Vec<&&Map<String, Value>> = parameters
+        .iter()
+        .filter(|p| location(p) == "formData")
+        .collect();
+    if fields.is_empty() {
+        return None;
+    }
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for field in &fields {
+        let Some(name) = field.get("name").and_then(Value::as_str) else {
+            continue;
+        };
+        let mut schema: Map<String, Value> = field
+            .iter()
+            .filter(|(key, _)| SCHEMA_KEYS.contains(&key.as_str()))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        if let Some(description) = field.get("description") {
+            schema.insert("description".to_string(), description.clone());
+        }
+        properties.insert(name.to_string(), schema_to_openapi(&Value::Object(schema)));
+        if field.get("required").and_then(Value::as_bool) == Some(true) {
+            required.push(json!(name));
+        }
+    }
+
+    let has_files = fields
+        .iter()
+        .any(|f| f.get("type").and_then(Value::as_str) == Some("file"));
+    let media_type = if has_files || consumes.iter().any(|c| c == "multipart/form-data") {
+        "multipart/form-data"
+    } else {
+        "application/x-www-form-urlencoded"
+    };
+    let mut schema = json!({ "type": "object", "properties": properties });
+    if !required.is_empty() {
+        schema["required"] = Value::Array(required);
+    }
+    Some(json!({ "content": { media_type: { "schema": schema } } }))
+}
+
+/// Swagger 2.0 schemas are OpenAPI 3.0 schemas, except for `type: file` and `x-nullable`
+fn schema_to_openapi(schema: &Value) -> Value {
+    match schema {
+        Value::Object(object) => {
+            let mut converted = Map::new();
+            for (key, value) in object {
+                match (key.as_str(), value) {
+                    ("type", Value::String(t)) if t == "file" => {
+                        converted.insert("type".to_string(), json!("string"));
+                        converted.insert("format".to_string(), json!("binary"));
+                    }
+                    ("x-nullable", nullable) => {
+                        converted.insert("nullable".to_string(), nullable.clone());
+                    }
+                    // A property named `type` or `x-nullable` is not a keyword
+                    ("properties", Value::Object(properties)) => {
+                        let properties = properties
+                            .iter()
+                            .map(|(name, property)| (name.clone(), schema_to_openapi(property)))
+                            .collect();
+                        converted.insert(key.clone(), Value::Object(properties));
+                    }
+                    _ => {
+                        converted.insert(key.clone(), schema_to_openapi(value));
+                    }
+                }
+            }
+            Value::Object(converted)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(schema_to_openapi).collect()),
+        other => other.clone(),
+    }
+}
+
+fn location(parameter: &Map<String, Value>) -> &str {
+    parameter.get("in").and_then(Value::as_str).unwrap_or("")
+}
+
+/// The media types listed in `consumes` / `produces`
+fn media_types(value: Option<&Value>) -> Vec<String> {
+    value
+        .and_then(Value::as_array)
+        .map(|types| {
+            types
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `consumes` / `produces` of the document, JSON when it declares none
+fn document_media_types(value: Option<&Value>) -> Vec<String> {
+    match media_types(value) {
+        types if types.is_empty() => vec![DEFAULT_MEDIA_TYPE.to_string()],
+        types => types,
+    }
+}
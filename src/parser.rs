@@ -1,15 +1,27 @@
 mod ast;
+mod commands;
+pub mod cst;
+mod diagnostic;
 mod lexer;
+mod resolve;
+pub mod secrets;
 
 pub use ast::*;
-pub use lexer::Lexer;
+pub use diagnostic::*;
+pub use lexer::{Lexer, TokenType};
+pub use resolve::{resolve_types, resolve_types_with};
 
+use indexmap::IndexMap;
 use std::fs;
 use std::path::Path;
 
 pub struct Parser {
     lexer: Lexer,
     current_token: lexer::Token,
+    warnings: Vec<ParseWarning>,
+    errors: Vec<SyntaxError>,
+    /// Number of `{` consumed and not closed yet
+    depth: usize,
 }
 
 impl Parser {
@@ -19,9 +31,17 @@ impl Parser {
         Self {
             lexer,
             current_token,
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            depth: 0,
         }
     }
 
+    /// Problems that did not stop parsing, such as unknown keywords or types
+    pub fn warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+
     #[allow(dead_code)]
     pub fn parse_file(path: &Path) -> Result<RqcConfig, ParseError> {
         let content = fs::read_to_string(path)?;
@@ -30,20 +50,149 @@ impl Parser {
     }
 
     fn next_token(&mut self) {
+        match self.current_token.token_type {
+            lexer::TokenType::LBrace => self.depth += 1,
+            lexer::TokenType::RBrace => self.depth = self.depth.saturating_sub(1),
+            _ => {}
+        }
         self.current_token = self.lexer.next_token();
     }
 
-    fn expect(&mut self, expected: lexer::TokenType) -> Result<String, ParseError> {
+    fn warn(&mut self, kind: WarningKind, message: String, span: Span) {
+        self.warnings.push(ParseWarning {
+            kind,
+            message,
+            span,
+        });
+    }
+
+    /// Syntax error pointing at the current token
+    fn error(&self, message: String) -> ParseError {
+        ParseError::Syntax(vec![SyntaxError {
+            message,
+            span: self.current_token.span(),
+        }])
+    }
+
+    /// Record a syntax error and skip past the block it happened in, so that parsing
+    /// can go on with the next item of the enclosing block at `depth`
+    fn recover<T>(
+        &mut self,
+        result: Result<T, ParseError>,
+        depth: usize,
+    ) -> Result<Option<T>, ParseError> {
+        let errors = match result {
+            Ok(value) => return Ok(Some(value)),
+            Err(ParseError::Syntax(errors)) => errors,
+            Err(e) => return Err(e),
+        };
+
+        for error in errors {
+            let seen = self
+                .errors
+                .iter()
+                .any(|e| e.span == error.span && e.message == error.message);
+            if !seen {
+                self.errors.push(error);
+            }
+        }
+
+        if self.depth > depth {
+            while self.depth > depth && self.current_token.token_type != lexer::TokenType::Eof {
+                self.next_token();
+            }
+        } else {
+            // The error happened before the block was opened, drop the rest of the line
+            let line = self.current_token.line;
+            while self.current_token.line == line {
+                match self.current_token.token_type {
+                    lexer::TokenType::RBrace | lexer::TokenType::Eof => break,
+                    lexer::TokenType::LBrace => {
+                        let _ = self.skip_block();
+                        break;
+                    }
+                    _ => self.next_token(),
+                }
+            }
+        }
+
+        if self.current_token.token_type == lexer::TokenType::Eof && depth > 0 {
+            // Nothing left to recover in, unwind to the top level (errors are already recorded)
+            return Err(ParseError::Syntax(Vec::new()));
+        }
+        Ok(None)
+    }
+
+    /// Skip a statement the parser does not understand, recording it unless it is a comment.
+    /// The rest of the line and any `{ ... }` body after the unknown keyword are skipped too.
+    fn skip_unknown(&mut self, context: &str) -> Result<(), ParseError> {
+        match self.current_token.token_type {
+            lexer::TokenType::Eof => return Err(self.unexpected_eof()),
+            lexer::TokenType::Comment | lexer::TokenType::DocComment => {
+                self.next_token();
+                return Ok(());
+            }
+            _ => self.warn(
+                WarningKind::Keyword,
+                format!(
+                    "unknown keyword `{}` in {}",
+                    self.current_token.literal, context
+                ),
+                self.current_token.span(),
+            ),
+        }
+
+        let line = self.current_token.line;
+        self.next_token();
+
+        while self.current_token.line == line {
+            match self.current_token.token_type {
+                lexer::TokenType::RBrace | lexer::TokenType::Eof => break,
+                lexer::TokenType::LBrace => {
+                    self.skip_block()?;
+                    break;
+                }
+                _ => self.next_token(),
+            }
+        }
+        Ok(())
+    }
+
+    /// Skip a balanced `{ ... }` block starting at the current `{`
+    fn skip_block(&mut self) -> Result<(), ParseError> {
+        let mut depth = 0;
+        loop {
+            match self.current_token.token_type {
+                lexer::TokenType::LBrace => depth += 1,
+                lexer::TokenType::RBrace => depth -= 1,
+                lexer::TokenType::Eof => return Err(self.unexpected_eof()),
+                _ => {}
+            }
+            self.next_token();
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    fn unexpected_eof(&self) -> ParseError {
+        self.error("unexpected end of file, expected `}` to close the block".to_string())
+    }
+
+    /// Consume a token of the given type, `context` says where it was expected,
+    /// e.g. "after api path"
+    fn expect(&mut self, expected: lexer::TokenType, context: &str) -> Result<String, ParseError> {
         if self.current_token.token_type == expected {
             let value = self.current_token.literal.clone();
             self.next_token();
             Ok(value)
         } else {
-            Err(ParseError::UnexpectedToken {
-                expected: format!("{:?}", expected),
-                got: format!("{:?}", self.current_token.token_type),
-                line: self.current_token.line,
-            })
+            Err(self.error(format!(
+                "expected {} {}, found {}",
+                expected.describe(),
+                context,
+                self.current_token.describe()
+            )))
         }
     }
 
@@ -52,58 +201,56 @@ impl Parser {
         let mut category_counter = 0;
 
         while self.current_token.token_type != lexer::TokenType::Eof {
-            match self.current_token.literal.as_str() {
-                "config" => {
-                    config.config = Some(self.parse_config_block()?);
-                }
-                "api" => {
-                    config.apis.push(self.parse_api_block()?);
-                }
-                "ws" => {
-                    config.ws_apis.push(self.parse_ws_block()?);
-                }
-                "socketio" => {
-                    config.socketio_apis.push(self.parse_ws_block()?);
-                }
-                "sse" => {
-                    config.sse_apis.push(self.parse_sse_block()?);
-                }
-                "import" => {
-                    config.imports.push(self.parse_import()?);
-                }
-                "category" => {
-                    config
-                        .categories
-                        .push(self.parse_category_block(&mut category_counter)?);
-                }
-                _ => {
-                    self.next_token();
-                }
+            if self.current_token.token_type == lexer::TokenType::RBrace {
+                self.errors.push(SyntaxError {
+                    message: "unexpected `}` without a matching `{`".to_string(),
+                    span: self.current_token.span(),
+                });
+                self.next_token();
+                continue;
             }
+
+            let depth = self.depth;
+            let result = match self.current_token.literal.as_str() {
+                "config" => self
+                    .parse_config_block()
+                    .map(|block| config.config = Some(block)),
+                "api" => self.parse_api_block().map(|api| config.apis.push(api)),
+                "ws" => self.parse_ws_block().map(|ws| config.ws_apis.push(ws)),
+                "socketio" => self
+                    .parse_ws_block()
+                    .map(|ws| config.socketio_apis.push(ws)),
+                "sse" => self.parse_sse_block().map(|sse| config.sse_apis.push(sse)),
+                "import" => self.parse_import().map(|path| config.imports.push(path)),
+                "type" => self
+                    .parse_type_definition()
+                    .map(|definition| config.types.push(definition)),
+                "enum" => self
+                    .parse_enum_definition()
+                    .map(|definition| config.enums.push(definition)),
+                "category" => self
+                    .parse_category_block(&mut category_counter)
+                    .map(|category| config.categories.push(category)),
+                _ => self.skip_unknown("file"),
+            };
+            self.recover(result, depth)?;
         }
 
+        if !self.errors.is_empty() {
+            return Err(ParseError::Syntax(std::mem::take(&mut self.errors)));
+        }
         Ok(config)
     }
 
     fn parse_config_block(&mut self) -> Result<ConfigBlock, ParseError> {
         self.next_token(); // skip 'config'
-        self.expect(lexer::TokenType::LBrace)?;
+        self.expect(lexer::TokenType::LBrace, "after `config`")?;
 
         let mut config = ConfigBlock::default();
 
         while self.current_token.token_type != lexer::TokenType::RBrace {
             match self.current_token.literal.as_str() {
-                "baseUrl" => {
-                    self.next_token();
-                    // Parse comma-separated URLs
-                    let urls_str = self.current_token.literal.clone();
-                    config.base_urls = urls_str
-                        .split(',')
-                        .map(|s| s.trim().to_string())
-                        .filter(|s| !s.is_empty())
-                        .collect();
-                    self.next_token();
-                }
+                "baseUrl" => config.base_urls = self.parse_base_urls(),
                 "cors" => {
                     self.next_token();
                     config.cors = self.current_token.literal == "true";
@@ -115,21 +262,105 @@ impl Parser {
                     self.next_token();
                 }
                 "variable" => {
-                    config.variables.push(self.parse_variable_definition()?);
+                    let depth = self.depth;
+                    let result = self.parse_variable_definition();
+                    if let Some(variable) = self.recover(result, depth)? {
+                        config.variables.push(variable);
+                    }
                 }
                 "header" => {
-                    config.headers.push(self.parse_header_definition()?);
+                    let depth = self.depth;
+                    let result = self.parse_header_definition();
+                    if let Some(header) = self.recover(result, depth)? {
+                        config.headers.push(header);
+                    }
+                }
+                "auth" => {
+                    let depth = self.depth;
+                    let result = self.parse_auth_block();
+                    config.auth = self.recover(result, depth)?;
+                }
+                "env" => {
+                    let depth = self.depth;
+                    let span = self.current_token.span();
+                    let result = self.parse_env_block();
+                    if let Some(env) = self.recover(result, depth)? {
+                        if config.envs.iter().any(|e| e.name == env.name) {
+                            self.warn(
+                                WarningKind::Keyword,
+                                format!("env `{}` is declared more than once", env.name),
+                                span,
+                            );
+                        }
+                        config.envs.push(env);
+                    }
                 }
                 _ => {
-                    self.next_token();
+                    self.skip_unknown("config block")?;
                 }
             }
         }
 
-        self.expect(lexer::TokenType::RBrace)?;
+        self.expect(lexer::TokenType::RBrace, "to close config block")?;
         Ok(config)
     }
 
+    /// `baseUrl http://localhost:3000, https://api.example.com`
+    fn parse_base_urls(&mut self) -> Vec<String> {
+        self.next_token(); // skip 'baseUrl'
+        // Parse comma-separated URLs, a space after the comma splits them into several tokens
+        let mut urls_str = self.current_token.literal.clone();
+        self.next_token();
+        while urls_str.ends_with(',') && self.current_token.token_type == lexer::TokenType::Ident
+        {
+            urls_str.push_str(&self.current_token.literal);
+            self.next_token();
+        }
+        urls_str
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+
+    /// `env staging { baseUrl ... variable ... header ... }` inside the config block
+    fn parse_env_block(&mut self) -> Result<EnvBlock, ParseError> {
+        self.next_token(); // skip 'env'
+        let name = self.expect(lexer::TokenType::Ident, "after `env`")?;
+        self.expect(lexer::TokenType::LBrace, "after env name")?;
+
+        let mut env = EnvBlock {
+            name,
+            ..EnvBlock::default()
+        };
+
+        while self.current_token.token_type != lexer::TokenType::RBrace {
+            match self.current_token.literal.as_str() {
+                "baseUrl" => env.base_urls = self.parse_base_urls(),
+                "variable" => {
+                    let depth = self.depth;
+                    let result = self.parse_variable_definition();
+                    if let Some(variable) = self.recover(result, depth)? {
+                        env.variables.push(variable);
+                    }
+                }
+                "header" => {
+                    let depth = self.depth;
+                    let result = self.parse_header_definition();
+                    if let Some(header) = self.recover(result, depth)? {
+                        env.headers.push(header);
+                    }
+                }
+                _ => {
+                    self.skip_unknown("env block")?;
+                }
+            }
+        }
+
+        self.expect(lexer::TokenType::RBrace, "to close env block")?;
+        Ok(env)
+    }
+
     fn parse_variable_definition(&mut self) -> Result<VariableDefinition, ParseError> {
         self.next_token(); // skip 'variable'
 
@@ -139,9 +370,10 @@ impl Parser {
 
         // Parse variable type (optional, default to "String")
         // Type is present if the next token is not "default", "variable", "header", "}" etc.
-        let var_type = if self.current_token.literal == "default"
-            || self.current_token.literal == "variable"
-            || self.current_token.literal == "header"
+        let var_type = if matches!(
+            self.current_token.literal.as_str(),
+            "default" | "from_command" | "ttl" | "variable" | "header" | "baseUrl" | "env"
+        )
             || self.current_token.token_type == lexer::TokenType::RBrace
         {
             "String".to_string()
@@ -151,30 +383,71 @@ impl Parser {
             t
         };
 
-        // Check for default value: default("value")
-        let default_value = if self.current_token.literal == "default" {
-            self.next_token(); // skip 'default'
-            self.expect(lexer::TokenType::LParen)?;
+        let mut variable = VariableDefinition {
+            name,
+            var_type,
+            default_value: None,
+            env: None,
+            command: None,
+            ttl: None,
+            secret: false,
+            error: None,
+            expires_in: None,
+        };
 
-            let value = if self.current_token.token_type == lexer::TokenType::String {
-                self.current_token.literal.clone()
-            } else {
-                // Handle unquoted values (numbers, booleans, identifiers)
-                self.current_token.literal.clone()
-            };
-            self.next_token();
+        // default("value") or default(env("NAME")), from_command("...") and ttl(seconds),
+        // in any order
+        loop {
+            match self.current_token.literal.as_str() {
+                "default" => {
+                    self.next_token(); // skip 'default'
+                    self.expect(lexer::TokenType::LParen, "after `default`")?;
+                    (variable.default_value, variable.env) = self.parse_default_value()?;
+                    self.expect(lexer::TokenType::RParen, "to close `default(...)`")?;
+                }
+                "from_command" => {
+                    self.next_token(); // skip 'from_command'
+                    self.expect(lexer::TokenType::LParen, "after `from_command`")?;
+                    let command = self.expect(lexer::TokenType::String, "as the command to run")?;
+                    self.expect(lexer::TokenType::RParen, "to close `from_command(...)`")?;
+                    variable.command = Some(command);
+                }
+                "ttl" => {
+                    let span = self.current_token.span();
+                    self.next_token(); // skip 'ttl'
+                    self.expect(lexer::TokenType::LParen, "after `ttl`")?;
+                    let seconds = self.expect(lexer::TokenType::Number, "as the seconds of `ttl`")?;
+                    self.expect(lexer::TokenType::RParen, "to close `ttl(...)`")?;
+                    match seconds.parse() {
+                        Ok(seconds) => variable.ttl = Some(seconds),
+                        Err(_) => self.warn(
+                            WarningKind::Keyword,
+                            format!("`ttl({})` is not a whole number of seconds", seconds),
+                            span,
+                        ),
+                    }
+                }
+                _ => break,
+            }
+        }
 
-            self.expect(lexer::TokenType::RParen)?;
-            Some(value)
-        } else {
-            None
-        };
+        Ok(variable)
+    }
 
-        Ok(VariableDefinition {
-            name,
-            var_type,
-            default_value,
-        })
+    /// The argument of `default(...)`, a value or `env("NAME")` read from the environment
+    fn parse_default_value(&mut self) -> Result<(Option<String>, Option<String>), ParseError> {
+        // Quoted or unquoted values (numbers, booleans, identifiers)
+        let is_ident = self.current_token.token_type == lexer::TokenType::Ident;
+        let value = self.current_token.literal.clone();
+        self.next_token();
+
+        if is_ident && value == "env" && self.current_token.token_type == lexer::TokenType::LParen {
+            self.next_token(); // skip '('
+            let name = self.expect(lexer::TokenType::String, "as the name of `env(...)`")?;
+            self.expect(lexer::TokenType::RParen, "to close `env(...)`")?;
+            return Ok((None, Some(name)));
+        }
+        Ok((Some(value), None))
     }
 
     fn parse_header_definition(&mut self) -> Result<HeaderDefinition, ParseError> {
@@ -185,32 +458,27 @@ impl Parser {
         self.next_token();
 
         // Check for @default annotation
-        let default_value = if self.current_token.token_type == lexer::TokenType::At {
+        let (default_value, env) = if self.current_token.token_type == lexer::TokenType::At {
             self.next_token(); // skip '@'
             
             if self.current_token.literal == "default" {
                 self.next_token(); // skip 'default'
-                self.expect(lexer::TokenType::LParen)?;
-
-                let value = if self.current_token.token_type == lexer::TokenType::String {
-                    self.current_token.literal.clone()
-                } else {
-                    self.current_token.literal.clone()
-                };
-                self.next_token();
-
-                self.expect(lexer::TokenType::RParen)?;
-                Some(value)
+                self.expect(lexer::TokenType::LParen, "after `@default`")?;
+                let default = self.parse_default_value()?;
+                self.expect(lexer::TokenType::RParen, "to close `@default(...)`")?;
+                default
             } else {
-                None
+                (None, None)
             }
         } else {
-            None
+            (None, None)
         };
 
         Ok(HeaderDefinition {
             name,
             default_value,
+            env,
+            secret: false,
         })
     }
 
@@ -220,7 +488,7 @@ impl Parser {
         let path = self.current_token.literal.clone();
         self.next_token();
 
-        self.expect(lexer::TokenType::LBrace)?;
+        self.expect(lexer::TokenType::LBrace, "after api path")?;
 
         let mut api = ApiBlock {
             path,
@@ -239,18 +507,21 @@ impl Parser {
 
             let method_name = self.current_token.literal.to_lowercase();
             if ["get", "post", "put", "delete", "patch"].contains(&method_name.as_str()) {
-                let mut method = self.parse_method_block()?;
-                // Assign pending doc comment to method description
-                if pending_doc_comment.is_some() {
-                    method.description = pending_doc_comment.take();
+                let depth = self.depth;
+                let result = self.parse_method_block();
+                if let Some(mut method) = self.recover(result, depth)? {
+                    // Assign pending doc comment to method description
+                    if pending_doc_comment.is_some() {
+                        method.description = pending_doc_comment.take();
+                    }
+                    api.methods.push(method);
                 }
-                api.methods.push(method);
             } else {
-                self.next_token();
+                self.skip_unknown("api block")?;
             }
         }
 
-        self.expect(lexer::TokenType::RBrace)?;
+        self.expect(lexer::TokenType::RBrace, "to close api block")?;
         Ok(api)
     }
 
@@ -260,7 +531,7 @@ impl Parser {
         let url = self.current_token.literal.clone();
         self.next_token();
 
-        self.expect(lexer::TokenType::LBrace)?;
+        self.expect(lexer::TokenType::LBrace, "after url")?;
 
         let mut ws = WsBlock {
             url,
@@ -303,12 +574,12 @@ impl Parser {
                     ws.events.push(event);
                 }
                 _ => {
-                    self.next_token();
+                    self.skip_unknown("ws block")?;
                 }
             }
         }
 
-        self.expect(lexer::TokenType::RBrace)?;
+        self.expect(lexer::TokenType::RBrace, "to close block")?;
 
         // If there was a doc comment before ws block (though not easily supported by this simple parser loop),
         // we could assign it. For now, let's just make sure we handle it.
@@ -325,7 +596,7 @@ impl Parser {
         let name = self.current_token.literal.clone();
         self.next_token();
 
-        self.expect(lexer::TokenType::LBrace)?;
+        self.expect(lexer::TokenType::LBrace, "after event name")?;
 
         let mut event = WsEvent {
             name,
@@ -344,12 +615,12 @@ impl Parser {
                     event.response = Some(self.parse_schema_block()?);
                 }
                 _ => {
-                    self.next_token();
+                    self.skip_unknown("event block")?;
                 }
             }
         }
 
-        self.expect(lexer::TokenType::RBrace)?;
+        self.expect(lexer::TokenType::RBrace, "to close event block")?;
         Ok(event)
     }
 
@@ -359,7 +630,7 @@ impl Parser {
         let path = self.current_token.literal.clone();
         self.next_token();
 
-        self.expect(lexer::TokenType::LBrace)?;
+        self.expect(lexer::TokenType::LBrace, "after sse path")?;
 
         let mut sse = SseBlock {
             path,
@@ -392,23 +663,23 @@ impl Parser {
                 }
                 "response" => {
                     self.next_token();
-                    self.expect(lexer::TokenType::LBrace)?;
+                    self.expect(lexer::TokenType::LBrace, "after `response`")?;
                     while self.current_token.token_type != lexer::TokenType::RBrace {
                         if self.current_token.literal == "event" {
                             sse.events.push(self.parse_sse_event()?);
                         } else {
-                            self.next_token();
+                            self.skip_unknown("sse response block")?;
                         }
                     }
-                    self.expect(lexer::TokenType::RBrace)?;
+                    self.expect(lexer::TokenType::RBrace, "to close response block")?;
                 }
                 _ => {
-                    self.next_token();
+                    self.skip_unknown("sse block")?;
                 }
             }
         }
 
-        self.expect(lexer::TokenType::RBrace)?;
+        self.expect(lexer::TokenType::RBrace, "to close sse block")?;
 
         if sse.description.is_none() {
             sse.description = pending_doc_comment;
@@ -423,7 +694,7 @@ impl Parser {
         let name = self.current_token.literal.clone();
         self.next_token();
 
-        self.expect(lexer::TokenType::LBrace)?;
+        self.expect(lexer::TokenType::LBrace, "after event name")?;
 
         let mut fields = Vec::new();
 
@@ -431,11 +702,11 @@ impl Parser {
             if self.current_token.token_type == lexer::TokenType::Ident {
                 fields.push(self.parse_field()?);
             } else {
-                self.next_token();
+                self.skip_unknown("event block")?;
             }
         }
 
-        self.expect(lexer::TokenType::RBrace)?;
+        self.expect(lexer::TokenType::RBrace, "to close event block")?;
 
         Ok(SseEvent { name, fields })
     }
@@ -443,7 +714,7 @@ impl Parser {
     fn parse_method_block(&mut self) -> Result<MethodBlock, ParseError> {
         let method = self.current_token.literal.to_uppercase();
         self.next_token();
-        self.expect(lexer::TokenType::LBrace)?;
+        self.expect(lexer::TokenType::LBrace, &format!("after `{}`", method.to_lowercase()))?;
 
         let mut method_block = MethodBlock {
             method,
@@ -451,6 +722,12 @@ impl Parser {
             description: None,
             request: None,
             response: None,
+            status: None,
+            responses: Vec::new(),
+            content_type: None,
+            expect: Vec::new(),
+            mock: None,
+            auth: None,
         };
 
         while self.current_token.token_type != lexer::TokenType::RBrace {
@@ -464,36 +741,236 @@ impl Parser {
                 }
                 "request" => {
                     self.next_token();
-                    method_block.request = Some(self.parse_schema_block()?);
+                    let depth = self.depth;
+                    let result = self.parse_schema_block();
+                    method_block.request = self.recover(result, depth)?;
                 }
                 "response" => {
                     self.next_token();
-                    method_block.response = Some(self.parse_schema_block()?);
+                    let depth = self.depth;
+                    let result = self.parse_status_response(&method_block);
+                    match self.recover(result, depth)? {
+                        // The first success response is the one that is mocked and tested
+                        Some((status, schema))
+                            if method_block.response.is_none()
+                                && status.as_deref().is_none_or(is_success_status) =>
+                        {
+                            method_block.response = Some(schema);
+                            method_block.status = status;
+                        }
+                        Some((Some(status), schema)) => {
+                            method_block.responses.push(StatusResponse { status, schema });
+                        }
+                        Some((None, schema)) => method_block.response = Some(schema),
+                        None => {}
+                    }
                 }
-                _ => {
+                "contentType" => {
                     self.next_token();
+                    if self.current_token.token_type == lexer::TokenType::String {
+                        method_block.content_type = Some(self.current_token.literal.clone());
+                        self.next_token();
+                    } else {
+                        return Err(self.error(format!(
+                            "expected a media type such as \"multipart/form-data\" after `contentType`, found {}",
+                            self.current_token.describe()
+                        )));
+                    }
+                }
+                "expect" => {
+                    let depth = self.depth;
+                    let result = self.parse_expect_block();
+                    method_block.expect = self.recover(result, depth)?.unwrap_or_default();
+                }
+                "mock" => {
+                    let depth = self.depth;
+                    let result = self.parse_mock_switch();
+                    method_block.mock = self.recover(result, depth)?;
+                }
+                "auth" => {
+                    let depth = self.depth;
+                    let result = self.parse_auth_block();
+                    method_block.auth = self.recover(result, depth)?;
+                }
+                _ => {
+                    self.skip_unknown("method block")?;
                 }
             }
         }
 
-        self.expect(lexer::TokenType::RBrace)?;
+        self.expect(lexer::TokenType::RBrace, "to close method block")?;
         Ok(method_block)
     }
 
+    /// The optional status code after `response` and the schema block. `404`, `4XX` and
+    /// `default` are statuses, a status already used is reported
+    fn parse_status_response(
+        &mut self,
+        method: &MethodBlock,
+    ) -> Result<(Option<String>, SchemaBlock), ParseError> {
+        let span = self.current_token.span();
+        let status = match self.current_token.token_type {
+            lexer::TokenType::Number => {
+                let mut status = self.current_token.literal.clone();
+                let end = span.offset + span.len;
+                self.next_token();
+                // `4XX` lexes as `4` and `XX`
+                if self.current_token.token_type == lexer::TokenType::Ident
+                    && self.current_token.offset == end
+                {
+                    status.push_str(&self.current_token.literal);
+                    self.next_token();
+                }
+                Some(status)
+            }
+            lexer::TokenType::Ident if self.current_token.literal == "default" => {
+                self.next_token();
+                Some("default".to_string())
+            }
+            _ => None,
+        };
+
+        if let Some(ref status) = status {
+            let valid = status == "default"
+                || status.len() == 3
+                    && status.starts_with(|c: char| ('1'..='5').contains(&c))
+                    && (status.chars().all(|c| c.is_ascii_digit()) || status.ends_with("XX"));
+            if !valid {
+                return Err(ParseError::Syntax(vec![SyntaxError {
+                    message: format!(
+                        "invalid response status `{}`, expected a code such as `404`, a range such as `4XX` or `default`",
+                        status
+                    ),
+                    span,
+                }]));
+            }
+            let used = method.status.as_ref() == Some(status)
+                || method.responses.iter().any(|r| &r.status == status);
+            if used {
+                self.warn(
+                    WarningKind::Keyword,
+                    format!("response {} is declared more than once", status),
+                    span,
+                );
+            }
+        }
+
+        let schema = self.parse_schema_block()?;
+        Ok((status, schema))
+    }
+
+    /// `mock true` or `mock false` inside a method or category block
+    fn parse_mock_switch(&mut self) -> Result<bool, ParseError> {
+        self.next_token(); // skip 'mock'
+        let value = match self.current_token.literal.as_str() {
+            "true" if self.current_token.token_type == lexer::TokenType::Ident => true,
+            "false" if self.current_token.token_type == lexer::TokenType::Ident => false,
+            _ => {
+                return Err(self.error(format!(
+                    "expected `true` or `false` after `mock`, found {}",
+                    self.current_token.describe()
+                )))
+            }
+        };
+        self.next_token();
+        Ok(value)
+    }
+
+    /// `auth bearer { token "{token}" }` in the config, a category or a method block, or
+    /// `auth none` to send no credentials
+    fn parse_auth_block(&mut self) -> Result<AuthBlock, ParseError> {
+        self.next_token(); // skip 'auth'
+        let scheme = match AuthScheme::from_name(&self.current_token.literal) {
+            Some(scheme) if self.current_token.token_type == lexer::TokenType::Ident => scheme,
+            _ => {
+                return Err(self.error(format!(
+                    "expected an auth scheme after `auth`, one of {}, found {}",
+                    AuthScheme::NAMES.join(", "),
+                    self.current_token.describe()
+                )))
+            }
+        };
+        self.next_token();
+
+        let mut auth = AuthBlock {
+            scheme,
+            properties: IndexMap::new(),
+        };
+        if scheme == AuthScheme::None {
+            return Ok(auth);
+        }
+
+        self.expect(
+            lexer::TokenType::LBrace,
+            &format!("after `auth {}`", scheme.name()),
+        )?;
+        while self.current_token.token_type != lexer::TokenType::RBrace {
+            let span = self.current_token.span();
+            let property = self.expect(lexer::TokenType::Ident, "as an auth property")?;
+            let value = match self.current_token.token_type {
+                lexer::TokenType::String | lexer::TokenType::Ident | lexer::TokenType::Number => {
+                    self.current_token.literal.clone()
+                }
+                _ => {
+                    return Err(self.error(format!(
+                        "expected a value after `{}`, found {}",
+                        property,
+                        self.current_token.describe()
+                    )))
+                }
+            };
+            self.next_token();
+
+            if !scheme.properties().contains(&property.as_str()) {
+                self.warn(
+                    WarningKind::Keyword,
+                    format!(
+                        "unknown property `{}` of `auth {}`, expected one of: {}",
+                        property,
+                        scheme.name(),
+                        scheme.properties().join(", ")
+                    ),
+                    span,
+                );
+            }
+            auth.properties.insert(property, value);
+        }
+        self.expect(lexer::TokenType::RBrace, "to close auth block")?;
+
+        Ok(auth)
+    }
+
     fn parse_schema_block(&mut self) -> Result<SchemaBlock, ParseError> {
-        self.expect(lexer::TokenType::LBrace)?;
+        self.expect(lexer::TokenType::LBrace, "to open schema block")?;
 
         let mut fields = Vec::new();
+        let mut spreads = Vec::new();
 
         while self.current_token.token_type != lexer::TokenType::RBrace {
-            if self.current_token.token_type == lexer::TokenType::Ident {
-                fields.push(self.parse_field()?);
-            } else {
+            if let Some(name) = self.current_token.literal.strip_prefix("...") {
+                if name.is_empty() {
+                    let error = self.error("expected a type name after `...`".to_string());
+                    self.recover::<()>(Err(error), self.depth)?;
+                    continue;
+                }
+                spreads.push(Spread {
+                    name: name.to_string(),
+                    position: fields.len(),
+                    span: self.current_token.span(),
+                });
                 self.next_token();
+            } else if self.current_token.token_type == lexer::TokenType::Ident {
+                let depth = self.depth;
+                let result = self.parse_field();
+                if let Some(field) = self.recover(result, depth)? {
+                    fields.push(field);
+                }
+            } else {
+                self.skip_unknown("schema block")?;
             }
         }
 
-        self.expect(lexer::TokenType::RBrace)?;
+        self.expect(lexer::TokenType::RBrace, "to close schema block")?;
 
         // Check for optional marker
         let optional = if self.current_token.literal == "?" {
@@ -503,89 +980,189 @@ impl Parser {
             false
         };
 
-        Ok(SchemaBlock { fields, optional })
+        Ok(SchemaBlock {
+            fields,
+            optional,
+            spreads,
+        })
     }
 
-    fn parse_field(&mut self) -> Result<Field, ParseError> {
+    /// `type User { ... }`
+    fn parse_type_definition(&mut self) -> Result<TypeDefinition, ParseError> {
+        self.next_token(); // skip 'type'
+
+        if self.current_token.token_type != lexer::TokenType::Ident {
+            return Err(self.error(format!(
+                "expected a type name after `type`, found {}",
+                self.current_token.describe()
+            )));
+        }
         let name = self.current_token.literal.clone();
         self.next_token();
 
-        let (field_type, nested, optional) =
-            if self.current_token.token_type == lexer::TokenType::LBrace {
-                // Nested object
-                let schema = self.parse_schema_block()?;
-                (
-                    FieldType::Object,
-                    Some(Box::new(schema.clone())),
-                    schema.optional,
-                )
-            } else {
-                // Simple type
-                let type_str = self.current_token.literal.clone();
-                self.next_token();
+        let schema = self.parse_schema_block()?;
+        Ok(TypeDefinition { name, schema })
+    }
 
-                let optional = if self.current_token.literal == "?" {
-                    self.next_token();
-                    true
-                } else {
-                    false
-                };
+    /// `enum Status { active disabled }`
+    fn parse_enum_definition(&mut self) -> Result<EnumDefinition, ParseError> {
+        self.next_token(); // skip 'enum'
 
-                let field_type = match type_str.as_str() {
-                    "String" => FieldType::String,
-                    "Number" => FieldType::Number,
-                    "Boolean" => FieldType::Boolean,
-                    "Array" => FieldType::Array,
-                    _ => FieldType::String,
-                };
+        if self.current_token.token_type != lexer::TokenType::Ident {
+            return Err(self.error(format!(
+                "expected an enum name after `enum`, found {}",
+                self.current_token.describe()
+            )));
+        }
+        let name = self.current_token.literal.clone();
+        let span = self.current_token.span();
+        self.next_token();
 
-                (field_type, None, optional)
-            };
+        self.expect(lexer::TokenType::LBrace, "to open enum block")?;
+        let values = self.parse_value_list(lexer::TokenType::RBrace);
+        self.expect(lexer::TokenType::RBrace, "to close enum block")?;
+
+        if values.is_empty() {
+            self.warn(
+                WarningKind::Type,
+                format!("enum `{}` has no values", name),
+                span,
+            );
+        }
+        Ok(EnumDefinition { name, values })
+    }
+
+    fn parse_field(&mut self) -> Result<Field, ParseError> {
+        let name = self.current_token.literal.clone();
+        self.next_token();
+
+        let Field {
+            field_type,
+            mut nullable,
+            nested,
+            items,
+            key_type,
+            type_ref,
+            variants,
+            enum_values,
+            span,
+            ..
+        } = self.parse_field_type(&name)?;
+        let optional = if self.current_token.token_type == lexer::TokenType::Question {
+            self.next_token();
+            true
+        } else {
+            // `{ ... }?` is read with the block, for a union with its last variant
+            let last = variants.as_ref().and_then(|v| v.last());
+            let block = last.map_or(nested.as_deref(), |v| v.nested.as_deref());
+            block.is_some_and(|n| n.optional)
+        };
+        // `String?!` reads the same as `String!?`
+        if self.is_nullable_marker() {
+            self.take_nullable_marker();
+            nullable = true;
+        }
 
-        // Parse annotations (@mock, @example, @params)
+        // Parse annotations (@mock, @example, @params, @header...)
         let mut mock: Option<MockValue> = None;
+        let mut generator: Option<MockGenerator> = None;
         let mut example: Option<MockValue> = None;
+        let mut discriminator: Option<String> = None;
         let mut is_params = false;
+        let mut location: Option<ParamLocation> = None;
+        let mut constraints = Constraints::default();
 
         while self.current_token.token_type == lexer::TokenType::At {
             self.next_token(); // skip @
             let annotation_name = self.current_token.literal.clone();
+            let annotation_span = self.current_token.span();
             self.next_token(); // skip annotation name
 
-            if annotation_name == "params" {
-                is_params = true;
-            } else if annotation_name == "mock" || annotation_name == "example" {
-                self.expect(lexer::TokenType::LParen)?;
-
-                let value = match self.current_token.token_type {
-                    lexer::TokenType::String => {
-                        let val = MockValue::String(self.current_token.literal.clone());
-                        self.next_token();
-                        val
+            if matches!(annotation_name.as_str(), "params" | "path" | "header" | "cookie") {
+                let previous = location
+                    .map(|l| l.annotation())
+                    .or(is_params.then_some("params"));
+                if let Some(previous) = previous.filter(|p| *p != annotation_name) {
+                    self.warn(
+                        WarningKind::Annotation,
+                        format!(
+                            "`{}` has both @{} and @{}, the last one is used",
+                            name, previous, annotation_name
+                        ),
+                        annotation_span,
+                    );
+                }
+                is_params = annotation_name == "params";
+                location = match annotation_name.as_str() {
+                    "path" => Some(ParamLocation::Path),
+                    "header" => Some(ParamLocation::Header),
+                    "cookie" => Some(ParamLocation::Cookie),
+                    _ => None,
+                };
+            } else if annotation_name == "discriminator" {
+                self.expect(lexer::TokenType::LParen, "after `@discriminator`")?;
+                let args = self.parse_value_list(lexer::TokenType::RParen);
+                self.expect(lexer::TokenType::RParen, "to close annotation")?;
+                match args.as_slice() {
+                    [MockValue::String(property)] if field_type == FieldType::Union => {
+                        discriminator = Some(property.clone());
                     }
-                    lexer::TokenType::Number => {
-                        let num: f64 = self.current_token.literal.parse().unwrap_or(0.0);
-                        self.next_token();
-                        MockValue::Number(num)
+                    [MockValue::String(_)] => self.warn(
+                        WarningKind::Annotation,
+                        format!(
+                            "@discriminator on `{}` has no effect, it is for union types such as `Click | View`",
+                            name
+                        ),
+                        annotation_span,
+                    ),
+                    _ => self.warn(
+                        WarningKind::Annotation,
+                        "@discriminator takes the name of one property, e.g. `@discriminator(type)`"
+                            .to_string(),
+                        annotation_span,
+                    ),
+                }
+            } else if annotation_name == "mock" || annotation_name == "example" {
+                self.expect(lexer::TokenType::LParen, &format!("after `@{}`", annotation_name))?;
+                let is_generator = annotation_name == "mock"
+                    && self.current_token.token_type == lexer::TokenType::Ident
+                    && MockGenerator::is_known(&self.current_token.literal);
+                if is_generator {
+                    generator = Some(self.parse_mock_generator()?);
+                } else {
+                    let value = self.parse_literal_value();
+                    if annotation_name == "mock" {
+                        mock = Some(value);
+                    } else {
+                        example = Some(value);
                     }
-                    lexer::TokenType::Ident => {
-                        let val = match self.current_token.literal.as_str() {
-                            "true" => MockValue::Boolean(true),
-                            "false" => MockValue::Boolean(false),
-                            _ => MockValue::String(self.current_token.literal.clone()),
-                        };
+                }
+                self.expect(lexer::TokenType::RParen, "to close annotation")?;
+            } else if matches!(
+                annotation_name.as_str(),
+                "min" | "max" | "pattern" | "format" | "length"
+            ) {
+                self.expect(lexer::TokenType::LParen, &format!("after `@{}`", annotation_name))?;
+                let args = self.parse_value_list(lexer::TokenType::RParen);
+                self.expect(lexer::TokenType::RParen, "to close annotation")?;
+                if let Err(message) = set_constraint(&mut constraints, &annotation_name, args) {
+                    self.warn(WarningKind::Annotation, message, annotation_span);
+                }
+            } else {
+                self.warn(
+                    WarningKind::Annotation,
+                    format!("unknown annotation `@{}` on field `{}`", annotation_name, name),
+                    annotation_span,
+                );
+                // Skip its arguments, if any
+                if self.current_token.token_type == lexer::TokenType::LParen {
+                    while !matches!(
+                        self.current_token.token_type,
+                        lexer::TokenType::RParen | lexer::TokenType::Eof
+                    ) {
                         self.next_token();
-                        val
                     }
-                    _ => MockValue::String(String::new()),
-                };
-
-                self.expect(lexer::TokenType::RParen)?;
-
-                if annotation_name == "mock" {
-                    mock = Some(value);
-                } else {
-                    example = Some(value);
+                    self.expect(lexer::TokenType::RParen, "to close annotation")?;
                 }
             }
         }
@@ -603,14 +1180,374 @@ impl Parser {
             name,
             field_type,
             optional,
+            nullable,
             nested,
+            items,
+            key_type,
+            type_ref,
+            variants,
+            discriminator,
+            enum_values,
+            constraints,
             mock,
+            generator,
             example,
             comment,
             is_params,
+            location,
+            span,
         })
     }
 
+    /// Parse the type of a field, with its `!` / `| Null` marker, and unions of several types
+    /// such as `Click | View`. Only the type parts of the returned field are set.
+    fn parse_field_type(&mut self, name: &str) -> Result<Field, ParseError> {
+        let span = self.current_token.span();
+        let mut nullable = false;
+        let mut variants = Vec::new();
+        loop {
+            if self.current_token.token_type == lexer::TokenType::Ident
+                && self.current_token.literal == "Null"
+            {
+                self.next_token();
+                nullable = true;
+            } else {
+                variants.push(self.parse_single_type(name)?);
+            }
+            if self.current_token.token_type != lexer::TokenType::Pipe {
+                break;
+            }
+            self.next_token(); // skip '|'
+        }
+        if self.is_nullable_marker() {
+            self.take_nullable_marker();
+            nullable = true;
+        }
+
+        let mut field = match variants.len() {
+            0 => {
+                self.warn(
+                    WarningKind::Type,
+                    format!("field `{}` can only be null, treated as `String | Null`", name),
+                    span,
+                );
+                Field::element(name, FieldType::String)
+            }
+            1 => variants.pop().unwrap(),
+            _ => {
+                let mut field = Field::element(name, FieldType::Union);
+                field.variants = Some(variants);
+                field
+            }
+        };
+        field.nullable = nullable;
+        Ok(field)
+    }
+
+    /// `!` after a type, the field may be `null`. In `Map<String, Number!>` it is lexed
+    /// together with the closing `>`
+    fn is_nullable_marker(&self) -> bool {
+        let literal = &self.current_token.literal;
+        self.current_token.token_type == lexer::TokenType::Operator
+            && literal.starts_with('!')
+            && literal[1..].chars().all(|c| c == '>')
+    }
+
+    fn take_nullable_marker(&mut self) {
+        if self.current_token.literal.len() > 1 {
+            self.current_token.literal.remove(0);
+        } else {
+            self.next_token();
+        }
+    }
+
+    /// Parse a single type: a type name, a `{ ... }` object, a `[ ... ]` typed array or a
+    /// `Map<K, V>`. Only the type, `nested`, `items` and `key_type` of the returned field are set.
+    fn parse_single_type(&mut self, name: &str) -> Result<Field, ParseError> {
+        match self.current_token.token_type {
+            lexer::TokenType::LBrace => {
+                let schema = self.parse_schema_block()?;
+                let mut field = Field::element(name, FieldType::Object);
+                field.nested = Some(Box::new(schema));
+                Ok(field)
+            }
+            lexer::TokenType::LBracket => {
+                self.next_token(); // skip '['
+                let element = self.parse_field_type(name)?;
+                self.expect(lexer::TokenType::RBracket, "to close array type")?;
+
+                let mut field = Field::element(name, FieldType::Array);
+                field.items = Some(Box::new(element));
+                Ok(field)
+            }
+            _ => {
+                let type_str = self.current_token.literal.clone();
+                let type_span = self.current_token.span();
+                self.next_token();
+
+                let field_type = match type_str.as_str() {
+                    "String" => FieldType::String,
+                    "Number" | "Float" => FieldType::Number,
+                    "Integer" => FieldType::Integer,
+                    "Boolean" => FieldType::Boolean,
+                    "DateTime" => FieldType::DateTime,
+                    "Date" => FieldType::Date,
+                    "UUID" => FieldType::Uuid,
+                    "File" | "Binary" => FieldType::File,
+                    "Array" => FieldType::Array,
+                    "Map" => return self.parse_map_type(name, type_span),
+                    "Enum" => {
+                        self.expect(lexer::TokenType::LParen, "after `Enum`")?;
+                        let values = self.parse_value_list(lexer::TokenType::RParen);
+                        self.expect(lexer::TokenType::RParen, "to close enum values")?;
+                        if values.is_empty() {
+                            self.warn(
+                                WarningKind::Type,
+                                format!("`Enum` of field `{}` has no values", name),
+                                type_span,
+                            );
+                        }
+
+                        let mut field = Field::element(name, enum_value_type(&values));
+                        field.enum_values = Some(values);
+                        return Ok(field);
+                    }
+                    // Anything capitalized refers to a `type`, checked when types are resolved
+                    _ if type_str.starts_with(|c: char| c.is_ascii_uppercase()) => {
+                        let mut field = Field::element(name, FieldType::Object);
+                        field.type_ref = Some(type_str);
+                        field.span = type_span;
+                        return Ok(field);
+                    }
+                    _ => {
+                        self.warn(
+                            WarningKind::Type,
+                            format!("unknown type `{}` for field `{}`, treated as String", type_str, name),
+                            type_span,
+                        );
+                        FieldType::String
+                    }
+                };
+                Ok(Field::element(name, field_type))
+            }
+        }
+    }
+
+    /// Parse the `<K, V>` of a `Map`, the `Map` keyword is already consumed
+    fn parse_map_type(&mut self, name: &str, span: Span) -> Result<Field, ParseError> {
+        if !self.current_token.literal.starts_with('<') {
+            return Err(self.error(format!(
+                "expected `<` after `Map`, e.g. `Map<String, Number>`, found {}",
+                self.current_token.describe()
+            )));
+        }
+        self.take_angle_bracket();
+
+        // Commas are lexed as part of identifiers, `String,` or `String,Number`
+        let key_literal = self.current_token.literal.clone();
+        let (key, rest) = key_literal.split_once(',').unwrap_or((&key_literal, ""));
+        let key_type = match key.trim() {
+            "String" => FieldType::String,
+            "Integer" => FieldType::Integer,
+            "UUID" => FieldType::Uuid,
+            "Date" => FieldType::Date,
+            other => {
+                self.warn(
+                    WarningKind::Type,
+                    format!(
+                        "unsupported key type `{}` for map `{}`, keys can be String, Integer, UUID or Date",
+                        other, name
+                    ),
+                    span,
+                );
+                FieldType::String
+            }
+        };
+        if rest.is_empty() {
+            self.next_token();
+            if self.current_token.literal == "," {
+                self.next_token();
+            }
+        } else {
+            // `Map<String,Number>`, keep the value type for parse_field_type
+            self.current_token.literal = rest.to_string();
+        }
+
+        let value = self.parse_field_type(name)?;
+        if !self.current_token.literal.starts_with('>') {
+            return Err(self.error(format!(
+                "expected `>` to close map type, found {}",
+                self.current_token.describe()
+            )));
+        }
+        self.take_angle_bracket();
+
+        let mut field = Field::element(name, FieldType::Map);
+        field.key_type = Some(key_type);
+        field.items = Some(Box::new(value));
+        Ok(field)
+    }
+
+    /// Consume one `<` or `>` of an operator token, `>>` closes two nested maps
+    fn take_angle_bracket(&mut self) {
+        if self.current_token.literal.len() > 1 {
+            self.current_token.literal.remove(0);
+        } else {
+            self.next_token();
+        }
+    }
+
+    /// Parse a generator such as `uuid` or `int(1, 100)`, the current token is its name
+    fn parse_mock_generator(&mut self) -> Result<MockGenerator, ParseError> {
+        let name = self.current_token.literal.clone();
+        let span = self.current_token.span();
+        self.next_token();
+
+        let mut args = Vec::new();
+        if self.current_token.token_type == lexer::TokenType::LParen {
+            self.next_token();
+            args = self.parse_value_list(lexer::TokenType::RParen);
+            self.expect(lexer::TokenType::RParen, "to close generator arguments")?;
+        }
+
+        let numbers = args
+            .iter()
+            .filter(|a| matches!(a, MockValue::Number(_)))
+            .count();
+        let problem = match name.as_str() {
+            "int" | "float" if args.len() != 2 || numbers != 2 => {
+                Some(format!("`{}` expects a minimum and a maximum, e.g. `{}(1, 100)`", name, name))
+            }
+            "oneOf" if args.is_empty() => {
+                Some("`oneOf` expects at least one value, e.g. `oneOf(\"a\", \"b\")`".to_string())
+            }
+            "int" | "float" | "oneOf" => None,
+            _ if !args.is_empty() => Some(format!("`{}` takes no arguments", name)),
+            _ => None,
+        };
+        if let Some(message) = problem {
+            self.warn(WarningKind::Annotation, message, span);
+        }
+
+        Ok(MockGenerator { name, args })
+    }
+
+    /// Parse values separated by commas or whitespace up to `end`, which is left in place
+    fn parse_value_list(&mut self, end: lexer::TokenType) -> Vec<MockValue> {
+        let mut values = Vec::new();
+        while self.current_token.token_type != end
+            && self.current_token.token_type != lexer::TokenType::Eof
+        {
+            match self.current_token.token_type {
+                lexer::TokenType::Ident => {
+                    // Commas are lexed as part of identifiers, `1,100` gives `1` and `,100`
+                    for part in self.current_token.literal.split(',').map(str::trim) {
+                        if part.is_empty() {
+                            continue;
+                        }
+                        values.push(match part {
+                            "true" => MockValue::Boolean(true),
+                            "false" => MockValue::Boolean(false),
+                            _ => match part.parse::<f64>() {
+                                Ok(n) => MockValue::Number(n),
+                                Err(_) => MockValue::String(part.to_string()),
+                            },
+                        });
+                    }
+                    self.next_token();
+                }
+                lexer::TokenType::String | lexer::TokenType::Number => {
+                    values.push(self.parse_literal_value())
+                }
+                lexer::TokenType::Comment | lexer::TokenType::DocComment => self.next_token(),
+                // Left for the caller to report
+                _ => break,
+            }
+        }
+        values
+    }
+
+    /// Parse a literal value: string, number, `true`/`false` or a bare identifier
+    fn parse_literal_value(&mut self) -> MockValue {
+        match self.current_token.token_type {
+            lexer::TokenType::String => {
+                let val = MockValue::String(self.current_token.literal.clone());
+                self.next_token();
+                val
+            }
+            lexer::TokenType::Number => {
+                let num: f64 = self.current_token.literal.parse().unwrap_or(0.0);
+                self.next_token();
+                MockValue::Number(num)
+            }
+            lexer::TokenType::Ident => {
+                let val = match self.current_token.literal.as_str() {
+                    "true" => MockValue::Boolean(true),
+                    "false" => MockValue::Boolean(false),
+                    _ => MockValue::String(self.current_token.literal.clone()),
+                };
+                self.next_token();
+                val
+            }
+            _ => MockValue::String(String::new()),
+        }
+    }
+
+    fn parse_expect_block(&mut self) -> Result<Vec<Expectation>, ParseError> {
+        self.next_token(); // skip 'expect'
+        self.expect(lexer::TokenType::LBrace, "after `expect`")?;
+
+        let mut expectations = Vec::new();
+
+        while self.current_token.token_type != lexer::TokenType::RBrace {
+            let literal = self.current_token.literal.clone();
+            let subject = if literal == "status" {
+                self.next_token();
+                ExpectSubject::Status
+            } else if literal == "body" || literal.starts_with("body.") {
+                self.next_token();
+                ExpectSubject::Body {
+                    path: literal.trim_start_matches("body").trim_start_matches('.').to_string(),
+                }
+            } else if literal == "header" {
+                self.next_token();
+                let name = self.current_token.literal.to_lowercase();
+                self.next_token();
+                ExpectSubject::Header { name }
+            } else {
+                self.skip_unknown("expect block")?;
+                continue;
+            };
+
+            // `status 200` is shorthand for `status == 200`, a bare subject checks existence
+            let op = if self.current_token.token_type == lexer::TokenType::Operator {
+                let op = ExpectOp::from_operator(&self.current_token.literal).ok_or_else(|| {
+                    self.error(format!(
+                        "expected a comparison operator (==, !=, >, >=, <, <=, ~), found {}",
+                        self.current_token.describe()
+                    ))
+                })?;
+                self.next_token();
+                op
+            } else if matches!(subject, ExpectSubject::Status) {
+                ExpectOp::Eq
+            } else {
+                ExpectOp::Exists
+            };
+
+            let value = if op == ExpectOp::Exists {
+                None
+            } else {
+                Some(self.parse_literal_value())
+            };
+
+            expectations.push(Expectation { subject, op, value });
+        }
+
+        self.expect(lexer::TokenType::RBrace, "to close expect block")?;
+        Ok(expectations)
+    }
+
     fn parse_import(&mut self) -> Result<String, ParseError> {
         self.next_token(); // skip 'import'
         let path = self
@@ -630,7 +1567,7 @@ impl Parser {
         let category_id_name = self.current_token.literal.clone();
         self.next_token();
 
-        self.expect(lexer::TokenType::LBrace)?;
+        self.expect(lexer::TokenType::LBrace, "after category name")?;
 
         *counter += 1;
         let mut category = CategoryBlock {
@@ -638,6 +1575,8 @@ impl Parser {
             name: None,
             desc: None,
             prefix: None,
+            mock: None,
+            auth: None,
             apis: Vec::new(),
             ws_apis: Vec::new(),
             socketio_apis: Vec::new(),
@@ -646,13 +1585,15 @@ impl Parser {
         };
 
         while self.current_token.token_type != lexer::TokenType::RBrace {
-            match self.current_token.literal.as_str() {
+            let depth = self.depth;
+            let result = match self.current_token.literal.as_str() {
                 "name" => {
                     self.next_token();
                     if self.current_token.token_type == lexer::TokenType::String {
                         category.name = Some(self.current_token.literal.clone());
                         self.next_token();
                     }
+                    Ok(())
                 }
                 "desc" => {
                     self.next_token();
@@ -660,6 +1601,7 @@ impl Parser {
                         category.desc = Some(self.current_token.literal.clone());
                         self.next_token();
                     }
+                    Ok(())
                 }
                 "prefix" => {
                     self.next_token();
@@ -671,41 +1613,68 @@ impl Parser {
                         category.prefix = Some(self.current_token.literal.clone());
                         self.next_token();
                     }
+                    Ok(())
                 }
-                "api" => {
-                    category.apis.push(self.parse_api_block()?);
-                }
-                "ws" => {
-                    category.ws_apis.push(self.parse_ws_block()?);
-                }
-                "socketio" => {
-                    category.socketio_apis.push(self.parse_ws_block()?);
-                }
-                "sse" => {
-                    category.sse_apis.push(self.parse_sse_block()?);
-                }
-                "category" => {
-                    category.children.push(self.parse_category_block(counter)?);
-                }
-                _ => {
-                    self.next_token();
-                }
-            }
+                "mock" => self
+                    .parse_mock_switch()
+                    .map(|mock| category.mock = Some(mock)),
+                "auth" => self
+                    .parse_auth_block()
+                    .map(|auth| category.auth = Some(auth)),
+                "api" => self.parse_api_block().map(|api| category.apis.push(api)),
+                "ws" => self.parse_ws_block().map(|ws| category.ws_apis.push(ws)),
+                "socketio" => self
+                    .parse_ws_block()
+                    .map(|ws| category.socketio_apis.push(ws)),
+                "sse" => self
+                    .parse_sse_block()
+                    .map(|sse| category.sse_apis.push(sse)),
+                "category" => self
+                    .parse_category_block(counter)
+                    .map(|child| category.children.push(child)),
+                _ => self.skip_unknown("category block"),
+            };
+            self.recover(result, depth)?;
         }
 
-        self.expect(lexer::TokenType::RBrace)?;
+        self.expect(lexer::TokenType::RBrace, "to close category block")?;
         Ok(category)
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WarningKind {
+    Keyword,
+    Type,
+    Annotation,
+}
+
+#[derive(Debug, Clone)]
+pub struct ParseWarning {
+    pub kind: WarningKind,
+    pub message: String,
+    pub span: Span,
+}
+
 #[derive(Debug)]
 pub enum ParseError {
     IoError(std::io::Error),
-    UnexpectedToken {
-        expected: String,
-        got: String,
-        line: usize,
-    },
+    /// Every syntax error found in the file, parsing recovers after each of them
+    Syntax(Vec<SyntaxError>),
+}
+
+impl ParseError {
+    /// Render the error with source snippets, `file` is the path shown to the user
+    pub fn render(&self, file: &str, source: &str) -> String {
+        match self {
+            ParseError::IoError(e) => format!("error: {}\n --> {}", e, file),
+            ParseError::Syntax(errors) => errors
+                .iter()
+                .map(|e| render_snippet("error", &e.message, file, source, e.span))
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+        }
+    }
 }
 
 impl From<std::io::Error> for ParseError {
@@ -718,13 +1687,76 @@ impl std::fmt::Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ParseError::IoError(e) => write!(f, "IO error: {}", e),
-            ParseError::UnexpectedToken {
-                expected,
-                got,
-                line,
-            } => {
-                write!(f, "Line {}: expected {}, got {}", line, expected, got)
+            ParseError::Syntax(errors) => {
+                let lines: Vec<String> = errors
+                    .iter()
+                    .map(|e| format!("Line {}:{}: {}", e.span.line, e.span.column, e.message))
+                    .collect();
+                write!(f, "{}", lines.join("\n"))
             }
         }
     }
 }
+
+/// `200`, `201`, `2XX`...
+fn is_success_status(status: &str) -> bool {
+    status.starts_with('2')
+}
+
+/// Store a `@min`, `@max`, `@pattern`, `@format` or `@length` annotation, the error explains
+/// arguments that do not fit it
+fn set_constraint(
+    constraints: &mut Constraints,
+    annotation: &str,
+    args: Vec<MockValue>,
+) -> Result<(), String> {
+    let number = |value: &MockValue| match value {
+        MockValue::Number(n) => Some(*n),
+        _ => None,
+    };
+    let length = |value: &MockValue| number(value).filter(|n| *n >= 0.0 && n.fract() == 0.0);
+
+    match (annotation, args.as_slice()) {
+        ("min", [value]) if number(value).is_some() => constraints.min = number(value),
+        ("max", [value]) if number(value).is_some() => constraints.max = number(value),
+        ("min" | "max", _) => {
+            return Err(format!("`@{}` expects a number, e.g. `@{}(0)`", annotation, annotation))
+        }
+        ("pattern", [MockValue::String(pattern)]) => constraints.pattern = Some(pattern.clone()),
+        ("pattern", _) => {
+            return Err(
+                "`@pattern` expects a regular expression, e.g. `@pattern(\"^[a-z]+$\")`"
+                    .to_string(),
+            )
+        }
+        ("format", [MockValue::String(format)]) => {
+            constraints.format = Some(format.clone());
+            if !FORMAT_NAMES.contains(&format.as_str()) {
+                return Err(format!(
+                    "unknown format `{}`, it is only documented, known formats are {}",
+                    format,
+                    FORMAT_NAMES.join(", ")
+                ));
+            }
+        }
+        ("format", _) => {
+            return Err("`@format` expects a format name, e.g. `@format(email)`".to_string())
+        }
+        // `@length(8)` is an exact length
+        ("length", [exact]) if length(exact).is_some() => {
+            constraints.min_length = length(exact).map(|n| n as u64);
+            constraints.max_length = constraints.min_length;
+        }
+        ("length", [min, max]) if length(min).is_some() && length(max).is_some() => {
+            constraints.min_length = length(min).map(|n| n as u64);
+            constraints.max_length = length(max).map(|n| n as u64);
+        }
+        _ => {
+            return Err(
+                "`@length` expects a length or a minimum and a maximum, e.g. `@length(1, 64)`"
+                    .to_string(),
+            )
+        }
+    }
+    Ok(())
+}
@@ -1,4 +1,10 @@
+use super::commands::CommandCache;
+use super::diagnostic::Span;
+use super::secrets;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
@@ -17,6 +23,46 @@ pub struct RqcConfig {
     pub sse_apis: Vec<SseBlock>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub categories: Vec<CategoryBlock>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub types: Vec<TypeDefinition>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub enums: Vec<EnumDefinition>,
+    /// Name of the `env` block selected with `--env` or in the web UI, the first one otherwise
+    #[serde(skip)]
+    pub active_env: Option<String>,
+    /// Variables of the `.env` and `.rqc.env` files next to the entry file
+    #[serde(skip)]
+    pub env_files: HashMap<String, String>,
+    /// Outputs of `from_command` variables, kept across reloads
+    #[serde(skip)]
+    pub commands: Arc<CommandCache>,
+}
+
+/// `type User { ... }`, usable as a field type (`owner User`) and spread with `...User`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeDefinition {
+    pub name: String,
+    pub schema: SchemaBlock,
+}
+
+/// `enum Status { active disabled }`, usable as a field type (`status Status`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnumDefinition {
+    pub name: String,
+    pub values: Vec<MockValue>,
+}
+
+/// Type of enum values: Number or Boolean when they all are, String otherwise
+pub fn enum_value_type(values: &[MockValue]) -> FieldType {
+    if values.iter().all(|v| matches!(v, MockValue::Number(_))) {
+        FieldType::Number
+    } else if values.iter().all(|v| matches!(v, MockValue::Boolean(_))) {
+        FieldType::Boolean
+    } else {
+        FieldType::String
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -76,6 +122,12 @@ pub struct CategoryBlock {
     pub desc: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub prefix: Option<String>,
+    /// `mock true|false`, applies to every endpoint in the category unless overridden
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mock: Option<bool>,
+    /// `auth ...`, applies to every endpoint in the category unless overridden
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth: Option<AuthBlock>,
     #[serde(default)]
     pub apis: Vec<ApiBlock>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -101,23 +153,147 @@ pub struct ConfigBlock {
     pub variables: Vec<VariableDefinition>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub headers: Vec<HeaderDefinition>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub envs: Vec<EnvBlock>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<AuthBlock>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// `env staging { ... }` in the config block, while it is the active environment its base URLs
+/// and defaults of variables and headers are used over the ones of the config block
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvBlock {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub base_urls: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variables: Vec<VariableDefinition>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<HeaderDefinition>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct VariableDefinition {
     pub name: String,
     pub var_type: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub default_value: Option<String>,
+    /// Environment variable read for the default, `default(env("API_TOKEN"))`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<String>,
+    /// Command printing the value, `from_command("gcloud auth print-access-token")`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Seconds the output of `command` is reused for, `ttl(3000)`, forever when not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u64>,
+    /// The default was read from the environment or a command, the web UI masks it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub secret: bool,
+    /// Why `command` gave no value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Seconds before the output of `command` is stale
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_in: Option<u64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct HeaderDefinition {
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub default_value: Option<String>,
+    /// Environment variable read for the default, `@default(env("API_KEY"))`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<String>,
+    /// The default was read from the environment, the web UI masks it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub secret: bool,
+}
+
+/// `auth bearer { token "{token}" }` in the config block, a category or a method block, the
+/// closest one applies to an endpoint and `auth none` turns it off
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthBlock {
+    pub scheme: AuthScheme,
+    /// `token "{token}"`, `in header`, `tokenUrl "..."`, values may use variables
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub properties: IndexMap<String, String>,
+}
+
+impl AuthBlock {
+    pub fn get(&self, property: &str) -> Option<&str> {
+        self.properties.get(property).map(String::as_str)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum AuthScheme {
+    None,
+    Bearer,
+    Basic,
+    /// `auth apiKey { name "X-API-Key" value "{apiKey}" in header }`, `in query` or `in cookie`
+    ApiKey,
+    /// `auth oauth2 { grant client_credentials tokenUrl "..." clientId "..." }`
+    #[serde(rename = "oauth2")]
+    OAuth2,
+}
+
+impl AuthScheme {
+    pub const NAMES: &'static [&'static str] = &["bearer", "basic", "apiKey", "oauth2", "none"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(AuthScheme::None),
+            "bearer" => Some(AuthScheme::Bearer),
+            "basic" => Some(AuthScheme::Basic),
+            "apiKey" => Some(AuthScheme::ApiKey),
+            "oauth2" => Some(AuthScheme::OAuth2),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AuthScheme::None => "none",
+            AuthScheme::Bearer => "bearer",
+            AuthScheme::Basic => "basic",
+            AuthScheme::ApiKey => "apiKey",
+            AuthScheme::OAuth2 => "oauth2",
+        }
+    }
+
+    /// Properties of the scheme, the required ones first
+    pub fn properties(self) -> &'static [&'static str] {
+        match self {
+            AuthScheme::None => &[],
+            AuthScheme::Bearer => &["token"],
+            AuthScheme::Basic => &["username", "password"],
+            AuthScheme::ApiKey => &["name", "value", "in"],
+            AuthScheme::OAuth2 => &[
+                "grant",
+                "tokenUrl",
+                "clientId",
+                "clientSecret",
+                "scope",
+                "username",
+                "password",
+            ],
+        }
+    }
+
+    /// Properties that must be set, `username` and `password` of the `password` grant aside
+    pub fn required(self) -> &'static [&'static str] {
+        match self {
+            AuthScheme::OAuth2 => &self.properties()[..3],
+            _ => self.properties(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -139,6 +315,105 @@ pub struct MethodBlock {
     pub request: Option<SchemaBlock>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response: Option<SchemaBlock>,
+    /// Status code of `response`, `response 201 { ... }`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// Responses for other status codes, `response 404 { ... }`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub responses: Vec<StatusResponse>,
+    /// Media type of the request body, `contentType "multipart/form-data"`, JSON otherwise
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expect: Vec<Expectation>,
+    /// `mock true|false`, forces the mock server to mock or forward this endpoint
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mock: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth: Option<AuthBlock>,
+}
+
+/// `response 404 { ... }`, the status is a code, a range such as `4XX`, or `default`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusResponse {
+    pub status: String,
+    pub schema: SchemaBlock,
+}
+
+// A single assertion from an `expect { ... }` block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Expectation {
+    pub subject: ExpectSubject,
+    pub op: ExpectOp,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<MockValue>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ExpectSubject {
+    Status,
+    Body { path: String },
+    Header { name: String },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ExpectOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Contains,
+    Exists,
+}
+
+impl ExpectOp {
+    pub fn from_operator(op: &str) -> Option<Self> {
+        match op {
+            "==" | "=" => Some(ExpectOp::Eq),
+            "!=" => Some(ExpectOp::Ne),
+            ">" => Some(ExpectOp::Gt),
+            ">=" => Some(ExpectOp::Ge),
+            "<" => Some(ExpectOp::Lt),
+            "<=" => Some(ExpectOp::Le),
+            "~" => Some(ExpectOp::Contains),
+            _ => None,
+        }
+    }
+
+    pub fn as_operator(&self) -> &'static str {
+        match self {
+            ExpectOp::Eq => "==",
+            ExpectOp::Ne => "!=",
+            ExpectOp::Gt => ">",
+            ExpectOp::Ge => ">=",
+            ExpectOp::Lt => "<",
+            ExpectOp::Le => "<=",
+            ExpectOp::Contains => "~",
+            ExpectOp::Exists => "exists",
+        }
+    }
+}
+
+impl std::fmt::Display for Expectation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.subject {
+            ExpectSubject::Status => write!(f, "status")?,
+            ExpectSubject::Body { ref path } if path.is_empty() => write!(f, "body")?,
+            ExpectSubject::Body { ref path } => write!(f, "body.{}", path)?,
+            ExpectSubject::Header { ref name } => write!(f, "header {}", name)?,
+        }
+        match self.value {
+            Some(ref value) => write!(f, " {} {}", self.op.as_operator(), value.to_json()),
+            None if self.op == ExpectOp::Exists => Ok(()),
+            None => write!(f, " {}", self.op.as_operator()),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -147,6 +422,19 @@ pub struct SchemaBlock {
     pub fields: Vec<Field>,
     #[serde(default)]
     pub optional: bool,
+    /// `...User` lines, replaced by the fields of the type when types are resolved
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spreads: Vec<Spread>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Spread {
+    pub name: String,
+    /// Number of fields declared before the spread, its fields are inserted there
+    pub position: usize,
+    #[serde(skip)]
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -154,31 +442,143 @@ pub struct SchemaBlock {
 pub struct Field {
     pub name: String,
     pub field_type: FieldType,
+    /// May be absent, `name String?`
     #[serde(default)]
     pub optional: bool,
+    /// May be `null`, `name String!` or `name String | Null`
+    #[serde(default)]
+    pub nullable: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub nested: Option<Box<SchemaBlock>>,
+    /// Element type of a typed array such as `[String]` or `[{ id Number }]`, or value type of
+    /// a `Map<String, Number>`. The element carries the name of the field
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<Box<Field>>,
+    /// Key type of a `Map<K, V>`, keys are strings in JSON so only their format differs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_type: Option<FieldType>,
+    /// Name of the `type` this field refers to, its fields end up in `nested`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_ref: Option<String>,
+    /// Alternatives of a union such as `Click | View`, each carries the name of the field
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variants: Option<Vec<Field>>,
+    /// Property telling the variants of a union apart, `@discriminator(kind)`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discriminator: Option<String>,
+    /// Allowed values, from `Enum("a", "b")` or a referenced `enum`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enum_values: Option<Vec<MockValue>>,
+    #[serde(default, skip_serializing_if = "Constraints::is_empty")]
+    pub constraints: Constraints,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mock: Option<MockValue>,
+    /// `@mock(faker.email)`, `@mock(int(1, 100))`, generates a new value for every response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generator: Option<MockGenerator>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub example: Option<MockValue>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub comment: Option<String>,
     #[serde(default)]
     pub is_params: bool,
+    /// Sent in the path, a header or a cookie rather than the body, `@path`, `@header`,
+    /// `@cookie`. Query parameters are `is_params`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<ParamLocation>,
+    /// Where the `type_ref` is written, for errors about the referenced type
+    #[serde(skip)]
+    pub span: Span,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamLocation {
+    /// Fills the `{name}` placeholder of the path
+    Path,
+    Header,
+    Cookie,
+}
+
+impl ParamLocation {
+    /// Name of the annotation, `@path`
+    pub fn annotation(&self) -> &'static str {
+        match self {
+            ParamLocation::Path => "path",
+            ParamLocation::Header => "header",
+            ParamLocation::Cookie => "cookie",
+        }
+    }
+}
+
+/// `@min(0) @max(150)`, `@pattern("^[a-z]+$")`, `@format(email)` and `@length(1, 64)`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Constraints {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// Characters of a string, or items of an array
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<u64>,
+}
+
+impl Constraints {
+    pub fn is_empty(&self) -> bool {
+        *self == Constraints::default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum FieldType {
     String,
     Number,
+    Integer,
     Boolean,
+    /// RFC 3339 date and time, a string in JSON
+    DateTime,
+    /// `YYYY-MM-DD`, a string in JSON
+    Date,
+    Uuid,
+    /// Uploaded file, sent as `multipart/form-data`
+    File,
     Array,
     Object,
+    /// `Map<String, Number>`, an object with arbitrary keys, the value type is in `items`
+    Map,
+    /// `Click | View`, a value of any of the types in `variants`
+    Union,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl FieldType {
+    /// Name of the type in .rqc files
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            FieldType::String => "String",
+            FieldType::Number => "Number",
+            FieldType::Integer => "Integer",
+            FieldType::Boolean => "Boolean",
+            FieldType::DateTime => "DateTime",
+            FieldType::Date => "Date",
+            FieldType::Uuid => "UUID",
+            FieldType::File => "File",
+            FieldType::Array => "Array",
+            FieldType::Object => "Object",
+            FieldType::Map => "Map",
+            FieldType::Union => "Union",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(untagged)]
 pub enum MockValue {
     String(String),
@@ -186,6 +586,143 @@ pub enum MockValue {
     Boolean(bool),
 }
 
+impl MockValue {
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            MockValue::String(s) => serde_json::Value::String(s.clone()),
+            MockValue::Number(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => {
+                serde_json::json!(*n as i64)
+            }
+            MockValue::Number(n) => serde_json::json!(*n),
+            MockValue::Boolean(b) => serde_json::Value::Bool(*b),
+        }
+    }
+}
+
+impl Field {
+    /// Element of an array field, `[String]` gives a `String` element named after the field
+    pub fn element(name: &str, field_type: FieldType) -> Self {
+        Field {
+            name: name.to_string(),
+            field_type,
+            optional: false,
+            nullable: false,
+            nested: None,
+            items: None,
+            key_type: None,
+            type_ref: None,
+            variants: None,
+            discriminator: None,
+            enum_values: None,
+            constraints: Constraints::default(),
+            mock: None,
+            generator: None,
+            example: None,
+            comment: None,
+            is_params: false,
+            location: None,
+            span: Span::default(),
+        }
+    }
+
+    /// Sent in the request body, not as a query, path, header or cookie parameter
+    pub fn in_body(&self) -> bool {
+        !self.is_params && self.location.is_none()
+    }
+
+    /// Value of the discriminator `property` that selects this variant of a union: the fixed
+    /// `@mock` or only `Enum` value of the property, else the name of the variant's type
+    pub fn discriminator_value(&self, property: &str) -> Option<MockValue> {
+        let own = self
+            .nested
+            .as_ref()
+            .and_then(|n| n.fields.iter().find(|f| f.name == property));
+        if let Some(field) = own {
+            if let Some(ref mock) = field.mock {
+                return Some(mock.clone());
+            }
+            if let Some([value]) = field.enum_values.as_deref() {
+                return Some(value.clone());
+            }
+        }
+        self.type_ref.clone().map(MockValue::String)
+    }
+}
+
+/// Formats `@format(...)` knows how to generate and validate, others are only documentation
+pub const FORMAT_NAMES: &[&str] = &["email", "uuid", "date-time", "date", "uri", "ipv4", "ipv6"];
+
+/// Generators usable in `@mock(...)`, `faker.*` names are listed in FAKER_NAMES
+pub const GENERATOR_NAMES: &[&str] = &[
+    "uuid",
+    "int",
+    "float",
+    "bool",
+    "oneOf",
+    "date.recent",
+    "date.past",
+    "date.future",
+];
+
+pub const FAKER_NAMES: &[&str] = &[
+    "email",
+    "name",
+    "firstName",
+    "lastName",
+    "username",
+    "phone",
+    "avatar",
+    "url",
+    "company",
+    "city",
+    "country",
+    "address",
+    "ip",
+    "word",
+    "sentence",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MockGenerator {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<MockValue>,
+}
+
+impl MockGenerator {
+    pub fn is_known(name: &str) -> bool {
+        GENERATOR_NAMES.contains(&name)
+            || name
+                .strip_prefix("faker.")
+                .is_some_and(|n| FAKER_NAMES.contains(&n))
+    }
+
+    /// Type of the generated values, `None` when it depends on the arguments
+    pub fn value_type(&self) -> Option<FieldType> {
+        match self.name.as_str() {
+            "int" => Some(FieldType::Integer),
+            "float" => Some(FieldType::Number),
+            "bool" => Some(FieldType::Boolean),
+            "uuid" => Some(FieldType::Uuid),
+            "date.recent" | "date.past" | "date.future" => Some(FieldType::DateTime),
+            "oneOf" => None,
+            _ => Some(FieldType::String),
+        }
+    }
+}
+
+impl std::fmt::Display for MockGenerator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.args.is_empty() {
+            let args: Vec<String> = self.args.iter().map(|a| a.to_json().to_string()).collect();
+            write!(f, "({})", args.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum EndpointType {
@@ -214,6 +751,12 @@ pub struct ApiEndpoint {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response: Option<SchemaBlock>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub responses: Vec<StatusResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub events: Option<Vec<WsEvent>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sse_events: Option<Vec<SseEvent>>,
@@ -225,6 +768,15 @@ pub struct ApiEndpoint {
     pub category_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub category_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expect: Vec<Expectation>,
+    /// Mock switch of the endpoint, or of its closest category that has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mock: Option<bool>,
+    /// `auth` of the endpoint, its closest category or the config block, `auth none` excluded.
+    /// `auth` is the handshake payload of Socket.IO endpoints
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_auth: Option<AuthBlock>,
 }
 
 // Category representation for Web UI
@@ -239,20 +791,194 @@ pub struct CategoryInfo {
     pub children: Vec<CategoryInfo>,
 }
 
+/// The auth that applies, unless it is `auth none`
+fn closest_auth(auth: Option<&AuthBlock>) -> Option<AuthBlock> {
+    auth.filter(|a| a.scheme != AuthScheme::None).cloned()
+}
+
 fn is_full_url(path: &str) -> bool {
     path.contains("://")
 }
 
+impl ApiEndpoint {
+    /// Schema of the response with this status: a `response 404 { ... }` for the code, the
+    /// main response for success codes, then a range such as `4XX`, then `default`
+    pub fn response_for(&self, status: u16) -> Option<&SchemaBlock> {
+        let code = status.to_string();
+        let range = format!("{}XX", status / 100);
+        let find = |wanted: &str| {
+            self.responses
+                .iter()
+                .find(|r| r.status == wanted)
+                .map(|r| &r.schema)
+        };
+        find(&code)
+            .or_else(|| self.response.as_ref().filter(|_| (200..300).contains(&status)))
+            .or_else(|| find(&range))
+            .or_else(|| find("default"))
+    }
+}
+
 impl RqcConfig {
+    /// Base URLs of the active environment, or of the config block when it declares none
     pub fn get_base_urls(&self) -> Vec<String> {
-        self.config
+        match self.active_env() {
+            Some(env) if !env.base_urls.is_empty() => env.base_urls.clone(),
+            _ => self
+                .config
+                .as_ref()
+                .map(|c| c.base_urls.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The selected `env` block, the first one declared when none is selected
+    pub fn active_env(&self) -> Option<&EnvBlock> {
+        let envs = &self.config.as_ref()?.envs;
+        match self.active_env {
+            Some(ref name) => envs.iter().find(|e| &e.name == name),
+            None => envs.first(),
+        }
+    }
+
+    /// Make `name` the active environment
+    pub fn select_env(&mut self, name: &str) -> Result<(), String> {
+        let envs: Vec<&str> = self
+            .config
+            .iter()
+            .flat_map(|c| c.envs.iter().map(|e| e.name.as_str()))
+            .collect();
+        if !envs.contains(&name) {
+            return Err(if envs.is_empty() {
+                format!("Unknown env `{}`, no env block is declared", name)
+            } else {
+                format!("Unknown env `{}`, declared: {}", name, envs.join(", "))
+            });
+        }
+        self.active_env = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Config variables, with the defaults of the active environment over the config ones and
+    /// secrets read from the environment or commands
+    pub fn get_variables(&self) -> Vec<VariableDefinition> {
+        let mut variables = self.env_variables(self.active_env());
+        for variable in &mut variables {
+            variable.secret = self.resolve_default(&mut variable.default_value, &variable.env);
+            let Some(ref command) = variable.command else {
+                continue;
+            };
+            // A failing command falls back on the default
+            let (output, expires_in) = self.commands.output(command, variable.ttl);
+            match output {
+                Ok(value) => {
+                    variable.default_value = Some(value);
+                    variable.secret = true;
+                }
+                Err(e) => variable.error = Some(e),
+            }
+            variable.expires_in = expires_in;
+        }
+        variables
+    }
+
+    /// Config variables, with the defaults of `env` over the config ones
+    pub fn env_variables(&self, env: Option<&EnvBlock>) -> Vec<VariableDefinition> {
+        let mut variables = self
+            .config
+            .as_ref()
+            .map(|c| c.variables.clone())
+            .unwrap_or_default();
+        for variable in env.iter().flat_map(|e| e.variables.iter()) {
+            match variables.iter_mut().find(|v| v.name == variable.name) {
+                // Without a default of its own the config default still applies
+                Some(existing) => {
+                    existing.var_type = variable.var_type.clone();
+                    if variable.default_value.is_some()
+                        || variable.env.is_some()
+                        || variable.command.is_some()
+                    {
+                        existing.default_value = variable.default_value.clone();
+                        existing.env = variable.env.clone();
+                        existing.command = variable.command.clone();
+                        existing.ttl = variable.ttl;
+                    }
+                }
+                None => variables.push(variable.clone()),
+            }
+        }
+        variables
+    }
+
+    /// Config headers, with the defaults of the active environment over the config ones
+    pub fn get_headers(&self) -> Vec<HeaderDefinition> {
+        let mut headers = self
+            .config
             .as_ref()
-            .map(|c| c.base_urls.clone())
-            .unwrap_or_default()
+            .map(|c| c.headers.clone())
+            .unwrap_or_default();
+        for header in self.active_env().iter().flat_map(|e| e.headers.iter()) {
+            match headers
+                .iter_mut()
+                .find(|h| h.name.eq_ignore_ascii_case(&header.name))
+            {
+                Some(_) if header.default_value.is_none() && header.env.is_none() => {}
+                Some(existing) => *existing = header.clone(),
+                None => headers.push(header.clone()),
+            }
+        }
+        for header in &mut headers {
+            header.secret = self.resolve_default(&mut header.default_value, &header.env);
+        }
+        headers
+    }
+
+    /// Read `env("NAME")` and `{env:NAME}` of a default, returns whether anything was read
+    fn resolve_default(&self, default: &mut Option<String>, env: &Option<String>) -> bool {
+        if let Some(name) = env {
+            *default = secrets::lookup(name, &self.env_files);
+            return default.is_some();
+        }
+        match default {
+            Some(value) => {
+                let (expanded, replaced) = secrets::expand(value, &self.env_files);
+                *value = expanded;
+                replaced
+            }
+            None => false,
+        }
+    }
+
+    /// Environment variables read by config variables and headers that are not set
+    pub fn missing_env(&self) -> Vec<String> {
+        let mut defaults: Vec<(&Option<String>, &Option<String>)> = Vec::new();
+        if let Some(ref config) = self.config {
+            let blocks = std::iter::once((&config.variables, &config.headers))
+                .chain(config.envs.iter().map(|e| (&e.variables, &e.headers)));
+            for (variables, headers) in blocks {
+                defaults.extend(variables.iter().map(|v| (&v.default_value, &v.env)));
+                defaults.extend(headers.iter().map(|h| (&h.default_value, &h.env)));
+            }
+        }
+
+        let mut missing: Vec<String> = Vec::new();
+        for (default, env) in defaults {
+            let names = env
+                .iter()
+                .cloned()
+                .chain(default.iter().flat_map(|d| secrets::references(d)));
+            for name in names {
+                if secrets::lookup(&name, &self.env_files).is_none() && !missing.contains(&name) {
+                    missing.push(name);
+                }
+            }
+        }
+        missing
     }
 
     pub fn to_endpoints(&self) -> Vec<ApiEndpoint> {
         let base_url = self.get_base_urls().first().cloned();
+        let config_auth = self.config.as_ref().and_then(|c| c.auth.as_ref());
         let mut endpoints = Vec::new();
         let mut id_counter = 0;
 
@@ -278,12 +1004,18 @@ impl RqcConfig {
                     description: method.description.clone(),
                     request: method.request.clone(),
                     response: method.response.clone(),
+                    status: method.status.clone(),
+                    responses: method.responses.clone(),
+                    content_type: method.content_type.clone(),
                     events: None,
                     sse_events: None,
                     auth: None,
                     connect_headers: None,
                     category_id: None,
                     category_name: None,
+                    expect: method.expect.clone(),
+                    mock: method.mock,
+                    http_auth: closest_auth(method.auth.as_ref().or(config_auth)),
                 });
             }
         }
@@ -301,12 +1033,18 @@ impl RqcConfig {
                 description: ws.description.clone(),
                 request: None,
                 response: None,
+                status: None,
+                responses: Vec::new(),
+                content_type: None,
                 events: Some(ws.events.clone()),
                 sse_events: None,
                 auth: None,
                 connect_headers: None,
                 category_id: None,
                 category_name: None,
+                expect: Vec::new(),
+                mock: None,
+                http_auth: None,
             });
         }
 
@@ -323,12 +1061,18 @@ impl RqcConfig {
                 description: sio.description.clone(),
                 request: None,
                 response: None,
+                status: None,
+                responses: Vec::new(),
+                content_type: None,
                 events: Some(sio.events.clone()),
                 sse_events: None,
                 auth: sio.auth.clone(),
                 connect_headers: sio.connect_headers.clone(),
                 category_id: None,
                 category_name: None,
+                expect: Vec::new(),
+                mock: None,
+                http_auth: None,
             });
         }
 
@@ -353,12 +1097,18 @@ impl RqcConfig {
                 description: sse.description.clone(),
                 request: sse.request.clone(),
                 response: None,
+                status: None,
+                responses: Vec::new(),
+                content_type: None,
                 events: None,
                 sse_events: Some(sse.events.clone()),
                 auth: None,
                 connect_headers: None,
                 category_id: None,
                 category_name: None,
+                expect: Vec::new(),
+                mock: None,
+                http_auth: None,
             });
         }
 
@@ -367,6 +1117,8 @@ impl RqcConfig {
             category: &CategoryBlock,
             base_url: &Option<String>,
             prefix_stack: &str,
+            parent_mock: Option<bool>,
+            parent_auth: Option<&AuthBlock>,
             endpoints: &mut Vec<ApiEndpoint>,
             id_counter: &mut usize,
         ) {
@@ -375,6 +1127,8 @@ impl RqcConfig {
             } else {
                 prefix_stack.to_string()
             };
+            let mock = category.mock.or(parent_mock);
+            let auth = category.auth.as_ref().or(parent_auth);
 
             // Process HTTP APIs in this category
             for api in &category.apis {
@@ -400,12 +1154,18 @@ impl RqcConfig {
                         description: method.description.clone(),
                         request: method.request.clone(),
                         response: method.response.clone(),
+                        status: method.status.clone(),
+                        responses: method.responses.clone(),
+                        content_type: method.content_type.clone(),
                         events: None,
                         sse_events: None,
                         auth: None,
                         connect_headers: None,
                         category_id: Some(category.id.clone()),
                         category_name: category.name.clone(),
+                        expect: method.expect.clone(),
+                        mock: method.mock.or(mock),
+                        http_auth: closest_auth(method.auth.as_ref().or(auth)),
                     });
                 }
             }
@@ -423,12 +1183,18 @@ impl RqcConfig {
                     description: ws.description.clone(),
                     request: None,
                     response: None,
+                    status: None,
+                    responses: Vec::new(),
+                    content_type: None,
                     events: Some(ws.events.clone()),
                     sse_events: None,
                     auth: None,
                     connect_headers: None,
                     category_id: Some(category.id.clone()),
                     category_name: category.name.clone(),
+                    expect: Vec::new(),
+                    mock: None,
+                    http_auth: None,
                 });
             }
 
@@ -445,12 +1211,18 @@ impl RqcConfig {
                     description: sio.description.clone(),
                     request: None,
                     response: None,
+                    status: None,
+                    responses: Vec::new(),
+                    content_type: None,
                     events: Some(sio.events.clone()),
                     sse_events: None,
                     auth: sio.auth.clone(),
                     connect_headers: sio.connect_headers.clone(),
                     category_id: Some(category.id.clone()),
                     category_name: category.name.clone(),
+                    expect: Vec::new(),
+                    mock: None,
+                    http_auth: None,
                 });
             }
 
@@ -477,23 +1249,45 @@ impl RqcConfig {
                     description: sse.description.clone(),
                     request: sse.request.clone(),
                     response: None,
+                    status: None,
+                    responses: Vec::new(),
+                    content_type: None,
                     events: None,
                     sse_events: Some(sse.events.clone()),
                     auth: None,
                     connect_headers: None,
                     category_id: Some(category.id.clone()),
                     category_name: category.name.clone(),
+                    expect: Vec::new(),
+                    mock: None,
+                    http_auth: None,
                 });
             }
 
             // Process nested categories
             for child in &category.children {
-                process_category(child, base_url, &current_prefix, endpoints, id_counter);
+                process_category(
+                    child,
+                    base_url,
+                    &current_prefix,
+                    mock,
+                    auth,
+                    endpoints,
+                    id_counter,
+                );
             }
         }
 
         for category in &self.categories {
-            process_category(category, &base_url, "", &mut endpoints, &mut id_counter);
+            process_category(
+                category,
+                &base_url,
+                "",
+                None,
+                config_auth,
+                &mut endpoints,
+                &mut id_counter,
+            );
         }
 
         endpoints
@@ -0,0 +1,87 @@
+//! Variables read from the output of a command, `variable token from_command("...") ttl(3000)`
+//!
+//! Outputs are cached by command, for `ttl` seconds or until the cache is cleared, so a token
+//! printed by a CLI tool is fetched again only once it expires. Failures are cached too, they
+//! are reported instead of running a failing command on every request.
+
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Default)]
+pub struct CommandCache {
+    outputs: Mutex<HashMap<String, CachedOutput>>,
+}
+
+#[derive(Debug, Clone)]
+struct CachedOutput {
+    result: Result<String, String>,
+    ran_at: Instant,
+}
+
+impl CommandCache {
+    /// Trimmed standard output of `command`, run again once `ttl` seconds have passed.
+    /// Returns the seconds left before it expires along with the output.
+    pub fn output(&self, command: &str, ttl: Option<u64>) -> (Result<String, String>, Option<u64>) {
+        let ttl = ttl.map(Duration::from_secs);
+        let mut outputs = self.outputs.lock().unwrap();
+
+        let fresh = outputs
+            .get(command)
+            .is_some_and(|cached| ttl.is_none_or(|ttl| cached.ran_at.elapsed() < ttl));
+        if !fresh {
+            let cached = CachedOutput {
+                result: run(command),
+                ran_at: Instant::now(),
+            };
+            outputs.insert(command.to_string(), cached);
+        }
+
+        // Rounded up, a value read again when this runs out is a new one
+        let cached = &outputs[command];
+        let expires_in = ttl.map(|ttl| {
+            let left = ttl.saturating_sub(cached.ran_at.elapsed());
+            left.as_secs() + u64::from(left.subsec_nanos() > 0)
+        });
+        (cached.result.clone(), expires_in)
+    }
+
+    /// Forget every output, commands run again the next time their variable is read
+    pub fn clear(&self) {
+        self.outputs.lock().unwrap().clear();
+    }
+}
+
+fn run(command: &str) -> Result<String, String> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+
+    let output = shell
+        .arg(command)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("`{}` could not be run: {}", command, e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = match stderr.trim() {
+            "" => output.status.to_string(),
+            stderr => stderr.lines().last().unwrap_or(stderr).to_string(),
+        };
+        return Err(format!("`{}` failed: {}", command, reason));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if stdout.is_empty() {
+        return Err(format!("`{}` printed nothing", command));
+    }
+    Ok(stdout)
+}
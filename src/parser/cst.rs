@@ -0,0 +1,257 @@
+//! Lossless concrete syntax tree, used by the formatter.
+//!
+//! Every token keeps its raw source text and the whitespace in front of it, so
+//! `SyntaxTree::to_source` gives back the input byte for byte, comments and blank
+//! lines included. The tree only knows about lines and `{ ... }` blocks, what the
+//! statements mean is left to the parser.
+
+use super::lexer::{Lexer, TokenType};
+
+#[derive(Debug, Clone)]
+pub struct CstToken {
+    pub kind: TokenType,
+    /// Raw source text, quotes and comment markers included
+    pub text: String,
+    /// Whitespace between the previous token and this one
+    pub leading: String,
+}
+
+impl CstToken {
+    /// Number of line breaks in front of the token
+    pub fn newlines_before(&self) -> usize {
+        self.leading.matches('\n').count()
+    }
+
+    fn is_comment(&self) -> bool {
+        matches!(self.kind, TokenType::Comment | TokenType::DocComment)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Element {
+    Token(CstToken),
+    Block(Block),
+}
+
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub open: CstToken,
+    /// `//` comment on the same line as the `{`
+    pub open_comment: Option<CstToken>,
+    pub items: Vec<Node>,
+    /// Missing when the file ends before the block is closed
+    pub close: Option<CstToken>,
+}
+
+/// A statement is everything on one line, plus any blocks opened on it
+#[derive(Debug, Clone, Default)]
+pub struct Statement {
+    pub elements: Vec<Element>,
+    pub trailing_comment: Option<CstToken>,
+}
+
+impl Statement {
+    fn first_token(&self) -> Option<&CstToken> {
+        match self.elements.first()? {
+            Element::Token(token) => Some(token),
+            Element::Block(block) => Some(&block.open),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Node {
+    Statement(Statement),
+    /// A `//` or `/** */` comment on its own line
+    Comment(CstToken),
+}
+
+impl Node {
+    /// Whether the source had a blank line in front of this node
+    pub fn blank_line_before(&self) -> bool {
+        let first = match self {
+            Node::Statement(statement) => statement.first_token(),
+            Node::Comment(comment) => Some(comment),
+        };
+        first.is_some_and(|t| t.newlines_before() > 1)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SyntaxTree {
+    pub items: Vec<Node>,
+    /// Whitespace at the end of the file
+    pub trailing: String,
+}
+
+impl SyntaxTree {
+    pub fn parse(source: &str) -> Self {
+        let mut lexer = Lexer::new(source);
+        let mut tokens = Vec::new();
+        let mut end = 0;
+
+        loop {
+            let token = lexer.next_token();
+            if token.token_type == TokenType::Eof {
+                break;
+            }
+            tokens.push(CstToken {
+                kind: token.token_type,
+                text: source[token.offset..token.offset + token.len].to_string(),
+                leading: source[end..token.offset].to_string(),
+            });
+            end = token.offset + token.len;
+        }
+
+        let mut builder = Builder {
+            tokens: tokens.into_iter().peekable(),
+        };
+
+        SyntaxTree {
+            items: builder.items(false),
+            trailing: source[end..].to_string(),
+        }
+    }
+
+    /// Print the tree back, this is always identical to the parsed source
+    pub fn to_source(&self) -> String {
+        let mut tokens: Vec<&CstToken> = Vec::new();
+        collect_tokens(&self.items, &mut tokens);
+
+        let mut out = String::new();
+        for token in tokens {
+            out.push_str(&token.leading);
+            out.push_str(&token.text);
+        }
+        out.push_str(&self.trailing);
+        out
+    }
+}
+
+fn collect_tokens<'a>(items: &'a [Node], tokens: &mut Vec<&'a CstToken>) {
+    for item in items {
+        match item {
+            Node::Comment(comment) => tokens.push(comment),
+            Node::Statement(statement) => {
+                for element in &statement.elements {
+                    match element {
+                        Element::Token(token) => tokens.push(token),
+                        Element::Block(block) => {
+                            tokens.push(&block.open);
+                            tokens.extend(block.open_comment.iter());
+                            collect_tokens(&block.items, tokens);
+                            if let Some(ref close) = block.close {
+                                tokens.push(close);
+                            }
+                        }
+                    }
+                }
+                if let Some(ref comment) = statement.trailing_comment {
+                    tokens.push(comment);
+                }
+            }
+        }
+    }
+}
+
+struct Builder {
+    tokens: std::iter::Peekable<std::vec::IntoIter<CstToken>>,
+}
+
+impl Builder {
+    /// Read nodes until the `}` closing the current block (left in the stream) or the end of file
+    fn items(&mut self, in_block: bool) -> Vec<Node> {
+        let mut items: Vec<Node> = Vec::new();
+
+        while let Some(token) = self.tokens.peek() {
+            if token.kind == TokenType::RBrace {
+                if in_block {
+                    break;
+                }
+                // Stray `}`, keep it as a statement of its own
+                let token = self.tokens.next().unwrap();
+                items.push(Node::Statement(Statement {
+                    elements: vec![Element::Token(token)],
+                    trailing_comment: None,
+                }));
+                continue;
+            }
+
+            if token.is_comment() {
+                let comment = self.tokens.next().unwrap();
+                items.push(Node::Comment(comment));
+                continue;
+            }
+
+            // A `{` on its own line still belongs to the statement above it
+            if token.kind == TokenType::LBrace {
+                if let Some(Node::Statement(previous)) = items.last_mut() {
+                    if previous.trailing_comment.is_none()
+                        && !matches!(previous.elements.last(), Some(Element::Block(_)))
+                    {
+                        self.statement(previous);
+                        continue;
+                    }
+                }
+            }
+
+            let mut statement = Statement::default();
+            self.statement(&mut statement);
+            items.push(Node::Statement(statement));
+        }
+
+        items
+    }
+
+    /// Read tokens into `statement` until the end of its line
+    fn statement(&mut self, statement: &mut Statement) {
+        let mut parens = 0usize;
+
+        while let Some(token) = self.tokens.peek() {
+            let continues_line = statement.elements.is_empty()
+                || token.newlines_before() == 0
+                || parens > 0
+                || token.kind == TokenType::LBrace && !statement.elements.is_empty();
+
+            if !continues_line || token.kind == TokenType::RBrace {
+                break;
+            }
+
+            if token.is_comment() {
+                if token.newlines_before() == 0 && token.kind == TokenType::Comment {
+                    statement.trailing_comment = self.tokens.next();
+                }
+                break;
+            }
+
+            let token = self.tokens.next().unwrap();
+            match token.kind {
+                TokenType::LParen => parens += 1,
+                TokenType::RParen => parens = parens.saturating_sub(1),
+                _ => {}
+            }
+
+            if token.kind == TokenType::LBrace {
+                let open_comment = match self.tokens.peek() {
+                    Some(t) if t.kind == TokenType::Comment && t.newlines_before() == 0 => {
+                        self.tokens.next()
+                    }
+                    _ => None,
+                };
+                let items = self.items(true);
+                let close = match self.tokens.peek() {
+                    Some(t) if t.kind == TokenType::RBrace => self.tokens.next(),
+                    _ => None,
+                };
+                statement.elements.push(Element::Block(Block {
+                    open: token,
+                    open_comment,
+                    items,
+                    close,
+                }));
+            } else {
+                statement.elements.push(Element::Token(token));
+            }
+        }
+    }
+}
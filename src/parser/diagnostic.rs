@@ -0,0 +1,53 @@
+use std::fmt::Write;
+
+/// Location of a piece of source text
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+    pub len: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct SyntaxError {
+    pub message: String,
+    pub span: Span,
+}
+
+/// Render a rustc-style message with the offending source line and a caret under the span
+///
+/// ```text
+/// error: expected `{` after api path, found `get`
+///  --> user.rqc:3:12
+///   |
+/// 3 | api /users get {
+///   |            ^^^
+/// ```
+pub fn render_snippet(level: &str, message: &str, file: &str, source: &str, span: Span) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{}: {}", level, message);
+
+    let line_text = source.lines().nth(span.line.saturating_sub(1)).unwrap_or("");
+    let gutter = " ".repeat(span.line.to_string().len());
+    let _ = writeln!(out, "{}--> {}:{}:{}", gutter, file, span.line, span.column);
+    let _ = writeln!(out, "{} |", gutter);
+    let _ = writeln!(out, "{} | {}", span.line, line_text);
+
+    // Keep tabs so the caret lines up with the source line
+    let padding: String = line_text
+        .chars()
+        .take(span.column.saturating_sub(1))
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let available = line_text.chars().count().saturating_sub(span.column.saturating_sub(1));
+    let width = source
+        .get(span.offset..span.offset + span.len)
+        .map(|s| s.lines().next().unwrap_or("").chars().count())
+        .unwrap_or(0)
+        .min(available)
+        .max(1);
+    let _ = write!(out, "{} | {}{}", gutter, padding, "^".repeat(width));
+
+    out
+}
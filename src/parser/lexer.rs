@@ -1,4 +1,6 @@
-#[derive(Debug, Clone, PartialEq)]
+use super::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TokenType {
     Ident,
     String,
@@ -7,8 +9,13 @@ pub enum TokenType {
     RBrace,
     LParen,
     RParen,
+    LBracket,
+    RBracket,
     Question,
+    /// `|` between the alternatives of a union type
+    Pipe,
     At,
+    Operator,
     Comment,
     DocComment,
     Eof,
@@ -19,12 +26,65 @@ pub struct Token {
     pub token_type: TokenType,
     pub literal: String,
     pub line: usize,
+    /// 1-based column, counted in characters
+    pub column: usize,
+    /// Byte offset of the first character in the source
+    pub offset: usize,
+    /// Length of the token in the source, in bytes
+    pub len: usize,
+}
+
+impl TokenType {
+    /// How the token type is named in error messages
+    pub fn describe(&self) -> &'static str {
+        match self {
+            TokenType::Ident => "identifier",
+            TokenType::String => "string",
+            TokenType::Number => "number",
+            TokenType::LBrace => "`{`",
+            TokenType::RBrace => "`}`",
+            TokenType::LParen => "`(`",
+            TokenType::LBracket => "`[`",
+            TokenType::RBracket => "`]`",
+            TokenType::RParen => "`)`",
+            TokenType::Question => "`?`",
+            TokenType::Pipe => "`|`",
+            TokenType::At => "`@`",
+            TokenType::Operator => "operator",
+            TokenType::Comment | TokenType::DocComment => "comment",
+            TokenType::Eof => "end of file",
+        }
+    }
+}
+
+impl Token {
+    /// How this token is shown in error messages, e.g. `` `get` `` or `string "abc"`
+    pub fn describe(&self) -> String {
+        match self.token_type {
+            TokenType::String => format!("string \"{}\"", self.literal),
+            TokenType::Eof | TokenType::Comment | TokenType::DocComment => {
+                self.token_type.describe().to_string()
+            }
+            _ => format!("`{}`", self.literal),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        Span {
+            line: self.line,
+            column: self.column,
+            offset: self.offset,
+            len: self.len,
+        }
+    }
 }
 
 pub struct Lexer {
     input: Vec<char>,
     position: usize,
     line: usize,
+    column: usize,
+    offset: usize,
 }
 
 impl Lexer {
@@ -33,6 +93,8 @@ impl Lexer {
             input: input.chars().collect(),
             position: 0,
             line: 1,
+            column: 1,
+            offset: 0,
         }
     }
 
@@ -48,7 +110,11 @@ impl Lexer {
         if let Some(ch) = self.current_char() {
             if ch == '\n' {
                 self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
             }
+            self.offset += ch.len_utf8();
             self.position += 1;
         }
     }
@@ -82,9 +148,15 @@ impl Lexer {
     fn read_identifier(&mut self) -> String {
         let mut result = String::new();
         while let Some(ch) = self.current_char() {
-            if ch.is_alphanumeric() || ch == '_' || ch == '/' || ch == ':' || ch == '.' || ch == '-' || ch == ',' {
+            if ch.is_alphanumeric() || ch == '_' || ch == '/' || ch == ':' || ch == '.' || ch == '-' || ch == ',' || ch == '*' {
                 result.push(ch);
                 self.advance();
+            } else if ch == '{' && !result.is_empty() && self.placeholder_len() > 0 {
+                // Path placeholder like /users/{id}, keep it as part of the identifier
+                for _ in 0..self.placeholder_len() {
+                    result.push(self.current_char().unwrap());
+                    self.advance();
+                }
             } else {
                 break;
             }
@@ -92,6 +164,23 @@ impl Lexer {
         result
     }
 
+    /// Length of a `{name}` placeholder starting at the current position, 0 if there is none.
+    /// `{$uuid}` and the like are built-in dynamic variables.
+    fn placeholder_len(&self) -> usize {
+        let start = if self.peek_char_at(1) == Some('$') { 2 } else { 1 };
+        let mut len = start;
+        while let Some(ch) = self.peek_char_at(len) {
+            if ch == '}' {
+                return if len > start { len + 1 } else { 0 };
+            }
+            if !(ch.is_alphanumeric() || ch == '_') {
+                return 0;
+            }
+            len += 1;
+        }
+        0
+    }
+
     fn read_number(&mut self) -> String {
         let mut result = String::new();
         let mut has_dot = false;
@@ -117,6 +206,19 @@ impl Lexer {
         result
     }
 
+    fn read_operator(&mut self) -> String {
+        let mut result = String::new();
+        while let Some(ch) = self.current_char() {
+            if matches!(ch, '=' | '!' | '<' | '>' | '~') {
+                result.push(ch);
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        result
+    }
+
     fn read_comment(&mut self) -> String {
         self.advance(); // skip first /
         self.advance(); // skip second /
@@ -165,103 +267,54 @@ impl Lexer {
         self.skip_whitespace();
 
         let line = self.line;
+        let column = self.column;
+        let offset = self.offset;
 
-        match self.current_char() {
-            None => Token {
-                token_type: TokenType::Eof,
-                literal: String::new(),
-                line,
-            },
+        let (token_type, literal) = match self.current_char() {
+            None => (TokenType::Eof, String::new()),
             Some(ch) => match ch {
-                '{' => {
-                    self.advance();
-                    Token {
-                        token_type: TokenType::LBrace,
-                        literal: "{".to_string(),
-                        line,
-                    }
-                }
-                '}' => {
-                    self.advance();
-                    Token {
-                        token_type: TokenType::RBrace,
-                        literal: "}".to_string(),
-                        line,
-                    }
-                }
-                '(' => {
-                    self.advance();
-                    Token {
-                        token_type: TokenType::LParen,
-                        literal: "(".to_string(),
-                        line,
-                    }
-                }
-                ')' => {
-                    self.advance();
-                    Token {
-                        token_type: TokenType::RParen,
-                        literal: ")".to_string(),
-                        line,
-                    }
-                }
-                '?' => {
-                    self.advance();
-                    Token {
-                        token_type: TokenType::Question,
-                        literal: "?".to_string(),
-                        line,
-                    }
-                }
-                '@' => {
-                    self.advance();
-                    Token {
-                        token_type: TokenType::At,
-                        literal: "@".to_string(),
-                        line,
-                    }
-                }
-                '"' | '\'' => {
-                    let s = self.read_string();
-                    Token {
-                        token_type: TokenType::String,
-                        literal: s,
-                        line,
-                    }
-                }
-                '/' if self.peek_char() == Some('/') => {
-                    let comment = self.read_comment();
-                    Token {
-                        token_type: TokenType::Comment,
-                        literal: comment,
-                        line,
-                    }
-                }
+                '{' => self.single(TokenType::LBrace, ch),
+                '}' => self.single(TokenType::RBrace, ch),
+                '(' => self.single(TokenType::LParen, ch),
+                '[' => self.single(TokenType::LBracket, ch),
+                ']' => self.single(TokenType::RBracket, ch),
+                ')' => self.single(TokenType::RParen, ch),
+                '?' => self.single(TokenType::Question, ch),
+                '|' => self.single(TokenType::Pipe, ch),
+                '@' => self.single(TokenType::At, ch),
+                '=' | '!' | '<' | '>' | '~' => (TokenType::Operator, self.read_operator()),
+                '"' | '\'' => (TokenType::String, self.read_string()),
+                '/' if self.peek_char() == Some('/') => (TokenType::Comment, self.read_comment()),
                 '/' if self.peek_char() == Some('*') && self.peek_char_at(2) == Some('*') => {
-                    let doc = self.read_doc_comment();
-                    Token {
-                        token_type: TokenType::DocComment,
-                        literal: doc,
-                        line,
-                    }
+                    (TokenType::DocComment, self.read_doc_comment())
                 }
-                c if c.is_ascii_digit() || (c == '-' && self.peek_char().map_or(false, |p| p.is_ascii_digit())) => {
-                    let num = self.read_number();
-                    Token {
-                        token_type: TokenType::Number,
-                        literal: num,
-                        line,
-                    }
+                c if c.is_ascii_digit() || (c == '-' && self.peek_char().is_some_and(|p| p.is_ascii_digit())) => {
+                    (TokenType::Number, self.read_number())
                 }
                 _ => {
-                    let ident = self.read_identifier();
-                    Token {
-                        token_type: TokenType::Ident,
-                        literal: ident,
-                        line,
+                    let mut ident = self.read_identifier();
+                    if ident.is_empty() {
+                        // Unknown character, consume it so the parser can skip it
+                        ident.push(ch);
+                        self.advance();
                     }
+                    (TokenType::Ident, ident)
                 }
             },
+        };
+
+        Token {
+            token_type,
+            literal,
+            line,
+            column,
+            offset,
+            len: self.offset - offset,
         }
     }
+
+    fn single(&mut self, token_type: TokenType, ch: char) -> (TokenType, String) {
+        self.advance();
+        (token_type, ch.to_string())
+    }
 }
@@ -0,0 +1,273 @@
+//! Resolution of `type` declarations.
+//!
+//! Runs on the merged project after imports, so a type declared in one file can be used in
+//! any other. Field references (`owner User`, `members [User]`) get the fields of the type
+//! as `nested`, and `...User` spreads are replaced by the fields of the type. Fields
+//! referring to an `enum` get its values.
+
+use super::ast::{
+    enum_value_type, ApiBlock, CategoryBlock, EnumDefinition, Field, MockValue, RqcConfig,
+    SchemaBlock, SseBlock, TypeDefinition, WsBlock,
+};
+use super::diagnostic::Span;
+use std::collections::HashMap;
+
+/// A reference that cannot be resolved
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolveError {
+    pub message: String,
+    /// The field or spread making the reference
+    pub span: Span,
+    /// The `type` whose declaration holds `span`, `None` when it is in the resolved config
+    /// itself. Types may come from other files
+    pub in_type: Option<String>,
+}
+
+/// Resolve every type reference and spread of the project, using the project's own types
+pub fn resolve_types(config: &mut RqcConfig) -> Result<(), String> {
+    let types = config.types.clone();
+    let enums = config.enums.clone();
+
+    let mut errors = Vec::new();
+    let names: Vec<&str> = types
+        .iter()
+        .map(|t| t.name.as_str())
+        .chain(enums.iter().map(|e| e.name.as_str()))
+        .collect();
+    for (i, name) in names.iter().enumerate() {
+        if names[..i].contains(name) {
+            errors.push(format!("type `{}` is declared more than once", name));
+        }
+    }
+    errors.extend(
+        resolve_types_with(config, &types, &enums)
+            .into_iter()
+            .map(|e| e.message),
+    );
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+/// Resolve the references of `config` against `types` and `enums`, which may come from
+/// other files. Returns one message per problem, the references that could be resolved are
+/// resolved. When a type is declared twice the first declaration is used.
+pub fn resolve_types_with(
+    config: &mut RqcConfig,
+    types: &[TypeDefinition],
+    enums: &[EnumDefinition],
+) -> Vec<ResolveError> {
+    let mut resolver = Resolver::new(types, enums);
+
+    for definition in config.types.iter_mut() {
+        if let Some(schema) = resolver.resolve_type(&definition.name, Span::default()) {
+            definition.schema = schema;
+        }
+    }
+
+    for api in config.apis.iter_mut() {
+        resolver.api(api);
+    }
+    for ws in config.ws_apis.iter_mut().chain(config.socketio_apis.iter_mut()) {
+        resolver.ws(ws);
+    }
+    for sse in config.sse_apis.iter_mut() {
+        resolver.sse(sse);
+    }
+    for category in config.categories.iter_mut() {
+        resolver.category(category);
+    }
+
+    // Every problem is reported once, even when the type is used in many places
+    let mut errors = Vec::new();
+    for error in resolver.errors {
+        if !errors.contains(&error) {
+            errors.push(error);
+        }
+    }
+    errors
+}
+
+struct Resolver<'a> {
+    types: HashMap<&'a str, &'a SchemaBlock>,
+    enums: HashMap<&'a str, &'a [MockValue]>,
+    /// Fully expanded types
+    resolved: HashMap<String, SchemaBlock>,
+    /// Types being expanded, to detect cycles
+    stack: Vec<String>,
+    errors: Vec<ResolveError>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(types: &'a [TypeDefinition], enums: &'a [EnumDefinition]) -> Self {
+        let mut map = HashMap::new();
+        for definition in types {
+            map.entry(definition.name.as_str())
+                .or_insert(&definition.schema);
+        }
+        let mut enum_map = HashMap::new();
+        for definition in enums {
+            enum_map
+                .entry(definition.name.as_str())
+                .or_insert(definition.values.as_slice());
+        }
+
+        Resolver {
+            types: map,
+            enums: enum_map,
+            resolved: HashMap::new(),
+            stack: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn error(&mut self, message: String, span: Span) {
+        self.errors.push(ResolveError {
+            message,
+            span,
+            in_type: self.stack.last().cloned(),
+        });
+    }
+
+    /// The expanded fields of a type, `None` if it is unknown or part of a cycle. `span` is
+    /// the reference that asks for it
+    fn resolve_type(&mut self, name: &str, span: Span) -> Option<SchemaBlock> {
+        if let Some(schema) = self.resolved.get(name) {
+            return Some(schema.clone());
+        }
+
+        if let Some(start) = self.stack.iter().position(|n| n == name) {
+            let mut cycle = self.stack[start..].to_vec();
+            cycle.push(name.to_string());
+            let message = format!("type `{}` refers to itself: {}", name, cycle.join(" -> "));
+            self.error(message, span);
+            return None;
+        }
+
+        let mut schema = self.types.get(name).map(|s| (*s).clone())?;
+        self.stack.push(name.to_string());
+        self.schema(&mut schema);
+        self.stack.pop();
+
+        self.resolved.insert(name.to_string(), schema.clone());
+        Some(schema)
+    }
+
+    fn schema(&mut self, schema: &mut SchemaBlock) {
+        for field in schema.fields.iter_mut() {
+            self.field(field);
+        }
+
+        // Insert spread fields from the last one so earlier positions stay valid, fields
+        // declared in the block win over spread ones of the same name
+        let spreads = std::mem::take(&mut schema.spreads);
+        for spread in spreads.iter().rev() {
+            if self.enums.contains_key(spread.name.as_str()) {
+                let message =
+                    format!("`...{}` spreads an enum, only types can be spread", spread.name);
+                self.error(message, spread.span);
+                continue;
+            }
+            let usage = format!("in `...{}`", spread.name);
+            let Some(spread_schema) = self.lookup(&spread.name, &usage, spread.span) else {
+                continue;
+            };
+            let position = spread.position.min(schema.fields.len());
+            let added: Vec<Field> = spread_schema
+                .fields
+                .into_iter()
+                .filter(|f| !schema.fields.iter().any(|own| own.name == f.name))
+                .collect();
+            schema.fields.splice(position..position, added);
+        }
+    }
+
+    fn field(&mut self, field: &mut Field) {
+        if let Some(values) = field.type_ref.as_deref().and_then(|n| self.enums.get(n)) {
+            if field.enum_values.is_none() {
+                field.field_type = enum_value_type(values);
+                field.enum_values = Some(values.to_vec());
+            }
+        } else if let Some(ref name) = field.type_ref {
+            if field.nested.is_none() {
+                let usage = format!("for field `{}`", field.name);
+                if let Some(schema) = self.lookup(name, &usage, field.span) {
+                    field.nested = Some(Box::new(schema));
+                }
+            }
+        } else if let Some(ref mut nested) = field.nested {
+            self.schema(nested);
+        }
+
+        if let Some(ref mut items) = field.items {
+            self.field(items);
+        }
+        for variant in field.variants.iter_mut().flatten() {
+            self.field(variant);
+        }
+    }
+
+    /// Resolve a referenced type, reporting it when it does not exist
+    fn lookup(&mut self, name: &str, usage: &str, span: Span) -> Option<SchemaBlock> {
+        if !self.types.contains_key(name) {
+            self.error(format!("unknown type `{}` {}", name, usage), span);
+            return None;
+        }
+        self.resolve_type(name, span)
+    }
+
+    fn schema_opt(&mut self, schema: &mut Option<SchemaBlock>) {
+        if let Some(ref mut schema) = schema {
+            self.schema(schema);
+        }
+    }
+
+    fn api(&mut self, api: &mut ApiBlock) {
+        for method in api.methods.iter_mut() {
+            self.schema_opt(&mut method.request);
+            self.schema_opt(&mut method.response);
+            for response in method.responses.iter_mut() {
+                self.schema(&mut response.schema);
+            }
+        }
+    }
+
+    fn ws(&mut self, ws: &mut WsBlock) {
+        self.schema_opt(&mut ws.auth);
+        self.schema_opt(&mut ws.connect_headers);
+        for event in ws.events.iter_mut() {
+            self.schema_opt(&mut event.request);
+            self.schema_opt(&mut event.response);
+        }
+    }
+
+    fn sse(&mut self, sse: &mut SseBlock) {
+        self.schema_opt(&mut sse.request);
+        for event in sse.events.iter_mut() {
+            for field in event.fields.iter_mut() {
+                self.field(field);
+            }
+        }
+    }
+
+    fn category(&mut self, category: &mut CategoryBlock) {
+        for api in category.apis.iter_mut() {
+            self.api(api);
+        }
+        for ws in category
+            .ws_apis
+            .iter_mut()
+            .chain(category.socketio_apis.iter_mut())
+        {
+            self.ws(ws);
+        }
+        for sse in category.sse_apis.iter_mut() {
+            self.sse(sse);
+        }
+        for child in category.children.iter_mut() {
+            self.category(child);
+        }
+    }
+}
@@ -0,0 +1,111 @@
+//! Secrets read from the process environment and the `.env` files next to the entry file
+//!
+//! `default(env("API_TOKEN"))` and `{env:API_TOKEN}` in the default of a config variable or
+//! header are resolved on the server, the process environment wins over the files.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Files read next to the entry file, a later one overrides an earlier one
+pub const ENV_FILES: &[&str] = &[".env", ".rqc.env"];
+
+/// Variables of the `.env` files in `dir`, missing files are skipped
+pub fn load_env_files(dir: &Path) -> HashMap<String, String> {
+    let mut vars = HashMap::new();
+    for file in ENV_FILES {
+        if let Ok(content) = fs::read_to_string(dir.join(file)) {
+            vars.extend(parse_env_file(&content));
+        }
+    }
+    vars
+}
+
+/// `KEY=VALUE` lines, with optional `export ` prefixes, `#` comments and quoted values
+fn parse_env_file(content: &str) -> Vec<(String, String)> {
+    let mut vars = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        let value = value.trim();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let inner = &value[1..];
+                let inner = inner.find(quote).map(|end| &inner[..end]).unwrap_or(inner);
+                if quote == '"' {
+                    inner.replace("\\n", "\n").replace("\\\"", "\"")
+                } else {
+                    inner.to_string()
+                }
+            }
+            // An unquoted value ends at a ` #` comment
+            _ => value
+                .split_once(" #")
+                .map(|(v, _)| v)
+                .unwrap_or(value)
+                .trim_end()
+                .to_string(),
+        };
+        vars.push((key.trim().to_string(), value));
+    }
+    vars
+}
+
+/// Value of an environment variable, from the process first and then the `.env` files
+pub fn lookup(name: &str, env_files: &HashMap<String, String>) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .or_else(|| env_files.get(name).cloned())
+}
+
+/// Replace the `{env:NAME}` references of `text`, unset ones are left as they are.
+/// Returns whether anything was replaced.
+pub fn expand(text: &str, env_files: &HashMap<String, String>) -> (String, bool) {
+    let mut result = String::with_capacity(text.len());
+    let mut replaced = false;
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{env:") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 5..];
+        match after
+            .find('}')
+            .and_then(|end| Some((end, lookup(&after[..end], env_files)?)))
+        {
+            Some((end, value)) => {
+                result.push_str(&value);
+                replaced = true;
+                rest = &after[end + 1..];
+            }
+            None => {
+                result.push_str("{env:");
+                rest = after;
+            }
+        }
+    }
+
+    result.push_str(rest);
+    (result, replaced)
+}
+
+/// Names of the `{env:NAME}` references of `text`
+pub fn references(text: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{env:") {
+        let after = &rest[start + 5..];
+        let Some(end) = after.find('}') else {
+            break;
+        };
+        names.push(after[..end].to_string());
+        rest = &after[end + 1..];
+    }
+    names
+}
@@ -0,0 +1,530 @@
+//! Headless runner - executes endpoints defined in .rqc files from the terminal
+
+use crate::auth::{TokenCache, TokenRequest};
+use crate::dynamic;
+use crate::parser::{
+    ApiEndpoint, AuthBlock, AuthScheme, EndpointType, Field, FieldType, ParamLocation, RqcConfig,
+    SchemaBlock,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Options that affect how a request is built before it is sent
+#[derive(Debug, Default, Clone)]
+pub struct RunOptions {
+    /// Base URL to use instead of the first one declared in `config`
+    pub base_url: Option<String>,
+    /// Variable overrides from the command line (`--var key=value`)
+    pub vars: Vec<(String, String)>,
+}
+
+/// A fully resolved HTTP request, ready to be sent
+#[derive(Debug, Clone)]
+pub struct PreparedRequest {
+    pub method: String,
+    pub url: String,
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Value>,
+    /// Media type of the body, JSON when not set
+    pub content_type: Option<String>,
+    /// Username and password sent as `Basic` credentials
+    pub basic_auth: Option<(String, String)>,
+    /// OAuth2 token fetched before the request is sent, as a bearer token
+    pub token: Option<TokenRequest>,
+}
+
+/// The response of an executed request
+#[derive(Debug, Clone)]
+pub struct RunResponse {
+    pub status: u16,
+    pub status_text: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    pub elapsed: Duration,
+}
+
+impl RunResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Parse the body as JSON, if possible
+    pub fn json(&self) -> Option<Value> {
+        serde_json::from_str(&self.body).ok()
+    }
+}
+
+/// Find an HTTP endpoint by id (`api-1`), `METHOD /path` or name
+pub fn find_endpoint<'a>(
+    endpoints: &'a [ApiEndpoint],
+    query: &str,
+) -> Result<&'a ApiEndpoint, String> {
+    let http = || {
+        endpoints
+            .iter()
+            .filter(|e| e.endpoint_type == EndpointType::Http)
+    };
+    let query = query.trim();
+
+    if let Some(endpoint) = http().find(|e| e.id == query) {
+        return Ok(endpoint);
+    }
+
+    if let Some((method, path)) = query.split_once(char::is_whitespace) {
+        let method = method.to_uppercase();
+        let path = path.trim();
+        if let Some(endpoint) = http()
+            .find(|e| e.method.as_deref() == Some(method.as_str()) && e.path == path)
+        {
+            return Ok(endpoint);
+        }
+    }
+
+    let by_name: Vec<&ApiEndpoint> = http()
+        .filter(|e| {
+            e.name
+                .as_deref()
+                .is_some_and(|n| n.eq_ignore_ascii_case(query))
+        })
+        .collect();
+
+    match by_name.as_slice() {
+        [endpoint] => Ok(endpoint),
+        [] => Err(format!("No endpoint matches '{}'", query)),
+        many => Err(format!(
+            "'{}' is ambiguous, it matches: {}",
+            query,
+            many.iter()
+                .map(|e| describe_endpoint(e))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Short human readable description of an endpoint, e.g. `api-1 (GET /users)`
+pub fn describe_endpoint(endpoint: &ApiEndpoint) -> String {
+    format!(
+        "{} ({} {})",
+        endpoint.id,
+        endpoint.method.as_deref().unwrap_or("GET"),
+        endpoint.path
+    )
+}
+
+/// Collect config variable defaults, layered with command line overrides
+pub fn collect_variables(config: &RqcConfig, options: &RunOptions) -> HashMap<String, String> {
+    let mut vars = HashMap::new();
+
+    for var in config.get_variables() {
+        if let Some(value) = var.default_value {
+            vars.insert(var.name, value);
+        }
+    }
+
+    for (key, value) in &options.vars {
+        vars.insert(key.clone(), value.clone());
+    }
+
+    vars
+}
+
+/// Replace `{name}` references with variable values, then the `{$uuid}` style built-ins.
+/// Unknown names are left untouched
+pub fn interpolate(text: &str, vars: &HashMap<String, String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        match after.find('}') {
+            Some(end)
+                if after[..end]
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '_') =>
+            {
+                let name = &after[..end];
+                match vars.get(name) {
+                    Some(value) => result.push_str(value),
+                    None => {
+                        result.push('{');
+                        result.push_str(name);
+                        result.push('}');
+                    }
+                }
+                rest = &after[end + 1..];
+            }
+            _ => {
+                result.push('{');
+                rest = after;
+            }
+        }
+    }
+
+    result.push_str(rest);
+    dynamic::expand(&result)
+}
+
+/// Names of all `{name}` references in a string
+pub fn placeholders(text: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        match after.find('}') {
+            Some(end)
+                if end > 0
+                    && after[..end]
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || c == '_') =>
+            {
+                names.push(after[..end].to_string());
+                rest = &after[end + 1..];
+            }
+            _ => rest = after,
+        }
+    }
+
+    names
+}
+
+fn interpolate_json(value: Value, vars: &HashMap<String, String>) -> Value {
+    match value {
+        Value::String(s) => Value::String(interpolate(&s, vars)),
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|v| interpolate_json(v, vars))
+                .collect(),
+        ),
+        Value::Object(obj) => Value::Object(
+            obj.into_iter()
+                .map(|(k, v)| (k, interpolate_json(v, vars)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+/// Example value of a field, falling back to an empty value of its type
+fn example_value(field: &Field) -> Value {
+    if let Some(ref example) = field.example {
+        return example.to_json();
+    }
+
+    match field.field_type {
+        FieldType::String
+        | FieldType::DateTime
+        | FieldType::Date
+        | FieldType::Uuid
+        | FieldType::File => Value::String(String::new()),
+        FieldType::Number | FieldType::Integer => Value::from(0),
+        FieldType::Boolean => Value::Bool(false),
+        FieldType::Array => match (&field.items, &field.nested) {
+            (Some(items), _) => Value::Array(vec![example_value(items)]),
+            (None, Some(nested)) => Value::Array(vec![example_body(nested)]),
+            (None, None) => Value::Array(Vec::new()),
+        },
+        FieldType::Object => match field.nested {
+            Some(ref nested) => example_body(nested),
+            None => Value::Object(serde_json::Map::new()),
+        },
+        FieldType::Map => Value::Object(serde_json::Map::new()),
+        FieldType::Union => match field.variants.as_deref() {
+            Some([first, ..]) => example_value(first),
+            _ => Value::Null,
+        },
+    }
+}
+
+/// Build a JSON body from the fields of a schema that are not parameters
+pub fn example_body(schema: &SchemaBlock) -> Value {
+    let obj = schema
+        .fields
+        .iter()
+        .filter(|f| f.in_body())
+        .map(|f| (f.name.clone(), example_value(f)))
+        .collect();
+    Value::Object(obj)
+}
+
+/// Resolve an endpoint into a concrete request using examples, config defaults and overrides
+pub fn prepare_request(
+    config: &RqcConfig,
+    endpoint: &ApiEndpoint,
+    options: &RunOptions,
+) -> Result<PreparedRequest, String> {
+    let vars = collect_variables(config, options);
+    let method = endpoint.method.clone().unwrap_or_else(|| "GET".to_string());
+
+    // `@path` fields fill the placeholders of the path, unless given on the command line
+    let mut path_vars = vars.clone();
+    for field in request_fields(endpoint, ParamLocation::Path) {
+        if let Some(value) = example_text(field, &vars) {
+            if !options.vars.iter().any(|(name, _)| name == &field.name) {
+                path_vars.insert(field.name.clone(), value);
+            }
+        }
+    }
+
+    let url = if endpoint.path.contains("://") {
+        endpoint.path.clone()
+    } else if let Some(ref base) = options.base_url {
+        format!("{}{}", base.trim_end_matches('/'), endpoint.path)
+    } else {
+        endpoint.full_url.clone().ok_or_else(|| {
+            format!(
+                "No baseUrl configured for {}, pass --base-url",
+                describe_endpoint(endpoint)
+            )
+        })?
+    };
+
+    let mut query: Vec<(String, String)> = endpoint
+        .request
+        .iter()
+        .flat_map(|schema| schema.fields.iter())
+        .filter(|f| f.is_params)
+        .filter_map(|f| Some((f.name.clone(), example_text(f, &vars)?)))
+        .collect();
+
+    let mut headers: Vec<(String, String)> = config
+        .get_headers()
+        .into_iter()
+        .filter_map(|h| {
+            let value = h.default_value.as_ref()?;
+            Some((h.name.clone(), interpolate(value, &vars)))
+        })
+        .collect();
+    let mut cookies: Vec<String> = Vec::new();
+
+    // Credentials of `auth` override the config headers of the same name
+    let mut basic_auth = None;
+    let mut token = None;
+    if let Some(ref auth) = endpoint.http_auth {
+        let property = |name: &str| interpolate(auth.get(name).unwrap_or_default(), &vars);
+        match auth.scheme {
+            AuthScheme::Bearer => {
+                let value = format!("Bearer {}", property("token"));
+                set_header(&mut headers, "Authorization", value);
+            }
+            AuthScheme::Basic => {
+                headers.retain(|(name, _)| !name.eq_ignore_ascii_case("Authorization"));
+                basic_auth = Some((property("username"), property("password")));
+            }
+            AuthScheme::ApiKey => {
+                let (name, value) = (property("name"), property("value"));
+                match auth.get("in") {
+                    Some("query") => query.push((name, value)),
+                    Some("cookie") => cookies.push(format!("{}={}", name, value)),
+                    _ => set_header(&mut headers, &name, value),
+                }
+            }
+            AuthScheme::OAuth2 => {
+                headers.retain(|(name, _)| !name.eq_ignore_ascii_case("Authorization"));
+                token = Some(token_request(auth, &vars).map_err(|e| {
+                    format!("Invalid oauth2 auth of {}: {}", describe_endpoint(endpoint), e)
+                })?);
+            }
+            AuthScheme::None => {}
+        }
+    }
+
+    // `@header` fields override the config headers and auth of the same name
+    for field in request_fields(endpoint, ParamLocation::Header) {
+        if let Some(value) = example_text(field, &vars) {
+            if field.name.eq_ignore_ascii_case("Authorization") {
+                basic_auth = None;
+                token = None;
+            }
+            set_header(&mut headers, &field.name, value);
+        }
+    }
+    cookies.extend(
+        request_fields(endpoint, ParamLocation::Cookie)
+            .filter_map(|f| Some(format!("{}={}", f.name, example_text(f, &vars)?))),
+    );
+    if !cookies.is_empty() {
+        headers.push(("Cookie".to_string(), cookies.join("; ")));
+    }
+
+    let body = match endpoint.request {
+        Some(ref schema)
+            if matches!(method.as_str(), "POST" | "PUT" | "PATCH")
+                && schema.fields.iter().any(Field::in_body) =>
+        {
+            Some(interpolate_json(example_body(schema), &vars))
+        }
+        _ => None,
+    };
+
+    Ok(PreparedRequest {
+        method,
+        url: interpolate(&url, &path_vars),
+        query,
+        headers,
+        body,
+        content_type: endpoint.content_type.clone(),
+        basic_auth,
+        token,
+    })
+}
+
+/// Replace the headers named `name`, whatever their case
+fn set_header(headers: &mut Vec<(String, String)>, name: &str, value: String) {
+    headers.retain(|(header, _)| !header.eq_ignore_ascii_case(name));
+    headers.push((name.to_string(), value));
+}
+
+/// The token request of an `auth oauth2` block, with the variables filled
+fn token_request(auth: &AuthBlock, vars: &HashMap<String, String>) -> Result<TokenRequest, String> {
+    let properties: serde_json::Map<String, Value> = auth
+        .properties
+        .iter()
+        .map(|(name, value)| (name.clone(), Value::String(interpolate(value, vars))))
+        .collect();
+    serde_json::from_value(Value::Object(properties)).map_err(|e| e.to_string())
+}
+
+/// Request fields sent at `location`
+fn request_fields(
+    endpoint: &ApiEndpoint,
+    location: ParamLocation,
+) -> impl Iterator<Item = &Field> {
+    endpoint
+        .request
+        .iter()
+        .flat_map(|schema| schema.fields.iter())
+        .filter(move |f| f.location == Some(location))
+}
+
+/// Example of a parameter as text, with variables replaced
+fn example_text(field: &Field, vars: &HashMap<String, String>) -> Option<String> {
+    let value = match field.example.as_ref()?.to_json() {
+        Value::String(s) => s,
+        other => other.to_string(),
+    };
+    Some(interpolate(&value, vars))
+}
+
+/// Form fields of a body, nested values are sent as JSON text
+fn form_fields(body: &Value) -> Vec<(String, String)> {
+    body.as_object()
+        .into_iter()
+        .flatten()
+        .map(|(name, value)| {
+            let text = match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            (name.clone(), text)
+        })
+        .collect()
+}
+
+/// Send a prepared request with the given client, OAuth2 tokens are kept in `tokens`
+pub async fn send_request(
+    client: &reqwest::Client,
+    request: &PreparedRequest,
+    tokens: &TokenCache,
+) -> Result<RunResponse, Box<dyn std::error::Error>> {
+    let method = reqwest::Method::from_bytes(request.method.as_bytes())?;
+    let mut builder = client.request(method, &request.url).query(&request.query);
+
+    for (name, value) in &request.headers {
+        builder = builder.header(name, value);
+    }
+    if let Some((ref username, ref password)) = request.basic_auth {
+        builder = builder.basic_auth(username, Some(password));
+    }
+    if let Some(ref token) = request.token {
+        let token = tokens.token(client, token).await?;
+        builder = builder.header("Authorization", format!("Bearer {}", token.access_token));
+    }
+
+    if let Some(ref body) = request.body {
+        let media_type = request.content_type.as_deref().unwrap_or("application/json");
+        builder = match media_type.split(';').next().unwrap_or_default().trim() {
+            "application/x-www-form-urlencoded" => builder.form(&form_fields(body)),
+            "multipart/form-data" => builder.multipart(multipart_form(body)?),
+            t if t.ends_with("/json") || t.ends_with("+json") => {
+                builder.header("Content-Type", media_type).json(body)
+            }
+            // Anything else is sent as text, a string body as is
+            _ => {
+                let text = match body {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                builder.header("Content-Type", media_type).body(text)
+            }
+        };
+    }
+
+    let start = Instant::now();
+    let response = builder.send().await?;
+    let status = response.status();
+    let headers = response
+        .headers()
+        .iter()
+        .map(|(k, v)| (k.to_string(), String::from_utf8_lossy(v.as_bytes()).to_string()))
+        .collect();
+    let body = response.text().await?;
+
+    Ok(RunResponse {
+        status: status.as_u16(),
+        status_text: status.canonical_reason().unwrap_or("").to_string(),
+        headers,
+        body,
+        elapsed: start.elapsed(),
+    })
+}
+
+/// Multipart form of a body, `@file:path` values upload the file at that path
+fn multipart_form(body: &Value) -> Result<reqwest::multipart::Form, Box<dyn std::error::Error>> {
+    let mut form = reqwest::multipart::Form::new();
+    for (name, text) in form_fields(body) {
+        form = match text.strip_prefix("@file:") {
+            Some(path) => {
+                let bytes = std::fs::read(path)
+                    .map_err(|e| format!("cannot read file `{}` of `{}`: {}", path, name, e))?;
+                let file_name = std::path::Path::new(path)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let part = reqwest::multipart::Part::bytes(bytes).file_name(file_name);
+                form.part(name, part)
+            }
+            None => form.text(name, text),
+        };
+    }
+    Ok(form)
+}
+
+/// Print status, headers and body (pretty JSON when possible) to stdout
+pub fn print_response(request: &PreparedRequest, response: &RunResponse) {
+    println!("{} {}", request.method, request.url);
+    println!(
+        "HTTP {} {} ({} ms)",
+        response.status,
+        response.status_text,
+        response.elapsed.as_millis()
+    );
+    for (name, value) in &response.headers {
+        println!("{}: {}", name, value);
+    }
+    println!();
+
+    match response.json() {
+        Some(json) => println!(
+            "{}",
+            serde_json::to_string_pretty(&json).unwrap_or_else(|_| response.body.clone())
+        ),
+        None => println!("{}", response.body),
+    }
+}
@@ -0,0 +1,318 @@
+//! Test runner - executes endpoints with `expect` blocks and reports the results
+
+use crate::auth::TokenCache;
+use crate::cli::ReportFormat;
+use crate::parser::{ApiEndpoint, ExpectOp, ExpectSubject, Expectation, RqcConfig};
+use crate::runner::{self, RunOptions, RunResponse};
+use crate::validate;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestResult {
+    pub id: String,
+    pub method: String,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    pub duration_ms: u128,
+    pub assertions: usize,
+    pub failures: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty() && self.error.is_none()
+    }
+
+    fn title(&self) -> String {
+        match self.name {
+            Some(ref name) => format!("{} {} ({})", self.method, self.path, name),
+            None => format!("{} {}", self.method, self.path),
+        }
+    }
+}
+
+/// Run every HTTP endpoint that declares expectations, in definition order. With
+/// `validate_response`, successful responses are also checked against the `response` block.
+pub async fn run_tests(
+    config: &RqcConfig,
+    options: &RunOptions,
+    validate_response: bool,
+) -> Result<Vec<TestResult>, Box<dyn std::error::Error>> {
+    let client = reqwest::Client::builder()
+        .connect_timeout(std::time::Duration::from_secs(10))
+        .build()?;
+    // Endpoints sharing an `auth oauth2` block share its token
+    let tokens = TokenCache::default();
+
+    let mut results = Vec::new();
+    for endpoint in config.to_endpoints() {
+        if endpoint.expect.is_empty() {
+            continue;
+        }
+        let test = run_test(&client, &tokens, config, &endpoint, options, validate_response);
+        results.push(test.await);
+    }
+
+    Ok(results)
+}
+
+async fn run_test(
+    client: &reqwest::Client,
+    tokens: &TokenCache,
+    config: &RqcConfig,
+    endpoint: &ApiEndpoint,
+    options: &RunOptions,
+    validate_response: bool,
+) -> TestResult {
+    let mut result = TestResult {
+        id: endpoint.id.clone(),
+        method: endpoint.method.clone().unwrap_or_else(|| "GET".to_string()),
+        path: endpoint.path.clone(),
+        name: endpoint.name.clone(),
+        category: endpoint.category_name.clone(),
+        status: None,
+        duration_ms: 0,
+        assertions: endpoint.expect.len(),
+        failures: Vec::new(),
+        error: None,
+    };
+
+    let request = match runner::prepare_request(config, endpoint, options) {
+        Ok(request) => request,
+        Err(e) => {
+            result.error = Some(e);
+            return result;
+        }
+    };
+
+    match runner::send_request(client, &request, tokens).await {
+        Ok(response) => {
+            result.status = Some(response.status);
+            result.duration_ms = response.elapsed.as_millis();
+            result.failures = endpoint
+                .expect
+                .iter()
+                .filter_map(|e| check(e, &response).err())
+                .collect();
+
+            let schema = endpoint.response_for(response.status);
+            // Responses without fields, such as a `204`, have nothing to check
+            let schema = schema.filter(|s| validate_response && !s.fields.is_empty());
+            if let Some(schema) = schema {
+                result.assertions += 1;
+                match response.json() {
+                    Some(body) => result.failures.extend(
+                        validate::response(schema, &body)
+                            .into_iter()
+                            .map(|e| format!("response: {}", e)),
+                    ),
+                    None => result.failures.push("response: body is not JSON".to_string()),
+                }
+            }
+        }
+        Err(e) => result.error = Some(e.to_string()),
+    }
+
+    result
+}
+
+/// Check a single expectation against a response
+pub fn check(expectation: &Expectation, response: &RunResponse) -> Result<(), String> {
+    let actual = match expectation.subject {
+        ExpectSubject::Status => Some(json!(response.status)),
+        ExpectSubject::Header { ref name } => response
+            .headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| Value::String(v.clone())),
+        ExpectSubject::Body { ref path } => match response.json() {
+            Some(body) => lookup(&body, path),
+            None if path.is_empty() => Some(Value::String(response.body.clone())),
+            None => None,
+        },
+    };
+
+    let expected = expectation.value.as_ref().map(|v| v.to_json());
+    let ok = match (expectation.op, actual.as_ref(), expected.as_ref()) {
+        (ExpectOp::Exists, actual, _) => actual.is_some_and(|v| !v.is_null()),
+        (_, None, _) | (_, _, None) => false,
+        (ExpectOp::Eq, Some(a), Some(e)) => values_equal(a, e),
+        (ExpectOp::Ne, Some(a), Some(e)) => !values_equal(a, e),
+        (ExpectOp::Contains, Some(a), Some(e)) => contains(a, e),
+        (op, Some(a), Some(e)) => match (a.as_f64(), e.as_f64()) {
+            (Some(a), Some(e)) => match op {
+                ExpectOp::Gt => a > e,
+                ExpectOp::Ge => a >= e,
+                ExpectOp::Lt => a < e,
+                _ => a <= e,
+            },
+            _ => false,
+        },
+    };
+
+    if ok {
+        Ok(())
+    } else {
+        let got = actual
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| "nothing".to_string());
+        Err(format!("expected {}, got {}", expectation, got))
+    }
+}
+
+/// Resolve a dotted path such as `users.0.name` or `users.length` inside a JSON value
+fn lookup(value: &Value, path: &str) -> Option<Value> {
+    let mut current = value.clone();
+
+    for segment in path.split('.').filter(|s| !s.is_empty()) {
+        current = match current {
+            Value::Object(ref obj) if obj.contains_key(segment) => obj[segment].clone(),
+            Value::Object(ref obj) if segment == "length" => json!(obj.len()),
+            Value::Array(ref items) if segment == "length" => json!(items.len()),
+            Value::String(ref s) if segment == "length" => json!(s.chars().count()),
+            Value::Array(ref items) => items.get(segment.parse::<usize>().ok()?)?.clone(),
+            _ => return None,
+        };
+    }
+
+    Some(current)
+}
+
+fn values_equal(actual: &Value, expected: &Value) -> bool {
+    match (actual.as_f64(), expected.as_f64()) {
+        (Some(a), Some(e)) => a == e,
+        _ => actual == expected,
+    }
+}
+
+fn contains(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::String(a), Value::String(e)) => a.contains(e.as_str()),
+        (Value::Array(items), e) => items.iter().any(|item| values_equal(item, e)),
+        (Value::Object(obj), Value::String(key)) => obj.contains_key(key),
+        (a, e) => a.to_string().contains(&e.to_string()),
+    }
+}
+
+/// Render test results in the requested format
+pub fn render(results: &[TestResult], format: ReportFormat) -> String {
+    match format {
+        ReportFormat::Human => render_human(results),
+        ReportFormat::Json => render_json(results),
+        ReportFormat::Junit => render_junit(results),
+    }
+}
+
+fn render_human(results: &[TestResult]) -> String {
+    let mut out = String::new();
+
+    for result in results {
+        let mark = if result.passed() { "PASS" } else { "FAIL" };
+        out.push_str(&format!(
+            "{} {} [{} ms]\n",
+            mark,
+            result.title(),
+            result.duration_ms
+        ));
+        if let Some(ref error) = result.error {
+            out.push_str(&format!("     error: {}\n", error));
+        }
+        for failure in &result.failures {
+            out.push_str(&format!("     {}\n", failure));
+        }
+    }
+
+    let failed = results.iter().filter(|r| !r.passed()).count();
+    out.push_str(&format!(
+        "\n{} passed, {} failed, {} total\n",
+        results.len() - failed,
+        failed,
+        results.len()
+    ));
+    out
+}
+
+fn render_json(results: &[TestResult]) -> String {
+    let failed = results.iter().filter(|r| !r.passed()).count();
+    let report = json!({
+        "passed": results.len() - failed,
+        "failed": failed,
+        "total": results.len(),
+        "results": results,
+    });
+    serde_json::to_string_pretty(&report).unwrap_or_default()
+}
+
+fn render_junit(results: &[TestResult]) -> String {
+    let failures = results
+        .iter()
+        .filter(|r| r.error.is_none() && !r.failures.is_empty())
+        .count();
+    let errors = results.iter().filter(|r| r.error.is_some()).count();
+    let total_ms: u128 = results.iter().map(|r| r.duration_ms).sum();
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(&format!(
+        "<testsuites tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+        results.len(),
+        failures,
+        errors,
+        total_ms as f64 / 1000.0
+    ));
+    out.push_str(&format!(
+        "  <testsuite name=\"reqcraft\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+        results.len(),
+        failures,
+        errors,
+        total_ms as f64 / 1000.0
+    ));
+
+    for result in results {
+        out.push_str(&format!(
+            "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+            xml_escape(result.category.as_deref().unwrap_or("reqcraft")),
+            xml_escape(&result.title()),
+            result.duration_ms as f64 / 1000.0
+        ));
+
+        if result.passed() {
+            out.push_str("/>\n");
+            continue;
+        }
+
+        out.push_str(">\n");
+        if let Some(ref error) = result.error {
+            out.push_str(&format!(
+                "      <error message=\"{}\"/>\n",
+                xml_escape(error)
+            ));
+        }
+        for failure in &result.failures {
+            out.push_str(&format!(
+                "      <failure message=\"{}\"/>\n",
+                xml_escape(failure)
+            ));
+        }
+        out.push_str("    </testcase>\n");
+    }
+
+    out.push_str("  </testsuite>\n</testsuites>\n");
+    out
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -0,0 +1,397 @@
+//! Validation of request and response bodies against the schemas of an endpoint, used by
+//! the mock server and `rqc test --validate`
+
+use crate::mock::{self, PathParams};
+use crate::parser::{Field, FieldType, MockValue, ParamLocation, SchemaBlock};
+use axum::http::{header, HeaderMap};
+use chrono::{DateTime, NaiveDate};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// Check a request against the request schema of its endpoint and return one message per
+/// problem. Path parameters, `@params`, `@header` and `@cookie` fields are read from the path,
+/// the query string, the headers and the cookies, the other fields from the JSON or
+/// url-encoded body. Only what the request contains is checked, except for required fields
+/// of the objects it sends.
+pub fn request(
+    schema: &SchemaBlock,
+    params: &PathParams,
+    query: Option<&str>,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Vec<String> {
+    let mut errors = Vec::new();
+    let query = parse_pairs(query.unwrap_or_default(), '&');
+    let cookies: HashMap<String, String> = headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| parse_pairs(v, ';'))
+        .collect();
+
+    let mut body_fields = Vec::new();
+    for field in &schema.fields {
+        let text = match (params.get(&field.name), field.location) {
+            (Some(value), _) => Some(value.clone()),
+            (None, Some(ParamLocation::Header)) => headers
+                .get(field.name.as_str())
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string),
+            (None, Some(ParamLocation::Cookie)) => cookies.get(&field.name).cloned(),
+            (None, Some(ParamLocation::Path)) => None,
+            (None, None) if field.is_params => query.get(&field.name).cloned(),
+            (None, None) => {
+                body_fields.push(field.clone());
+                continue;
+            }
+        };
+        if let Some(text) = text {
+            let value = mock::typed_value(&text, &field.field_type);
+            check_value(field, &value, &field.name, &mut errors);
+        }
+    }
+
+    if body_fields.is_empty() || body.iter().all(u8::is_ascii_whitespace) {
+        return errors;
+    }
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    if content_type.starts_with("application/x-www-form-urlencoded") {
+        let form = parse_pairs(&String::from_utf8_lossy(body), '&');
+        for field in &body_fields {
+            match form.get(&field.name) {
+                Some(text) => {
+                    let value = mock::typed_value(text, &field.field_type);
+                    check_value(field, &value, &field.name, &mut errors);
+                }
+                None if !field.optional => {
+                    errors.push(format!("missing required field `{}`", field.name))
+                }
+                None => {}
+            }
+        }
+        return errors;
+    }
+    // Multipart forms are not parsed, bodies with files are usually sent as one
+    let has_files = body_fields.iter().any(|f| f.field_type == FieldType::File);
+    if content_type.starts_with("multipart/form-data") {
+        return errors;
+    }
+    match serde_json::from_slice::<Value>(body) {
+        Ok(Value::Object(ref object)) => {
+            let schema = SchemaBlock {
+                fields: body_fields,
+                optional: false,
+                spreads: Vec::new(),
+            };
+            check_object(&schema, object, "", &mut errors);
+        }
+        Ok(other) => errors.push(format!("request body must be an object, got {}", other)),
+        Err(_) if has_files => {}
+        Err(e) => errors.push(format!("request body is not valid JSON: {}", e)),
+    }
+    errors
+}
+
+/// `a=1&b=2` of a query string or form, or `a=1; b=2` of a cookie header, percent-decoded
+fn parse_pairs(text: &str, separator: char) -> HashMap<String, String> {
+    let decode = |s: &str| {
+        let s = s.trim().replace('+', " ");
+        urlencoding::decode(&s).map(|v| v.into_owned()).unwrap_or(s)
+    };
+    text.split(separator)
+        .filter_map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let name = decode(name);
+            (!name.is_empty()).then(|| (name, decode(value)))
+        })
+        .collect()
+}
+
+fn check_object(
+    schema: &SchemaBlock,
+    object: &serde_json::Map<String, Value>,
+    prefix: &str,
+    errors: &mut Vec<String>,
+) {
+    for field in &schema.fields {
+        let location = if prefix.is_empty() {
+            field.name.clone()
+        } else {
+            format!("{}.{}", prefix, field.name)
+        };
+        match object.get(&field.name) {
+            Some(value) => check_value(field, value, &location, errors),
+            None if !field.optional => {
+                errors.push(format!("missing required field `{}`", location))
+            }
+            None => {}
+        }
+    }
+}
+
+fn check_value(field: &Field, value: &Value, location: &str, errors: &mut Vec<String>) {
+    // `?` only allows leaving the field out, `null` needs `!` or `| Null`
+    if value.is_null() {
+        if !field.nullable {
+            errors.push(format!("`{}` must not be null", location));
+        }
+        return;
+    }
+
+    if let Some(ref variants) = field.variants {
+        check_union(field, variants, value, location, errors);
+        return;
+    }
+
+    let text = value.as_str();
+    let (matches, expected) = match field.field_type {
+        FieldType::String => (value.is_string(), "a string"),
+        FieldType::Number => (value.is_number(), "a number"),
+        FieldType::Integer => (is_integer(value), "an integer"),
+        FieldType::Boolean => (value.is_boolean(), "a boolean"),
+        FieldType::DateTime => (
+            text.is_some_and(|t| matches_format("date-time", t)),
+            "a date-time such as \"2024-01-01T08:30:00Z\"",
+        ),
+        FieldType::Date => (
+            text.is_some_and(|t| matches_format("date", t)),
+            "a date such as \"2024-01-01\"",
+        ),
+        FieldType::Uuid => (text.is_some_and(|t| matches_format("uuid", t)), "a UUID"),
+        FieldType::File => (value.is_string(), "a file"),
+        FieldType::Array => (value.is_array(), "an array"),
+        FieldType::Object | FieldType::Map | FieldType::Union => (value.is_object(), "an object"),
+    };
+    if !matches {
+        errors.push(format!("`{}` must be {}, got {}", location, expected, value));
+        return;
+    }
+
+    check_constraints(field, value, location, errors);
+
+    if let Some(ref values) = field.enum_values {
+        let allowed: Vec<Value> = values.iter().map(MockValue::to_json).collect();
+        if !allowed.iter().any(|a| same_value(a, value)) {
+            let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+            errors.push(format!(
+                "`{}` must be one of {}, got {}",
+                location,
+                allowed.join(", "),
+                value
+            ));
+        }
+    }
+
+    match value {
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                let location = format!("{}[{}]", location, i);
+                match (&field.items, &field.nested, item) {
+                    (Some(element), _, _) => check_value(element, item, &location, errors),
+                    // Untyped `Array` with a block, its items are objects
+                    (None, Some(nested), Value::Object(object)) => {
+                        check_object(nested, object, &location, errors)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Value::Object(object) if field.field_type == FieldType::Map => {
+            let key_type = field.key_type.as_ref().unwrap_or(&FieldType::String);
+            for (key, item) in object {
+                if !key_matches(key_type, key) {
+                    errors.push(format!(
+                        "key `{}` of `{}` must be of type {}",
+                        key,
+                        location,
+                        key_type.keyword()
+                    ));
+                }
+                if let Some(ref element) = field.items {
+                    check_value(element, item, &format!("{}.{}", location, key), errors);
+                }
+            }
+        }
+        Value::Object(object) => {
+            if let Some(ref nested) = field.nested {
+                check_object(nested, object, location, errors);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// A union value is checked against the variant named by its discriminator, without one it
+/// has to match any variant
+fn check_union(
+    field: &Field,
+    variants: &[Field],
+    value: &Value,
+    location: &str,
+    errors: &mut Vec<String>,
+) {
+    let tagged = field
+        .discriminator
+        .as_ref()
+        .and_then(|property| Some((property, value.get(property)?)));
+    if let Some((property, tag)) = tagged {
+        let variant = variants.iter().find(|v| {
+            v.discriminator_value(property)
+                .is_some_and(|d| same_value(&d.to_json(), tag))
+        });
+        match variant {
+            Some(variant) => check_value(variant, value, location, errors),
+            None => {
+                let allowed: Vec<String> = variants
+                    .iter()
+                    .filter_map(|v| v.discriminator_value(property))
+                    .map(|d| d.to_json().to_string())
+                    .collect();
+                errors.push(format!(
+                    "`{}.{}` must be one of {}, got {}",
+                    location,
+                    property,
+                    allowed.join(", "),
+                    tag
+                ));
+            }
+        }
+        return;
+    }
+
+    let matches_any = variants.iter().any(|variant| {
+        let mut variant_errors = Vec::new();
+        check_value(variant, value, location, &mut variant_errors);
+        variant_errors.is_empty()
+    });
+    if !matches_any {
+        let names: Vec<&str> = variants
+            .iter()
+            .map(|v| v.type_ref.as_deref().unwrap_or(v.field_type.keyword()))
+            .collect();
+        errors.push(format!(
+            "`{}` must be one of {}, got {}",
+            location,
+            names.join(" | "),
+            value
+        ));
+    }
+}
+
+/// Whole numbers, `1.0` included
+fn is_integer(value: &Value) -> bool {
+    value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+}
+
+/// Whether a key of a `Map<K, V>` is of type `K`
+fn key_matches(key_type: &FieldType, key: &str) -> bool {
+    match key_type {
+        FieldType::Integer => key.parse::<i64>().is_ok(),
+        FieldType::Uuid => matches_format("uuid", key),
+        FieldType::Date => matches_format("date", key),
+        _ => true,
+    }
+}
+
+/// JSON equality, except that `1` and `1.0` are the same number
+fn same_value(a: &Value, b: &Value) -> bool {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(x), Some(y)) => x == y,
+        _ => a == b,
+    }
+}
+
+
+/// Check a response body against the response schema, one message per problem
+pub fn response(schema: &SchemaBlock, body: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    match body {
+        Value::Object(object) => check_object(schema, object, "", &mut errors),
+        other => errors.push(format!("response body must be an object, got {}", other)),
+    }
+    errors
+}
+
+/// Problems of a value with the `@min`, `@max`, `@length`, `@pattern` and `@format` of its
+/// field, its type is not checked
+pub fn constraints(field: &Field, value: &Value, location: &str) -> Vec<String> {
+    let mut errors = Vec::new();
+    check_constraints(field, value, location, &mut errors);
+    errors
+}
+
+fn check_constraints(field: &Field, value: &Value, location: &str, errors: &mut Vec<String>) {
+    let constraints = &field.constraints;
+
+    if let Some(n) = value.as_f64() {
+        if let Some(min) = constraints.min.filter(|min| n < *min) {
+            errors.push(format!("`{}` must be at least {}, got {}", location, min, value));
+        }
+        if let Some(max) = constraints.max.filter(|max| n > *max) {
+            errors.push(format!("`{}` must be at most {}, got {}", location, max, value));
+        }
+    }
+
+    let (length, unit) = match value {
+        Value::String(s) => (s.chars().count() as u64, "characters"),
+        Value::Array(items) => (items.len() as u64, "items"),
+        _ => return,
+    };
+    if let Some(min) = constraints.min_length.filter(|min| length < *min) {
+        errors.push(format!(
+            "`{}` must have at least {} {}, got {}",
+            location, min, unit, length
+        ));
+    }
+    if let Some(max) = constraints.max_length.filter(|max| length > *max) {
+        errors.push(format!(
+            "`{}` must have at most {} {}, got {}",
+            location, max, unit, length
+        ));
+    }
+
+    let Value::String(text) = value else {
+        return;
+    };
+    if let Some(ref pattern) = constraints.pattern {
+        // Invalid patterns are reported by `rqc check`
+        if Regex::new(pattern).is_ok_and(|re| !re.is_match(text)) {
+            errors.push(format!(
+                "`{}` must match `{}`, got {}",
+                location, pattern, value
+            ));
+        }
+    }
+    if let Some(ref format) = constraints.format {
+        if !matches_format(format, text) {
+            errors.push(format!("`{}` must be a valid {}, got {}", location, format, value));
+        }
+    }
+}
+
+/// Whether `text` is in the given `@format`, unknown formats accept anything
+pub fn matches_format(format: &str, text: &str) -> bool {
+    match format {
+        "email" => text
+            .split_once('@')
+            .is_some_and(|(user, domain)| !user.is_empty() && domain.contains('.')),
+        "uuid" => {
+            let groups: Vec<&str> = text.split('-').collect();
+            groups.iter().map(|g| g.len()).eq([8, 4, 4, 4, 12])
+                && groups.iter().all(|g| g.chars().all(|c| c.is_ascii_hexdigit()))
+        }
+        "date-time" => DateTime::parse_from_rfc3339(text).is_ok(),
+        "date" => NaiveDate::parse_from_str(text, "%Y-%m-%d").is_ok(),
+        "uri" => text
+            .split_once("://")
+            .is_some_and(|(scheme, rest)| !scheme.is_empty() && !rest.is_empty()),
+        "ipv4" => text.parse::<Ipv4Addr>().is_ok(),
+        "ipv6" => text.parse::<Ipv6Addr>().is_ok(),
+        _ => true,
+    }
+}
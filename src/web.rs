@@ -1,23 +1,29 @@
 use axum::{
-    body::Body,
+    body::{Body, Bytes},
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
         Path, State,
     },
     http::{header, HeaderMap, HeaderName, HeaderValue, Method, Request, StatusCode, Uri},
     response::{IntoResponse, Response},
-    routing::{any, get},
+    routing::{any, get, post, put},
     Json, Router,
 };
 use rust_embed::Embed;
-use serde::Serialize;
-use serde_json::{json, Value};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::{Arc, RwLock};
 use tower_http::cors::{Any, CorsLayer};
 use tracing::{info, warn};
 
-use crate::parser::{ApiEndpoint, CategoryInfo, EndpointType, FieldType, HeaderDefinition, MockValue, RqcConfig, SchemaBlock, VariableDefinition};
+use crate::auth::{TokenCache, TokenRequest};
+use crate::dynamic;
+use crate::mock;
+use crate::validate;
+use crate::runner::interpolate;
+use crate::parser::{ApiEndpoint, CategoryInfo, HeaderDefinition, RqcConfig, VariableDefinition};
 
 #[derive(Embed)]
 #[folder = "web-ui/dist"]
@@ -27,8 +33,13 @@ struct Assets;
 pub struct AppState {
     pub config: Arc<RwLock<RqcConfig>>,
     pub mock_mode: bool,
+    pub mock_options: mock::MockOptions,
     pub cors_mode: bool,
     pub http_client: reqwest::Client,
+    /// OAuth2 tokens fetched for the web UI
+    pub tokens: Arc<TokenCache>,
+    /// Where `rqc mock` forwards the requests it does not mock, with `--forward` or `--upstream`
+    pub upstream: Option<String>,
     pub reload_tx: tokio::sync::broadcast::Sender<()>,
 }
 
@@ -38,6 +49,11 @@ pub struct ApiInfo {
     pub name: String,
     pub version: String,
     pub base_urls: Vec<String>,
+    /// Names of the `env` blocks, and the one in use
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub envs: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_env: Option<String>,
     pub endpoint_count: usize,
     pub mock_mode: bool,
     pub cors_mode: bool,
@@ -48,6 +64,7 @@ pub async fn start_server(
     port: u16,
     config: Arc<RwLock<RqcConfig>>,
     mock_mode: bool,
+    mock_options: mock::MockOptions,
     cors_mode: bool,
     reload_tx: tokio::sync::broadcast::Sender<()>,
 ) -> Result<(), Box<dyn std::error::Error>> {
@@ -58,8 +75,11 @@ pub async fn start_server(
     let state = AppState {
         config,
         mock_mode,
+        mock_options,
         cors_mode,
         http_client,
+        tokens: Arc::default(),
+        upstream: None,
         reload_tx,
     };
 
@@ -68,26 +88,36 @@ pub async fn start_server(
         .allow_methods(Any)
         .allow_headers(Any);
 
-    let mut app = Router::new()
+    // The web UI is served from this server, so `/api` gets no CORS headers: other sites must
+    // not read the variables, secrets and OAuth2 tokens it returns
+    let app = Router::new()
         .route("/api/info", get(api_info))
         .route("/api/config", get(get_config))
         .route("/api/endpoints", get(get_endpoints))
         .route("/api/categories", get(get_categories))
         .route("/api/variables", get(get_variables))
+        .route("/api/variables/refresh", post(refresh_variables))
         .route("/api/headers", get(get_headers))
+        .route("/api/env", put(select_env))
+        .route("/api/auth/token", post(fetch_token))
         .route("/ws", get(ws_handler));
 
+    let mut open = Router::new();
+
     // Add mock proxy endpoint in mock mode
     if mock_mode {
-        app = app.route("/mock/*path", any(mock_handler));
+        open = open.route("/mock/*path", any(mock_handler));
     }
 
     // Add CORS proxy endpoint in cors mode
     if cors_mode {
-        app = app.route("/proxy/*path", any(cors_proxy_handler));
+        open = open.route("/proxy/*path", any(cors_proxy_handler));
     }
 
-    let app = app.fallback(static_handler).layer(cors).with_state(state);
+    let app = app
+        .merge(open.layer(cors))
+        .fallback(static_handler)
+        .with_state(state);
 
     let addr: SocketAddr = format!("{}:{}", host, port).parse()?;
     info!("ReqCraft dev server running at http://{}", addr);
@@ -98,6 +128,49 @@ pub async fn start_server(
     Ok(())
 }
 
+/// Serve only the mocks, mounted at the real API paths, without the web UI
+pub async fn start_mock_server(
+    host: &str,
+    port: u16,
+    config: Arc<RwLock<RqcConfig>>,
+    mock_options: mock::MockOptions,
+    upstream: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let http_client = reqwest::Client::builder()
+        .connect_timeout(std::time::Duration::from_secs(10))
+        .build()?;
+    let (reload_tx, _) = tokio::sync::broadcast::channel::<()>(1);
+
+    let state = AppState {
+        config,
+        mock_mode: true,
+        mock_options,
+        cors_mode: false,
+        http_client,
+        tokens: Arc::default(),
+        upstream,
+        reload_tx,
+    };
+
+    let cors = CorsLayer::new()
+        .allow_origin(Any)
+        .allow_methods(Any)
+        .allow_headers(Any);
+
+    let app = Router::new()
+        .fallback(standalone_mock_handler)
+        .layer(cors)
+        .with_state(state);
+
+    let addr: SocketAddr = format!("{}:{}", host, port).parse()?;
+    info!("ReqCraft mock server running at http://{}", addr);
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
+
 async fn static_handler(uri: Uri) -> impl IntoResponse {
     let path = uri.path().trim_start_matches('/');
     let path = if path.is_empty() { "index.html" } else { path };
@@ -127,15 +200,42 @@ async fn static_handler(uri: Uri) -> impl IntoResponse {
 
 async fn api_info(State(state): State<AppState>) -> Json<ApiInfo> {
     let config = state.config.read().unwrap();
-    let endpoints = config.to_endpoints();
-    Json(ApiInfo {
+    Json(info(&state, &config))
+}
+
+fn info(state: &AppState, config: &RqcConfig) -> ApiInfo {
+    ApiInfo {
         name: "reqcraft".to_string(),
         version: env!("CARGO_PKG_VERSION").to_string(),
         base_urls: config.get_base_urls(),
-        endpoint_count: endpoints.len(),
+        envs: config
+            .config
+            .iter()
+            .flat_map(|c| c.envs.iter().map(|e| e.name.clone()))
+            .collect(),
+        active_env: config.active_env().map(|e| e.name.clone()),
+        endpoint_count: config.to_endpoints().len(),
         mock_mode: state.mock_mode,
         cors_mode: state.cors_mode,
-    })
+    }
+}
+
+#[derive(Deserialize)]
+struct SelectEnv {
+    name: String,
+}
+
+/// Switch the active environment, connected web UIs reload the base URLs, variables and headers
+async fn select_env(State(state): State<AppState>, Json(body): Json<SelectEnv>) -> Response {
+    let mut config = state.config.write().unwrap();
+    if let Err(e) = config.select_env(&body.name) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response();
+    }
+    info!("Switched to env `{}`", body.name);
+    let info = info(&state, &config);
+    drop(config);
+    let _ = state.reload_tx.send(());
+    Json(info).into_response()
 }
 
 async fn get_config(State(state): State<AppState>) -> Json<RqcConfig> {
@@ -154,25 +254,50 @@ async fn get_categories(State(state): State<AppState>) -> Json<Vec<CategoryInfo>
 }
 
 async fn get_variables(State(state): State<AppState>) -> Json<Vec<VariableDefinition>> {
-    let config = state.config.read().unwrap();
-    Json(
-        config
-            .config
-            .as_ref()
-            .map(|c| c.variables.clone())
-            .unwrap_or_default(),
-    )
+    Json(resolve_variables(&state).await)
+}
+
+/// Run the `from_command` variables again, connected web UIs reload the variables. The body
+/// is a JSON object, `{}`, so a cross-site form cannot run the commands
+async fn refresh_variables(
+    State(state): State<AppState>,
+    Json(_): Json<serde_json::Map<String, serde_json::Value>>,
+) -> Json<Vec<VariableDefinition>> {
+    state.config.read().unwrap().commands.clear();
+    let variables = resolve_variables(&state).await;
+    for variable in &variables {
+        if let Some(ref error) = variable.error {
+            warn!("Variable `{}`: {}", variable.name, error);
+        }
+    }
+    let _ = state.reload_tx.send(());
+    Json(variables)
+}
+
+/// Config variables, off the async runtime as `from_command` ones may run a command. They
+/// run on a copy of the config, so a slow command does not hold up reloads and env switches
+async fn resolve_variables(state: &AppState) -> Vec<VariableDefinition> {
+    let config = state.config.read().unwrap().clone();
+    tokio::task::spawn_blocking(move || config.get_variables())
+        .await
+        .unwrap_or_default()
+}
+
+/// Token of an `auth oauth2` block, fetched by the server so the token endpoint needs no CORS
+/// headers. The web UI fills the variables of the block before asking
+async fn fetch_token(State(state): State<AppState>, Json(request): Json<TokenRequest>) -> Response {
+    match state.tokens.token(&state.http_client, &request).await {
+        Ok(token) => Json(token).into_response(),
+        Err(e) => {
+            warn!("{}", e);
+            (StatusCode::BAD_GATEWAY, Json(json!({ "error": e }))).into_response()
+        }
+    }
 }
 
 async fn get_headers(State(state): State<AppState>) -> Json<Vec<HeaderDefinition>> {
     let config = state.config.read().unwrap();
-    Json(
-        config
-            .config
-            .as_ref()
-            .map(|c| c.headers.clone())
-            .unwrap_or_default(),
-    )
+    Json(config.get_headers())
 }
 
 async fn mock_handler(
@@ -180,25 +305,119 @@ async fn mock_handler(
     Path(path): Path<String>,
     req: Request<Body>,
 ) -> Response {
+    let base_url = forward_base_url(&state.config.read().unwrap(), req.headers());
+    mock_response(&state, req, &format!("/{}", path), base_url).await
+}
+
+async fn standalone_mock_handler(State(state): State<AppState>, req: Request<Body>) -> Response {
     let method = req.method().clone();
-    let request_path = format!("/{}", path);
+    let path = req.uri().path().to_string();
+    let upstream = state.upstream.clone();
+    let response = mock_response(&state, req, &path, upstream).await;
+    let forwarded = if response.extensions().get::<Forwarded>().is_some() {
+        " (forwarded)"
+    } else {
+        ""
+    };
+    info!("{} {} -> {}{}", method, path, response.status().as_u16(), forwarded);
+    response
+}
 
-    // Find matching API endpoint from flattened endpoints list
-    let config = state.config.read().unwrap();
-    let endpoints = config.to_endpoints();
-    for endpoint in &endpoints {
-        if endpoint.endpoint_type == EndpointType::Http
-            && endpoint.path == request_path
-            && endpoint.method.as_deref() == Some(method.as_str())
-        {
-            // Found matching endpoint, generate mock response
-            if let Some(ref response_schema) = endpoint.response {
-                let mock_data = generate_mock_response(response_schema);
-                return Json(mock_data).into_response();
-            } else {
-                return Json(json!({})).into_response();
+/// Marks responses that came from the real server rather than from a mock
+#[derive(Clone, Copy)]
+struct Forwarded;
+
+/// Header the web UI uses to pass the selected base URL to the mock server
+const BASE_URL_HEADER: &str = "x-rqc-base-url";
+
+/// Header the web UI sets on `/proxy` requests whose `{$uuid}` style built-ins the server fills,
+/// other traffic is forwarded as it is
+const EXPAND_HEADER: &str = "x-rqc-expand";
+
+/// Answer with a mock, or forward to `base_url` when the endpoint is unknown, has no mock data
+/// or is switched off with `mock false`
+async fn mock_response(
+    state: &AppState,
+    req: Request<Body>,
+    request_path: &str,
+    base_url: Option<String>,
+) -> Response {
+    let route = {
+        let config = state.config.read().unwrap();
+        let endpoints = config.to_endpoints();
+        mock::find_route(&endpoints, req.method().as_str(), request_path)
+            // Without a server to forward to, a generated mock is better than nothing
+            .filter(|(endpoint, _)| base_url.is_none() || mock::should_mock(endpoint))
+            .map(|(endpoint, params)| (endpoint.clone(), params))
+    };
+
+    if let Some((endpoint, params)) = route {
+        let key = format!("{} {}", req.method(), request_path);
+        let (parts, body) = req.into_parts();
+
+        // Requests that do not match the request schema get a 400, like a real server would
+        if let Some(ref request_schema) = endpoint.request {
+            let body = axum::body::to_bytes(body, usize::MAX)
+                .await
+                .unwrap_or_default();
+            let errors = validate::request(
+                request_schema,
+                &params,
+                parts.uri.query(),
+                &parts.headers,
+                &body,
+            );
+            if !errors.is_empty() {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({
+                        "error": "Request does not match the schema",
+                        "details": errors
+                    })),
+                )
+                    .into_response();
+            }
+        }
+
+        let mock_data = match endpoint.response {
+            Some(ref response_schema) => {
+                let mut ctx = mock::MockContext::new(&state.mock_options, &key, &params);
+                mock::generate(response_schema, &mut ctx)
             }
+            None => json!({}),
+        };
+        // `response 201 { ... }` answers with its status, `2XX` with a 200
+        let status = endpoint
+            .status
+            .as_deref()
+            .and_then(|s| s.parse::<u16>().ok())
+            .and_then(|s| StatusCode::from_u16(s).ok())
+            .unwrap_or(StatusCode::OK);
+        if status == StatusCode::NO_CONTENT {
+            return status.into_response();
+        }
+        return (status, Json(mock_data)).into_response();
+    }
+
+    if let Some(base_url) = base_url {
+        let mut target_url = format!("{}{}", base_url.trim_end_matches('/'), request_path);
+        if let Some(query) = req.uri().query() {
+            target_url.push('?');
+            target_url.push_str(query);
         }
+        let (parts, body) = req.into_parts();
+        let mut response =
+            forward_request(
+                &state.http_client,
+                parts.method,
+                &target_url,
+                &parts.headers,
+                body,
+                false,
+            )
+            .await;
+        response.extensions_mut().insert(Forwarded);
+        return response;
     }
 
     // No mock found, return 404 with info
@@ -207,39 +426,35 @@ async fn mock_handler(
         Json(json!({
             "error": "No mock defined",
             "path": request_path,
-            "method": method.as_str()
+            "method": req.method().as_str()
         })),
     )
         .into_response()
 }
 
-fn generate_mock_response(schema: &SchemaBlock) -> Value {
-    let mut obj = serde_json::Map::new();
-
-    for field in &schema.fields {
-        let value = if let Some(ref mock) = field.mock {
-            match mock {
-                MockValue::String(s) => Value::String(s.clone()),
-                MockValue::Number(n) => json!(*n),
-                MockValue::Boolean(b) => Value::Bool(*b),
-            }
-        } else if let Some(ref nested) = field.nested {
-            generate_mock_response(nested)
-        } else {
-            // Generate default mock based on type
-            match field.field_type {
-                FieldType::String => Value::String(format!("mock_{}", field.name)),
-                FieldType::Number => json!(0),
-                FieldType::Boolean => Value::Bool(false),
-                FieldType::Array => Value::Array(vec![]),
-                FieldType::Object => Value::Object(serde_json::Map::new()),
-            }
-        };
-
-        obj.insert(field.name.clone(), value);
+/// Base URL selected in the web UI, only trusted on the dev server's `/mock` route, or the
+/// config's default one
+fn forward_base_url(config: &RqcConfig, headers: &HeaderMap) -> Option<String> {
+    if let Some(url) = headers
+        .get(BASE_URL_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+    {
+        return Some(url.to_string());
     }
+    default_base_url(config)
+}
 
-    Value::Object(obj)
+/// The first base URL of the config, with its `{var}` placeholders filled from the variable
+/// defaults. `from_command` variables are not run, this is called with the config locked
+pub fn default_base_url(config: &RqcConfig) -> Option<String> {
+    let base_url = config.get_base_urls().into_iter().next()?;
+    let defaults: HashMap<String, String> = config
+        .env_variables(config.active_env())
+        .into_iter()
+        .filter_map(|v| Some((v.name, v.default_value?)))
+        .collect();
+    Some(interpolate(&base_url, &defaults))
 }
 
 async fn cors_proxy_handler(
@@ -265,16 +480,42 @@ async fn cors_proxy_handler(
         }
     };
 
+    let expand = headers.contains_key(EXPAND_HEADER);
+    forward_request(&state.http_client, method, &target_url, &headers, body, expand).await
+}
+
+/// Fill the `{$uuid}` style built-ins of a text body, binary ones are left as they are
+fn expand_body(body: Bytes) -> Bytes {
+    match std::str::from_utf8(&body) {
+        Ok(text) if !dynamic::references(text).is_empty() => Bytes::from(dynamic::expand(text)),
+        _ => body,
+    }
+}
+
+/// Send a request on to `target_url` and stream the answer back, with the `{$uuid}` style
+/// built-ins of its URL, headers and body filled when `expand` is set
+async fn forward_request(
+    client: &reqwest::Client,
+    method: Method,
+    target_url: &str,
+    headers: &HeaderMap,
+    body: Body,
+    expand: bool,
+) -> Response {
+    let target_url = &if expand {
+        dynamic::expand(target_url)
+    } else {
+        target_url.to_string()
+    };
     // Build the request
-    let client = &state.http_client;
     let mut request_builder = match method {
-        Method::GET => client.get(&target_url),
-        Method::POST => client.post(&target_url),
-        Method::PUT => client.put(&target_url),
-        Method::DELETE => client.delete(&target_url),
-        Method::PATCH => client.patch(&target_url),
-        Method::HEAD => client.head(&target_url),
-        Method::OPTIONS => client.request(reqwest::Method::OPTIONS, &target_url),
+        Method::GET => client.get(target_url),
+        Method::POST => client.post(target_url),
+        Method::PUT => client.put(target_url),
+        Method::DELETE => client.delete(target_url),
+        Method::PATCH => client.patch(target_url),
+        Method::HEAD => client.head(target_url),
+        Method::OPTIONS => client.request(reqwest::Method::OPTIONS, target_url),
         _ => {
             return (
                 StatusCode::METHOD_NOT_ALLOWED,
@@ -298,14 +539,21 @@ async fn cors_proxy_handler(
         "trailers",
         "transfer-encoding",
         "upgrade",
+        BASE_URL_HEADER,
+        EXPAND_HEADER,
     ];
 
+    // Content-Length is set by reqwest for the body that is actually sent
     for (key, value) in headers.iter() {
         let key_str = key.as_str().to_lowercase();
-        if !skip_headers.contains(&key_str.as_str()) {
+        if !skip_headers.contains(&key_str.as_str()) && key_str != "content-length" {
             if let Ok(header_name) = reqwest::header::HeaderName::from_bytes(key.as_str().as_bytes())
             {
-                if let Ok(header_value) = reqwest::header::HeaderValue::from_bytes(value.as_bytes())
+                let value = match value.to_str() {
+                    Ok(text) if expand => dynamic::expand(text).into_bytes(),
+                    _ => value.as_bytes().to_vec(),
+                };
+                if let Ok(header_value) = reqwest::header::HeaderValue::from_bytes(&value)
                 {
                     request_builder = request_builder.header(header_name, header_value);
                 }
@@ -332,6 +580,7 @@ async fn cors_proxy_handler(
                     .into_response();
             }
         };
+        let body_bytes = if expand { expand_body(body_bytes) } else { body_bytes };
         request_builder = request_builder.body(body_bytes);
     }
 
@@ -21,12 +21,14 @@ import {
   saveVariables,
   replaceVariables,
   replaceVariablesInKeyValues,
+  replaceDynamicVariables,
   mergeVariables,
   loadConfigHeaders,
   saveConfigHeaders,
   mergeConfigHeaders,
 } from './utils/variables';
-import { generateExampleFromSchema, hasBodyFields } from './utils/schema';
+import { fillPathExamples, generateExampleFromSchema, hasBodyFields, locationHeaders } from './utils/schema';
+import { type AuthBlock, type AuthCredentials, resolveAuth } from './utils/auth';
 import { useServiceWSStore } from './store/useWebSocketStore';
 import { io as sioConnect, type Socket as SioSocket } from 'socket.io-client';
 
@@ -76,12 +78,25 @@ export interface ApiEndpoint {
   description?: string;
   request?: SchemaBlock;
   response?: SchemaBlock;
+  /** Status of `response` when it is not 200, e.g. `201` */
+  status?: string;
+  /** Responses of other statuses, such as `404`, `4XX` or `default` */
+  responses?: StatusResponse[];
+  /** Media type of the request body when it is not JSON */
+  contentType?: string;
   events?: WsEvent[];
   sseEvents?: SseEvent[];
   auth?: SchemaBlock;
   connectHeaders?: SchemaBlock;
   categoryId?: string;
   categoryName?: string;
+  /** `auth` block of the endpoint, its category or the config, `auth none` excluded */
+  httpAuth?: AuthBlock;
+}
+
+export interface StatusResponse {
+  status: string;
+  schema: SchemaBlock;
 }
 
 export interface CategoryInfo {
@@ -101,17 +116,48 @@ export interface Field {
   name: string;
   fieldType: string;
   optional: boolean;
+  /** May be `null`, `String!` or `String | Null` */
+  nullable?: boolean;
   nested?: SchemaBlock;
+  items?: Field;
+  /** Key type of a `Map<K, V>`, the value type is `items` */
+  keyType?: string;
+  typeRef?: string;
+  /** Alternatives of a union such as `Click | View` */
+  variants?: Field[];
+  discriminator?: string;
+  enumValues?: (string | number | boolean)[];
+  constraints?: Constraints;
   mock?: string | number | boolean;
+  generator?: MockGenerator;
   example?: string | number | boolean;
   comment?: string;
   isParams?: boolean;
+  /** Sent in the path, a header or a cookie rather than the body */
+  location?: 'path' | 'header' | 'cookie';
+}
+
+export interface Constraints {
+  min?: number;
+  max?: number;
+  pattern?: string;
+  format?: string;
+  minLength?: number;
+  maxLength?: number;
+}
+
+export interface MockGenerator {
+  name: string;
+  args?: (string | number | boolean)[];
 }
 
 export interface ApiInfo {
   name: string;
   version: string;
   baseUrls: string[];
+  /** Names of the `env` blocks of the config, and the one in use */
+  envs?: string[];
+  activeEnv?: string;
   endpointCount: number;
   mockMode: boolean;
   corsMode: boolean;
@@ -127,6 +173,8 @@ function App() {
   const [corsMode, setCorsMode] = useState(false);
   const [baseUrls, setBaseUrls] = useState<string[]>([]);
   const [selectedBaseUrl, setSelectedBaseUrl] = useState<string>('');
+  const [envs, setEnvs] = useState<string[]>([]);
+  const [activeEnv, setActiveEnv] = useState<string | undefined>();
 
   const [request, setRequest] = useState<RequestState>({
     method: 'GET',
@@ -195,6 +243,40 @@ function App() {
     return expandedCategoryIds;
   }, [selectedEndpoint, categories, expandedCategoryIds, findCategoryPath]);
 
+  // `from_command` variables with a ttl are fetched again once their value expires
+  const [variablesExpiry, setVariablesExpiry] = useState<{ seconds: number } | null>(null);
+  const applyConfigVariables = useCallback((configVars: VariableDefinition[]) => {
+    const localVars = loadVariables();
+    setVariables(mergeVariables(configVars, localVars));
+    const expiries = configVars.flatMap((v) => (v.expiresIn === undefined ? [] : [v.expiresIn]));
+    setVariablesExpiry(expiries.length > 0 ? { seconds: Math.min(...expiries) } : null);
+  }, []);
+
+  const fetchVariables = useCallback(() => {
+    fetch('/api/variables')
+      .then((res) => res.json())
+      .then(applyConfigVariables)
+      .catch(console.error);
+  }, [applyConfigVariables]);
+
+  useEffect(() => {
+    if (!variablesExpiry) return;
+    const timer = setTimeout(fetchVariables, Math.max(variablesExpiry.seconds, 1) * 1000);
+    return () => clearTimeout(timer);
+  }, [variablesExpiry, fetchVariables]);
+
+  // The server runs the variable commands again
+  const handleRefreshVariables = useCallback(() => {
+    fetch('/api/variables/refresh', {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: '{}',
+    })
+      .then((res) => res.json())
+      .then(applyConfigVariables)
+      .catch(console.error);
+  }, [applyConfigVariables]);
+
   const fetchApiData = useCallback((restoreFromUrl = false) => {
     fetch('/api/info')
       .then((res) => res.json())
@@ -205,6 +287,8 @@ function App() {
         if (data.baseUrls.length > 0) {
           setSelectedBaseUrl(data.baseUrls[0]);
         }
+        setEnvs(data.envs ?? []);
+        setActiveEnv(data.activeEnv);
       })
       .catch(console.error);
 
@@ -288,14 +372,7 @@ function App() {
       })
       .catch(console.error);
 
-    fetch('/api/variables')
-      .then((res) => res.json())
-      .then((configVars: VariableDefinition[]) => {
-        const localVars = loadVariables();
-        const merged = mergeVariables(configVars, localVars);
-        setVariables(merged);
-      })
-      .catch(console.error);
+    fetchVariables();
 
     fetch('/api/headers')
       .then((res) => res.json())
@@ -305,7 +382,7 @@ function App() {
         setConfigHeaders(merged);
       })
       .catch(console.error);
-  }, []);
+  }, [fetchVariables]);
 
   // Initial data load
   useEffect(() => {
@@ -375,7 +452,10 @@ function App() {
 
       // For SIO endpoints, populate auth fields as params and connectHeaders as headers
       let initParams = paramsFields;
-      let initHeaders: KeyValue[] = [{ key: '', value: '', enabled: true }];
+      let initHeaders: KeyValue[] = [
+        ...locationHeaders(selectedEndpoint.request).map((h) => ({ ...h, enabled: true })),
+        { key: '', value: '', enabled: true },
+      ];
       if (isSocketio) {
         if (selectedEndpoint.auth?.fields) {
           initParams = [];
@@ -397,7 +477,7 @@ function App() {
 
       setRequest({
         method,
-        url: (isWebSocket || isSocketio) ? selectedEndpoint.path : getFullUrl(selectedEndpoint.path),
+        url: (isWebSocket || isSocketio) ? selectedEndpoint.path : getFullUrl(fillPathExamples(selectedEndpoint.path, selectedEndpoint.request)),
         params: initParams,
         headers: initHeaders,
         body,
@@ -442,7 +522,10 @@ function App() {
 
       // For SIO endpoints, populate auth fields as params and connectHeaders as headers
       let sioParams = paramsFields;
-      let sioHeaders: KeyValue[] = [{ key: '', value: '', enabled: true }];
+      let sioHeaders: KeyValue[] = [
+        ...locationHeaders(endpoint.request).map((h) => ({ ...h, enabled: true })),
+        { key: '', value: '', enabled: true },
+      ];
       if (endpoint.endpointType === 'socketio') {
         if (endpoint.auth?.fields) {
           sioParams = [];
@@ -464,7 +547,7 @@ function App() {
 
       setRequest({
         method,
-        url: (endpoint.endpointType === 'websocket' || endpoint.endpointType === 'socketio') ? endpoint.path : getFullUrl(endpoint.path),
+        url: (endpoint.endpointType === 'websocket' || endpoint.endpointType === 'socketio') ? endpoint.path : getFullUrl(fillPathExamples(endpoint.path, endpoint.request)),
         params: endpoint.endpointType === 'socketio' ? sioParams : paramsFields,
         headers: sioHeaders,
         body,
@@ -536,6 +619,26 @@ function App() {
     [selectedEndpoint, getFullUrl]
   );
 
+  // The server switches environment, its base URLs, variables and headers are then reloaded
+  const handleEnvChange = useCallback(
+    (name: string) => {
+      fetch('/api/env', {
+        method: 'PUT',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ name }),
+      })
+        .then((res) => res.json())
+        .then((data: ApiInfo) => {
+          fetchApiData(false);
+          if (data.baseUrls.length > 0) {
+            handleBaseUrlChange(data.baseUrls[0]);
+          }
+        })
+        .catch(console.error);
+    },
+    [fetchApiData, handleBaseUrlChange]
+  );
+
   const handleReset = useCallback(() => {
     setSelectedEndpoint(null);
     setSelectedCategory(null);
@@ -570,11 +673,25 @@ function App() {
   const handleSend = async (useMock: boolean = false) => {
     if (!request.url && !useMock) return;
 
+    // Built-ins such as {$uuid} are filled by the server for the requests it proxies, marked with
+    // X-Rqc-Expand, the browser fills them itself for mock requests and direct ones
+    const serverFills = !useMock && corsMode && request.method !== 'WS' && request.method !== 'SIO';
+    const fill = (text: string) => {
+      const replaced = replaceVariables(text, variables);
+      return serverFills ? replaced : replaceDynamicVariables(replaced);
+    };
+    const fillKeyValues = (items: KeyValue[]) =>
+      replaceVariablesInKeyValues(items, variables).map((item) =>
+        serverFills
+          ? item
+          : { ...item, key: replaceDynamicVariables(item.key), value: replaceDynamicVariables(item.value) }
+      );
+
     // Apply variable replacements
-    const resolvedUrl = replaceVariables(request.url, variables);
-    const resolvedParams = replaceVariablesInKeyValues(request.params, variables);
-    const resolvedHeaders = replaceVariablesInKeyValues(request.headers, variables);
-    const resolvedBody = replaceVariables(request.body, variables);
+    const resolvedUrl = fill(request.url);
+    const resolvedParams = fillKeyValues(request.params);
+    const resolvedHeaders = fillKeyValues(request.headers);
+    const resolvedBody = fill(request.body);
 
     if (request.method === 'WS') {
       if (wsConnected) {
@@ -648,9 +765,12 @@ function App() {
         }
 
         const headers: Record<string, string> = { 'Accept': 'text/event-stream' };
+        if (corsMode) {
+          headers['X-Rqc-Expand'] = '1';
+        }
         configHeaders.forEach((h) => {
           if (h.enabled && h.name) {
-            headers[h.name] = replaceVariables(h.value, variables);
+            headers[h.name] = fill(h.value);
           }
         });
         resolvedHeaders.forEach((h) => {
@@ -797,10 +917,16 @@ function App() {
     try {
       let targetUrl: string;
 
+      // Credentials of the auth block, request params and headers still override them
+      const auth: AuthCredentials = selectedEndpoint?.httpAuth
+        ? await resolveAuth(selectedEndpoint.httpAuth, fill)
+        : { headers: {}, params: [], cookies: [] };
+
       if (useMock && selectedEndpoint) {
         // Use mock endpoint with params (apply variable replacement to path)
-        const resolvedPath = replaceVariables(selectedEndpoint.path, variables);
+        const resolvedPath = fill(fillPathExamples(selectedEndpoint.path, selectedEndpoint.request));
         const mockUrl = new URL(`/mock${resolvedPath}`, window.location.origin);
+        auth.params.forEach(([key, value]) => mockUrl.searchParams.set(key, value));
         resolvedParams.forEach((p) => {
           if (p.enabled && p.key) {
             mockUrl.searchParams.append(p.key, p.value);
@@ -809,6 +935,7 @@ function App() {
         targetUrl = mockUrl.toString();
       } else {
         const url = new URL(resolvedUrl);
+        auth.params.forEach(([key, value]) => url.searchParams.set(key, value));
         resolvedParams.forEach((p) => {
           if (p.enabled && p.key) {
             url.searchParams.append(p.key, p.value);
@@ -829,16 +956,29 @@ function App() {
       // Add config headers first (can be overridden by request headers)
       configHeaders.forEach((h) => {
         if (h.enabled && h.name) {
-          headers[h.name] = replaceVariables(h.value, variables);
+          headers[h.name] = fill(h.value);
         }
       });
 
+      Object.assign(headers, auth.headers);
+
       // Add request-specific headers (override config headers)
       resolvedHeaders.forEach((h) => {
         if (h.enabled && h.key) {
           headers[h.key] = h.value;
         }
       });
+      if (auth.cookies.length > 0) {
+        headers['Cookie'] = [...auth.cookies, headers['Cookie']].filter(Boolean).join('; ');
+      }
+
+      // Requests the mock server does not mock are forwarded to the selected base URL
+      if (useMock && selectedBaseUrl) {
+        headers['X-Rqc-Base-Url'] = replaceVariables(selectedBaseUrl, variables);
+      }
+      if (serverFills) {
+        headers['X-Rqc-Expand'] = '1';
+      }
 
       const options: RequestInit = {
         method: request.method,
@@ -846,10 +986,27 @@ function App() {
       };
 
       if (['POST', 'PUT', 'PATCH'].includes(request.method) && resolvedBody) {
-        options.body = resolvedBody;
-        // Auto-add Content-Type for JSON if not specified
-        if (!headers['Content-Type'] && !headers['content-type']) {
-          headers['Content-Type'] = 'application/json';
+        const contentType = selectedEndpoint?.contentType;
+        const hasContentType = headers['Content-Type'] || headers['content-type'];
+        if (contentType === 'application/x-www-form-urlencoded' || contentType === 'multipart/form-data') {
+          // The body is edited as JSON and sent as a form
+          const fields = Object.entries(JSON.parse(resolvedBody) as Record<string, unknown>).map(
+            ([key, value]) => [key, typeof value === 'string' ? value : JSON.stringify(value)] as const
+          );
+          if (contentType === 'multipart/form-data') {
+            const form = new FormData();
+            fields.forEach(([key, value]) => form.append(key, value));
+            // The browser sets the multipart boundary
+            options.body = form;
+          } else {
+            options.body = new URLSearchParams([...fields]);
+          }
+        } else {
+          options.body = resolvedBody;
+          // Auto-add Content-Type if not specified
+          if (!hasContentType) {
+            headers['Content-Type'] = contentType ?? 'application/json';
+          }
         }
       }
 
@@ -932,6 +1089,9 @@ function App() {
             baseUrls={baseUrls}
             selectedBaseUrl={selectedBaseUrl}
             onBaseUrlChange={handleBaseUrlChange}
+            envs={envs}
+            activeEnv={activeEnv}
+            onEnvChange={handleEnvChange}
             variables={variables}
             variablesSelected={variablesSelected}
             onVariablesClick={handleVariablesClick}
@@ -953,6 +1113,7 @@ function App() {
                   onMockSend={mockMode && selectedEndpoint && selectedEndpoint.endpointType === 'http' ? handleMockSendRequest : undefined}
                   loading={loading || ((request.method === 'WS' || request.method === 'SIO' || request.method === 'SSE') && !wsConnected && !!(socket || sioSocket || sseAbort))}
                   wsConnected={wsConnected}
+                  authScheme={selectedEndpoint?.httpAuth?.scheme}
                 />
 
                 <PanelGroup orientation="horizontal" className="flex-1">
@@ -1026,6 +1187,7 @@ function App() {
                 onVariablesChange={handleVariablesChange}
                 configHeaders={configHeaders}
                 onConfigHeadersChange={handleConfigHeadersChange}
+                onRefreshVariables={handleRefreshVariables}
               />
             ) : (
               <WelcomePage endpointCount={endpoints.length} mockMode={mockMode} corsMode={corsMode} />
@@ -1051,6 +1213,8 @@ function App() {
             <SchemaPanel
               requestSchema={selectedEndpoint.request}
               responseSchema={selectedEndpoint.response}
+              responseStatus={selectedEndpoint.status}
+              statusResponses={selectedEndpoint.responses}
               wsEvents={selectedEndpoint.events}
               sseEvents={selectedEndpoint.sseEvents}
             />
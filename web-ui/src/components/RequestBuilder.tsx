@@ -1,7 +1,8 @@
 import { memo, useCallback } from 'react';
 import { Box, TextInput, Button, Group, Tooltip, Badge } from '@mantine/core';
-import { IconSend, IconLoader2, IconMask, IconPlugConnected, IconPlugConnectedX } from '@tabler/icons-react';
+import { IconSend, IconLoader2, IconMask, IconPlugConnected, IconPlugConnectedX, IconLock } from '@tabler/icons-react';
 import type { HttpMethod } from '../App';
+import { type AuthBlock, AUTH_LABELS } from '../utils/auth';
 
 interface RequestBuilderProps {
   method: HttpMethod;
@@ -11,6 +12,8 @@ interface RequestBuilderProps {
   onMockSend?: () => void;
   loading: boolean;
   wsConnected?: boolean;
+  /** Scheme of the endpoint's auth block, its credentials are added on send */
+  authScheme?: AuthBlock['scheme'];
 }
 
 const methodColors: Record<HttpMethod, string> = {
@@ -52,6 +55,7 @@ export const RequestBuilder = memo(function RequestBuilder({
   onMockSend,
   loading,
   wsConnected,
+  authScheme,
 }: RequestBuilderProps) {
   const isWs = method === 'WS' || method === 'SIO' || method === 'SSE';
 
@@ -90,6 +94,14 @@ export const RequestBuilder = memo(function RequestBuilder({
           styles={INPUT_STYLES}
         />
 
+        {authScheme && (
+          <Tooltip label="Credentials of the auth block are added when the request is sent">
+            <Badge size="lg" radius="md" color="gray" variant="light" leftSection={<IconLock size={12} />}>
+              {AUTH_LABELS[authScheme]}
+            </Badge>
+          </Tooltip>
+        )}
+
         <Button
           onClick={onSend}
           disabled={loading || !url}
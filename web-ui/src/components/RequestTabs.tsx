@@ -5,6 +5,7 @@ import {
   Table,
   Checkbox,
   TextInput,
+  Select,
   ActionIcon,
   Badge,
   Tooltip,
@@ -21,8 +22,8 @@ import {
   IconChevronRight,
 } from '@tabler/icons-react';
 import Editor from '@monaco-editor/react';
-import type { KeyValue, SchemaBlock, WsEvent, SseEvent } from '../App';
-import { generateExampleFromSchema, hasBodyFields } from '../utils/schema';
+import type { Field, KeyValue, SchemaBlock, WsEvent, SseEvent } from '../App';
+import { fieldTypeLabel, generateExampleFromSchema, hasBodyFields } from '../utils/schema';
 
 interface RequestTabsProps {
   params: KeyValue[];
@@ -73,8 +74,66 @@ const EDITOR_OPTIONS = {
   padding: { top: 12 },
 };
 
+/** HTML input types of the value input, by field type */
+const VALUE_INPUT_TYPES: Record<string, string> = {
+  integer: 'number',
+  number: 'number',
+  date: 'date',
+};
+
+/** Placeholders of the value input, by field type */
+const VALUE_PLACEHOLDERS: Record<string, string> = {
+  integer: '0',
+  number: '0.0',
+  datetime: '2024-01-01T08:30:00Z',
+  date: '2024-01-01',
+  uuid: '00000000-0000-0000-0000-000000000000',
+};
+
+interface ValueInputProps {
+  value: string;
+  field?: Field;
+  onChange: (value: string) => void;
+}
+
+/** Value of a param, with a widget that fits the type of its schema field */
+function ValueInput({ value, field, onChange }: ValueInputProps) {
+  const options =
+    field?.enumValues?.map(String) ??
+    (field?.fieldType === 'boolean' ? ['true', 'false'] : undefined);
+  if (options) {
+    return (
+      <Select
+        value={value || null}
+        onChange={(v) => onChange(v ?? '')}
+        data={options}
+        placeholder="Value"
+        variant="unstyled"
+        size="sm"
+        clearable
+        styles={INPUT_STYLES}
+      />
+    );
+  }
+
+  const type = field?.fieldType ?? '';
+  return (
+    <TextInput
+      value={value}
+      onChange={(e) => onChange(e.target.value)}
+      type={VALUE_INPUT_TYPES[type]}
+      step={type === 'number' ? 'any' : undefined}
+      placeholder={VALUE_PLACEHOLDERS[type] ?? 'Value'}
+      variant="unstyled"
+      size="sm"
+      styles={INPUT_STYLES}
+    />
+  );
+}
+
 interface KeyValueRowProps {
   item: KeyValue;
+  field?: Field;
   index: number;
   itemsLength: number;
   onUpdate: (index: number, field: keyof KeyValue, value: string | boolean) => void;
@@ -83,6 +142,7 @@ interface KeyValueRowProps {
 
 const KeyValueRow = memo(function KeyValueRow({
   item,
+  field,
   index,
   itemsLength,
   onUpdate,
@@ -109,13 +169,10 @@ const KeyValueRow = memo(function KeyValueRow({
         />
       </Table.Td>
       <Table.Td>
-        <TextInput
+        <ValueInput
           value={item.value}
-          onChange={(e) => onUpdate(index, 'value', e.target.value)}
-          placeholder="Value"
-          variant="unstyled"
-          size="sm"
-          styles={INPUT_STYLES}
+          field={field}
+          onChange={(value) => onUpdate(index, 'value', value)}
         />
       </Table.Td>
       <Table.Td>
@@ -137,10 +194,12 @@ const KeyValueRow = memo(function KeyValueRow({
 
 interface KeyValueTableProps {
   items: KeyValue[];
+  /** Schema fields of the keys, they pick the value widgets */
+  fields?: Field[];
   onChange: (items: KeyValue[]) => void;
 }
 
-const KeyValueTable = memo(function KeyValueTable({ items, onChange }: KeyValueTableProps) {
+const KeyValueTable = memo(function KeyValueTable({ items, fields, onChange }: KeyValueTableProps) {
   const handleUpdate = useCallback(
     (index: number, field: keyof KeyValue, value: string | boolean) => {
       const newItems = [...items];
@@ -215,6 +274,7 @@ const KeyValueTable = memo(function KeyValueTable({ items, onChange }: KeyValueT
           <KeyValueRow
             key={index}
             item={item}
+            field={fields?.find((f) => f.name === item.key)}
             index={index}
             itemsLength={items.length}
             onUpdate={handleUpdate}
@@ -244,6 +304,10 @@ export const RequestTabs = memo(function RequestTabs({
   const isWs = methodUpper === 'WS' || methodUpper === 'SIO';
   const isSse = methodUpper === 'SSE';
   const isSio = methodUpper === 'SIO';
+  const paramFields = useMemo(
+    () => requestSchema?.fields.filter((f) => f.isParams),
+    [requestSchema]
+  );
 
   // WebSocket event editing state
   const [selectedWsEvent, setSelectedWsEvent] = useState<WsEvent | null>(null);
@@ -403,7 +467,7 @@ export const RequestTabs = memo(function RequestTabs({
       {(!isWs || isSse) && !isSio && (
         <>
           <Tabs.Panel value="params" className="overflow-auto bg-bg-primary">
-            <KeyValueTable items={params} onChange={onParamsChange} />
+            <KeyValueTable items={params} fields={paramFields} onChange={onParamsChange} />
           </Tabs.Panel>
 
           <Tabs.Panel value="headers" className="overflow-auto bg-bg-primary">
@@ -584,7 +648,7 @@ export const RequestTabs = memo(function RequestTabs({
                         <Text size="sm" fw={500}>{field.name}</Text>
                       </Table.Td>
                       <Table.Td>
-                        <Badge size="xs" variant="light" color="gray">{field.fieldType}</Badge>
+                        <Badge size="xs" variant="light" color="gray">{fieldTypeLabel(field)}</Badge>
                       </Table.Td>
                     </Table.Tr>
                   ))}
@@ -15,11 +15,24 @@ import {
   IconSchema,
   IconMessage,
 } from '@tabler/icons-react';
-import type { SchemaBlock, Field, WsEvent, SseEvent } from '../App';
+import type {
+  SchemaBlock,
+  Field,
+  Constraints,
+  MockGenerator,
+  WsEvent,
+  SseEvent,
+  StatusResponse,
+} from '../App';
+import { fieldSchema, fieldTypeLabel } from '../utils/schema';
 
 interface SchemaPanelProps {
   requestSchema?: SchemaBlock;
   responseSchema?: SchemaBlock;
+  /** Status of the main response when it is not 200 */
+  responseStatus?: string;
+  /** Responses of other statuses, such as `404` or `default` */
+  statusResponses?: StatusResponse[];
   wsEvents?: WsEvent[];
   sseEvents?: SseEvent[];
 }
@@ -27,11 +40,45 @@ interface SchemaPanelProps {
 const TYPE_COLORS: Record<string, string> = {
   string: 'blue',
   number: 'green',
+  integer: 'green',
   boolean: 'yellow',
+  datetime: 'cyan',
+  date: 'cyan',
+  uuid: 'indigo',
+  file: 'pink',
   array: 'violet',
   object: 'orange',
+  map: 'orange',
+  union: 'grape',
 };
 
+/** `@min(0) @max(150)` reads `0..150`, `@length(1, 64)` reads `length 1..64` */
+function formatConstraints(constraints: Constraints): string[] {
+  const parts: string[] = [];
+  const range = (min?: number, max?: number) =>
+    min === max ? `${min}` : `${min ?? ''}..${max ?? ''}`;
+  if (constraints.min !== undefined || constraints.max !== undefined) {
+    parts.push(range(constraints.min, constraints.max));
+  }
+  if (constraints.minLength !== undefined || constraints.maxLength !== undefined) {
+    parts.push(`length ${range(constraints.minLength, constraints.maxLength)}`);
+  }
+  if (constraints.format) {
+    parts.push(constraints.format);
+  }
+  if (constraints.pattern) {
+    parts.push(`/${constraints.pattern}/`);
+  }
+  return parts;
+}
+
+function formatGenerator(generator: MockGenerator): string {
+  if (!generator.args || generator.args.length === 0) {
+    return generator.name;
+  }
+  return `${generator.name}(${generator.args.map((a) => JSON.stringify(a)).join(', ')})`;
+}
+
 interface FieldRowProps {
   field: Field;
   depth: number;
@@ -39,7 +86,16 @@ interface FieldRowProps {
 
 const FieldRow = memo(function FieldRow({ field, depth }: FieldRowProps) {
   const [expanded, setExpanded] = useState(true);
-  const hasNested = !!field.nested && field.nested.fields.length > 0;
+  // The variants of a union are listed under it, named after their type
+  const nested = field.variants
+    ? {
+        fields: field.variants.map((v, i) => ({ ...v, name: v.typeRef ?? `#${i + 1}` })),
+        optional: false,
+      }
+    : fieldSchema(field);
+  const hasNested = !!nested && nested.fields.length > 0;
+  // `[Status]` lists the values of its items
+  const enumValues = field.enumValues ?? field.items?.enumValues;
 
   const toggleExpanded = useCallback(() => {
     setExpanded((prev) => !prev);
@@ -47,10 +103,16 @@ const FieldRow = memo(function FieldRow({ field, depth }: FieldRowProps) {
 
   const typeColor = TYPE_COLORS[field.fieldType] || 'gray';
 
+  const constraints = field.constraints ? formatConstraints(field.constraints) : [];
+
   const hasMeta =
     field.comment ||
+    field.discriminator ||
+    constraints.length > 0 ||
+    enumValues !== undefined ||
     field.example !== undefined ||
-    field.mock !== undefined;
+    field.mock !== undefined ||
+    field.generator !== undefined;
 
   return (
     <>
@@ -81,7 +143,7 @@ const FieldRow = memo(function FieldRow({ field, depth }: FieldRowProps) {
           </Text>
 
           <Badge size="xs" variant="light" color={typeColor}>
-            {field.fieldType}
+            {fieldTypeLabel(field)}
           </Badge>
 
           {!field.optional && (
@@ -94,11 +156,21 @@ const FieldRow = memo(function FieldRow({ field, depth }: FieldRowProps) {
               optional
             </Badge>
           )}
+          {field.nullable && (
+            <Badge size="xs" variant="outline" color="grape">
+              nullable
+            </Badge>
+          )}
           {field.isParams && (
             <Badge size="xs" variant="light" color="cyan">
               query
             </Badge>
           )}
+          {field.location && (
+            <Badge size="xs" variant="light" color="cyan">
+              {field.location}
+            </Badge>
+          )}
         </div>
 
         {/* Second line: comment / example / mock */}
@@ -110,6 +182,32 @@ const FieldRow = memo(function FieldRow({ field, depth }: FieldRowProps) {
               </Text>
             )}
             <Group gap="sm" mt={field.comment ? 2 : 0} wrap="wrap">
+              {constraints.length > 0 && (
+                <Group gap={4}>
+                  <Text size="xs" c="dimmed">rules:</Text>
+                  {constraints.map((rule) => (
+                    <Code key={rule} style={{ fontSize: 11 }}>
+                      {rule}
+                    </Code>
+                  ))}
+                </Group>
+              )}
+              {field.discriminator && (
+                <Group gap={4}>
+                  <Text size="xs" c="dimmed">discriminator:</Text>
+                  <Code style={{ fontSize: 11 }}>{field.discriminator}</Code>
+                </Group>
+              )}
+              {enumValues !== undefined && (
+                <Group gap={4}>
+                  <Text size="xs" c="dimmed">one of:</Text>
+                  {enumValues.map((value) => (
+                    <Code key={String(value)} style={{ fontSize: 11 }}>
+                      {JSON.stringify(value)}
+                    </Code>
+                  ))}
+                </Group>
+              )}
               {field.example !== undefined && (
                 <Group gap={4}>
                   <Text size="xs" c="dimmed">example:</Text>
@@ -122,6 +220,12 @@ const FieldRow = memo(function FieldRow({ field, depth }: FieldRowProps) {
                   <Code style={{ fontSize: 11 }}>{String(field.mock)}</Code>
                 </Group>
               )}
+              {field.generator !== undefined && (
+                <Group gap={4}>
+                  <Text size="xs" c="dimmed">mock:</Text>
+                  <Code style={{ fontSize: 11 }}>{formatGenerator(field.generator)}</Code>
+                </Group>
+              )}
             </Group>
           </div>
         )}
@@ -130,7 +234,7 @@ const FieldRow = memo(function FieldRow({ field, depth }: FieldRowProps) {
       {/* Nested fields */}
       {hasNested && expanded && (
         <Box style={{ borderLeft: '2px solid var(--color-border)', marginLeft: 12 + depth * 16 + 6 }}>
-          <FieldList schema={field.nested!} depth={0} />
+          <FieldList schema={nested!} depth={0} />
         </Box>
       )}
     </>
@@ -282,12 +386,15 @@ const SseEventSchemaView = memo(function SseEventSchemaView({
 export const SchemaPanel = memo(function SchemaPanel({
   requestSchema,
   responseSchema,
+  responseStatus,
+  statusResponses,
   wsEvents,
   sseEvents,
 }: SchemaPanelProps) {
   const hasAnySchema =
     (requestSchema && requestSchema.fields.length > 0) ||
-    (responseSchema && responseSchema.fields.length > 0);
+    (responseSchema && responseSchema.fields.length > 0) ||
+    (statusResponses && statusResponses.length > 0);
   const hasWsEvents = wsEvents && wsEvents.length > 0;
   const hasSseEvents = sseEvents && sseEvents.length > 0;
 
@@ -328,10 +435,18 @@ export const SchemaPanel = memo(function SchemaPanel({
         color="blue"
       />
       <SchemaBlockView
-        title="Response"
+        title={responseStatus ? `Response ${responseStatus}` : 'Response'}
         schema={responseSchema}
         color="green"
       />
+      {statusResponses?.map((response) => (
+        <SchemaBlockView
+          key={response.status}
+          title={`Response ${response.status}`}
+          schema={response.schema}
+          color={response.status.startsWith('2') ? 'green' : 'red'}
+        />
+      ))}
     </Box>
   );
 });
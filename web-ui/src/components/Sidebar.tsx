@@ -40,11 +40,33 @@ interface SidebarProps {
   baseUrls: string[];
   selectedBaseUrl: string;
   onBaseUrlChange: (url: string) => void;
+  /** Names of the `env` blocks of the config */
+  envs: string[];
+  activeEnv?: string;
+  onEnvChange: (name: string) => void;
   variables: Variable[];
   variablesSelected?: boolean;
   onVariablesClick: () => void;
 }
 
+const SELECT_STYLES = {
+  input: {
+    backgroundColor: 'rgba(0, 0, 0, 0.3)',
+    border: '1px solid rgba(255, 255, 255, 0.1)',
+    color: '#fff',
+    fontFamily: 'var(--mantine-font-family-monospace)',
+    fontSize: 11,
+  },
+  dropdown: {
+    backgroundColor: '#252525',
+    border: '1px solid rgba(255, 255, 255, 0.1)',
+  },
+  option: {
+    fontSize: 11,
+    fontFamily: 'var(--mantine-font-family-monospace)',
+  },
+};
+
 const methodStyles: Record<string, { bg: string; text: string }> = {
   GET: { bg: 'rgba(59, 130, 246, 0.15)', text: '#60a5fa' },
   POST: { bg: 'rgba(34, 197, 94, 0.15)', text: '#4ade80' },
@@ -1050,6 +1072,9 @@ export const Sidebar = memo(function Sidebar({
   baseUrls,
   selectedBaseUrl,
   onBaseUrlChange,
+  envs,
+  activeEnv,
+  onEnvChange,
   variables,
   variablesSelected,
   onVariablesClick,
@@ -1139,7 +1164,7 @@ export const Sidebar = memo(function Sidebar({
       <ScrollArea style={{ flex: 1 }}>
         <Box style={{ padding: 12 }}>
           {/* Environment Selector */}
-          {baseUrls.length > 1 && (
+          {(envs.length > 0 || baseUrls.length > 1) && (
             <Box
               style={{
                 padding: 12,
@@ -1163,30 +1188,27 @@ export const Sidebar = memo(function Sidebar({
                   Environment
                 </Text>
               </Group>
-              <Select
-                value={selectedBaseUrl}
-                onChange={(value) => value && onBaseUrlChange(value)}
-                data={baseUrls.map((url) => ({ value: url, label: url }))}
-                size="xs"
-                allowDeselect={false}
-                styles={{
-                  input: {
-                    backgroundColor: 'rgba(0, 0, 0, 0.3)',
-                    border: '1px solid rgba(255, 255, 255, 0.1)',
-                    color: '#fff',
-                    fontFamily: 'var(--mantine-font-family-monospace)',
-                    fontSize: 11,
-                  },
-                  dropdown: {
-                    backgroundColor: '#252525',
-                    border: '1px solid rgba(255, 255, 255, 0.1)',
-                  },
-                  option: {
-                    fontSize: 11,
-                    fontFamily: 'var(--mantine-font-family-monospace)',
-                  },
-                }}
-              />
+              {envs.length > 0 && (
+                <Select
+                  value={activeEnv ?? null}
+                  onChange={(value) => value && onEnvChange(value)}
+                  data={envs.map((name) => ({ value: name, label: name }))}
+                  size="xs"
+                  allowDeselect={false}
+                  styles={SELECT_STYLES}
+                  style={{ marginBottom: baseUrls.length > 1 ? 8 : 0 }}
+                />
+              )}
+              {baseUrls.length > 1 && (
+                <Select
+                  value={selectedBaseUrl}
+                  onChange={(value) => value && onBaseUrlChange(value)}
+                  data={baseUrls.map((url) => ({ value: url, label: url }))}
+                  size="xs"
+                  allowDeselect={false}
+                  styles={SELECT_STYLES}
+                />
+              )}
             </Box>
           )}
 
@@ -3,13 +3,14 @@ import {
   Text,
   Table,
   TextInput,
+  PasswordInput,
   Checkbox,
   ActionIcon,
   Badge,
   Tooltip,
   Group,
 } from '@mantine/core';
-import { IconVariable, IconX, IconKey } from '@tabler/icons-react';
+import { IconVariable, IconX, IconKey, IconRefresh } from '@tabler/icons-react';
 import type { Variable, ConfigHeader } from '../utils/variables';
 
 interface VariablesPageProps {
@@ -17,13 +18,24 @@ interface VariablesPageProps {
   onVariablesChange: (variables: Variable[]) => void;
   configHeaders: ConfigHeader[];
   onConfigHeadersChange: (headers: ConfigHeader[]) => void;
+  /** Run the `from_command` variables again */
+  onRefreshVariables: () => void;
 }
 
+const VALUE_INPUT_STYLES = {
+  input: {
+    backgroundColor: 'var(--color-bg-primary)',
+    border: '1px solid var(--color-border)',
+    color: 'var(--color-text-primary)',
+  },
+};
+
 export function VariablesPage({
   variables,
   onVariablesChange,
   configHeaders,
   onConfigHeadersChange,
+  onRefreshVariables,
 }: VariablesPageProps) {
   const updateVariable = (
     index: number,
@@ -60,6 +72,7 @@ export function VariablesPage({
   };
 
   const configVariables = variables.filter((v) => v.isFromConfig && v.name);
+  const hasCommands = configVariables.some((v) => v.command);
   const userVariables = variables.filter((v) => !v.isFromConfig);
   const activeCount = variables.filter((v) => v.name && v.enabled).length;
   const activeHeaderCount = configHeaders.filter((h) => h.name && h.enabled).length;
@@ -118,6 +131,13 @@ export function VariablesPage({
               <Badge size="xs" variant="light" color="blue">
                 from .rqc
               </Badge>
+              {hasCommands && (
+                <Tooltip label="Run variable commands again">
+                  <ActionIcon variant="subtle" color="gray" size="sm" onClick={onRefreshVariables}>
+                    <IconRefresh size={14} />
+                  </ActionIcon>
+                </Tooltip>
+              )}
             </Group>
             <Box
               style={{
@@ -171,27 +191,51 @@ export function VariablesPage({
                           </Text>
                         </Table.Td>
                         <Table.Td>
-                          <TextInput
-                            value={variable.value}
-                            onChange={(e) =>
-                              updateVariable(realIndex, 'value', e.target.value)
-                            }
-                            placeholder="Enter value..."
-                            variant="filled"
-                            size="sm"
-                            styles={{
-                              input: {
-                                backgroundColor: 'var(--color-bg-primary)',
-                                border: '1px solid var(--color-border)',
-                                color: 'var(--color-text-primary)',
-                              },
-                            }}
-                          />
+                          {variable.isSecret ? (
+                            // Read from the environment by the server, hidden unless revealed
+                            <PasswordInput
+                              value={variable.value}
+                              onChange={(e) =>
+                                updateVariable(realIndex, 'value', e.target.value)
+                              }
+                              variant="filled"
+                              size="sm"
+                              styles={VALUE_INPUT_STYLES}
+                            />
+                          ) : (
+                            <TextInput
+                              value={variable.value}
+                              onChange={(e) =>
+                                updateVariable(realIndex, 'value', e.target.value)
+                              }
+                              placeholder="Enter value..."
+                              variant="filled"
+                              size="sm"
+                              styles={VALUE_INPUT_STYLES}
+                            />
+                          )}
+                          {variable.error && (
+                            <Text size="xs" c="red" mt={4}>
+                              {variable.error}
+                            </Text>
+                          )}
                         </Table.Td>
                         <Table.Td>
-                          <Badge size="xs" variant="light" color="gray">
-                            String
-                          </Badge>
+                          {variable.command ? (
+                            <Tooltip label={variable.command}>
+                              <Badge size="xs" variant="light" color="grape">
+                                Command
+                              </Badge>
+                            </Tooltip>
+                          ) : variable.isSecret ? (
+                            <Badge size="xs" variant="light" color="red">
+                              Secret
+                            </Badge>
+                          ) : (
+                            <Badge size="xs" variant="light" color="gray">
+                              String
+                            </Badge>
+                          )}
                         </Table.Td>
                       </Table.Tr>
                     );
@@ -259,22 +303,28 @@ export function VariablesPage({
                         </Text>
                       </Table.Td>
                       <Table.Td>
-                        <TextInput
-                          value={header.value}
-                          onChange={(e) =>
-                            updateHeader(idx, 'value', e.target.value)
-                          }
-                          placeholder="Enter header value..."
-                          variant="filled"
-                          size="sm"
-                          styles={{
-                            input: {
-                              backgroundColor: 'var(--color-bg-primary)',
-                              border: '1px solid var(--color-border)',
-                              color: 'var(--color-text-primary)',
-                            },
-                          }}
-                        />
+                        {header.isSecret ? (
+                          <PasswordInput
+                            value={header.value}
+                            onChange={(e) =>
+                              updateHeader(idx, 'value', e.target.value)
+                            }
+                            variant="filled"
+                            size="sm"
+                            styles={VALUE_INPUT_STYLES}
+                          />
+                        ) : (
+                          <TextInput
+                            value={header.value}
+                            onChange={(e) =>
+                              updateHeader(idx, 'value', e.target.value)
+                            }
+                            placeholder="Enter header value..."
+                            variant="filled"
+                            size="sm"
+                            styles={VALUE_INPUT_STYLES}
+                          />
+                        )}
                       </Table.Td>
                     </Table.Tr>
                   ))}
@@ -282,7 +332,8 @@ export function VariablesPage({
               </Table>
             </Box>
             <Text size="xs" c="dimmed" mt="xs">
-              These headers will be automatically added to all API requests. Values support variable syntax.
+              These headers will be automatically added to all API requests. Values support variable syntax,
+              secrets read from the environment or a .env file are masked and never saved.
             </Text>
           </Box>
         )}
@@ -0,0 +1,83 @@
+import { replaceDynamicVariables } from './variables';
+
+/** The `auth` block that applies to an endpoint, its property values may use variables */
+export interface AuthBlock {
+  scheme: 'bearer' | 'basic' | 'apiKey' | 'oauth2';
+  properties?: Record<string, string>;
+}
+
+export const AUTH_LABELS: Record<AuthBlock['scheme'], string> = {
+  bearer: 'Bearer',
+  basic: 'Basic',
+  apiKey: 'API Key',
+  oauth2: 'OAuth2',
+};
+
+export interface AuthCredentials {
+  headers: Record<string, string>;
+  params: [string, string][];
+  cookies: string[];
+}
+
+function basicCredentials(username: string, password: string): string {
+  const bytes = new TextEncoder().encode(`${username}:${password}`);
+  return btoa(String.fromCharCode(...bytes));
+}
+
+/**
+ * Credentials to send for an auth block, `fill` replaces the variables of its properties.
+ * OAuth2 tokens are fetched by the server, which caches them and refreshes them before they
+ * expire, so the token endpoint does not need CORS headers
+ */
+export async function resolveAuth(
+  auth: AuthBlock,
+  fill: (text: string) => string
+): Promise<AuthCredentials> {
+  const properties: Record<string, string> = {};
+  for (const [name, value] of Object.entries(auth.properties ?? {})) {
+    properties[name] = fill(value);
+  }
+
+  const credentials: AuthCredentials = { headers: {}, params: [], cookies: [] };
+  switch (auth.scheme) {
+    case 'bearer':
+      credentials.headers['Authorization'] = `Bearer ${properties.token ?? ''}`;
+      break;
+    case 'basic':
+      credentials.headers['Authorization'] = `Basic ${basicCredentials(
+        properties.username ?? '',
+        properties.password ?? ''
+      )}`;
+      break;
+    case 'apiKey': {
+      const name = properties.name ?? '';
+      const value = properties.value ?? '';
+      if (properties.in === 'query') {
+        credentials.params.push([name, value]);
+      } else if (properties.in === 'cookie') {
+        credentials.cookies.push(`${name}=${value}`);
+      } else {
+        credentials.headers[name] = value;
+      }
+      break;
+    }
+    case 'oauth2': {
+      // The server does not fill the built-ins of token requests
+      for (const name of Object.keys(properties)) {
+        properties[name] = replaceDynamicVariables(properties[name]);
+      }
+      const res = await fetch('/api/auth/token', {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify(properties),
+      });
+      const data = await res.json().catch(() => ({}));
+      if (!res.ok) {
+        throw new Error(data.error ?? `Token request failed with ${res.status}`);
+      }
+      credentials.headers['Authorization'] = `Bearer ${data.accessToken}`;
+      break;
+    }
+  }
+  return credentials;
+}
@@ -4,16 +4,33 @@ export function generateExampleValue(field: Field): unknown {
   if (field.example !== undefined) {
     return field.example;
   }
+  if (field.enumValues && field.enumValues.length > 0) {
+    return field.enumValues[0];
+  }
 
   // Default values based on type
   switch (field.fieldType) {
     case 'string':
       return '';
     case 'number':
+    case 'integer':
       return 0;
     case 'boolean':
       return false;
+    case 'datetime':
+      return new Date().toISOString().replace(/\.\d{3}Z$/, 'Z');
+    case 'date':
+      return new Date().toISOString().slice(0, 10);
+    case 'uuid':
+      return '00000000-0000-0000-0000-000000000000';
+    case 'map':
+      return {};
+    case 'union':
+      return field.variants?.length ? generateExampleValue(field.variants[0]) : null;
     case 'array':
+      if (field.items) {
+        return [generateExampleValue(field.items)];
+      }
       if (field.nested) {
         return [generateExampleFromSchema(field.nested)];
       }
@@ -32,8 +49,8 @@ export function generateExampleFromSchema(schema: SchemaBlock): Record<string, u
   const result: Record<string, unknown> = {};
 
   for (const field of schema.fields) {
-    // Skip params fields as they are query parameters, not body fields
-    if (field.isParams) {
+    // Skip query, path, header and cookie parameters, they are not body fields
+    if (field.isParams || field.location) {
       continue;
     }
 
@@ -53,5 +70,56 @@ export function generateExampleFromSchema(schema: SchemaBlock): Record<string, u
 
 export function hasBodyFields(schema?: SchemaBlock): boolean {
   if (!schema?.fields) return false;
-  return schema.fields.some((f) => !f.isParams);
+  return schema.fields.some((f) => !f.isParams && !f.location);
+}
+
+/** `@header` fields as request headers, `@cookie` fields joined into one `Cookie` header */
+export function locationHeaders(schema?: SchemaBlock): { key: string; value: string }[] {
+  const fields = schema?.fields ?? [];
+  const text = (field: Field) => (field.example !== undefined ? String(field.example) : '');
+  const headers = fields
+    .filter((f) => f.location === 'header')
+    .map((f) => ({ key: f.name, value: text(f) }));
+  const cookies = fields
+    .filter((f) => f.location === 'cookie')
+    .map((f) => `${f.name}=${text(f)}`);
+  if (cookies.length > 0) {
+    headers.push({ key: 'Cookie', value: cookies.join('; ') });
+  }
+  return headers;
+}
+
+/** Fill the `{id}` placeholders of a path with the examples of its `@path` fields */
+export function fillPathExamples(path: string, schema?: SchemaBlock): string {
+  return (schema?.fields ?? [])
+    .filter((f) => f.location === 'path' && f.example !== undefined)
+    .reduce((filled, f) => filled.split(`{${f.name}}`).join(String(f.example)), path);
+}
+
+/** Type shown for a field, typed arrays read like `string[]` or `object[][]` */
+export function fieldTypeLabel(field: Field): string {
+  const label = baseTypeLabel(field);
+  return field.nullable ? `${label} | null` : label;
+}
+
+function baseTypeLabel(field: Field): string {
+  if (field.variants) {
+    return field.variants.map(fieldTypeLabel).join(' | ');
+  }
+  if (field.fieldType === 'map') {
+    const value = field.items ? fieldTypeLabel(field.items) : 'any';
+    return `map<${field.keyType ?? 'string'}, ${value}>`;
+  }
+  if (field.items) {
+    return `${fieldTypeLabel(field.items)}[]`;
+  }
+  if (field.enumValues && !field.typeRef) {
+    return 'enum';
+  }
+  return field.typeRef ?? field.fieldType;
+}
+
+/** Fields of an object, or of the objects inside a typed array such as `[{ id Number }]` */
+export function fieldSchema(field: Field): SchemaBlock | undefined {
+  return field.items ? fieldSchema(field.items) : field.nested;
 }
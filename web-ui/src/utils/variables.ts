@@ -3,17 +3,34 @@ export interface Variable {
   value: string;
   enabled: boolean;
   isFromConfig?: boolean;
+  /** Value read from the environment or a .env file by the server, masked and never saved */
+  isSecret?: boolean;
+  /** Command printing the value, run by the server */
+  command?: string;
+  /** Why the command gave no value */
+  error?: string;
 }
 
 export interface VariableDefinition {
   name: string;
   varType: string;
   defaultValue?: string;
+  /** Environment variable the default is read from */
+  env?: string;
+  command?: string;
+  /** Seconds the command output is reused for */
+  ttl?: number;
+  secret?: boolean;
+  error?: string;
+  /** Seconds before the command output is stale */
+  expiresIn?: number;
 }
 
 export interface HeaderDefinition {
   name: string;
   defaultValue?: string;
+  env?: string;
+  secret?: boolean;
 }
 
 export interface ConfigHeader {
@@ -21,6 +38,7 @@ export interface ConfigHeader {
   value: string;
   enabled: boolean;
   isFromConfig: boolean;
+  isSecret?: boolean;
 }
 
 const HEADERS_STORAGE_KEY = 'reqcraft_config_headers';
@@ -41,11 +59,12 @@ export function loadConfigHeaders(): ConfigHeader[] {
 }
 
 /**
- * Save config headers to localStorage
+ * Save config headers to localStorage, without the values of secret headers
  */
 export function saveConfigHeaders(headers: ConfigHeader[]): void {
   try {
-    localStorage.setItem(HEADERS_STORAGE_KEY, JSON.stringify(headers));
+    const stored = headers.map((h) => (h.isSecret ? { ...h, value: '' } : h));
+    localStorage.setItem(HEADERS_STORAGE_KEY, JSON.stringify(stored));
   } catch (e) {
     console.error('Failed to save config headers:', e);
   }
@@ -63,10 +82,20 @@ export function mergeConfigHeaders(
 
   for (const configHeader of configHeaders) {
     const saved = savedMap.get(configHeader.name);
-    if (saved) {
+    if (configHeader.secret) {
+      // Secrets always come from the server, only whether they are sent is kept
+      result.push({
+        name: configHeader.name,
+        value: configHeader.defaultValue || '',
+        enabled: saved ? saved.enabled : true,
+        isFromConfig: true,
+        isSecret: true,
+      });
+    } else if (saved) {
       result.push({
         ...saved,
         isFromConfig: true,
+        isSecret: false,
       });
     } else {
       result.push({
@@ -96,6 +125,32 @@ export function replaceVariables(text: string, variables: Variable[]): string {
   });
 }
 
+const DYNAMIC_VARIABLE_REGEX = /\{\$(\w+)\}/g;
+
+/**
+ * Fill the built-in dynamic variables, {$uuid}, {$timestamp}, {$randomInt} and {$isoDate},
+ * every reference gets a fresh value. The server fills them the same way for the requests it
+ * proxies with X-Rqc-Expand, this is used for direct and mock requests
+ */
+export function replaceDynamicVariables(text: string): string {
+  if (!text) return text;
+
+  return text.replace(DYNAMIC_VARIABLE_REGEX, (match, name) => {
+    switch (name) {
+      case 'uuid':
+        return crypto.randomUUID();
+      case 'timestamp':
+        return String(Math.floor(Date.now() / 1000));
+      case 'randomInt':
+        return String(Math.floor(Math.random() * 1001));
+      case 'isoDate':
+        return new Date().toISOString();
+      default:
+        return match;
+    }
+  });
+}
+
 /**
  * Replace variables in a key-value array
  */
@@ -196,22 +251,27 @@ export function mergeVariables(
   // Add config variables first
   for (const configVar of configVars) {
     const userVar = userVarMap.get(configVar.name);
-    if (userVar) {
+    if (userVar && !configVar.secret) {
       // User has overridden this variable
       result.push({
         ...userVar,
         isFromConfig: true,
+        command: configVar.command,
+        error: configVar.error,
       });
-      userVarMap.delete(configVar.name);
     } else {
-      // Use config default
+      // Use config default, secrets always come from the server
       result.push({
         name: configVar.name,
         value: configVar.defaultValue || '',
         enabled: true,
         isFromConfig: true,
+        isSecret: configVar.secret,
+        command: configVar.command,
+        error: configVar.error,
       });
     }
+    userVarMap.delete(configVar.name);
   }
 
   // Add remaining user variables